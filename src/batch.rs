@@ -0,0 +1,387 @@
+//! Batch runs: questions for `pokidex batch`, images for
+//! `pokidex identify-dir`.
+
+use crate::citation::Source;
+use crate::error::{ErrorSummary, PokidexError, Result};
+use crate::image_input::ImageInput;
+use crate::rag::{RAGEngine, ToolCall};
+use crate::verify::Mismatch;
+use futures::stream::{self, Stream, StreamExt};
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tokio::time::{self, MissedTickBehavior};
+
+/// One question from a batch file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchQuestion {
+    /// Caller-supplied ID from a JSONL file, copied into the result.
+    pub id: Option<Value>,
+    pub question: String,
+}
+
+/// One line of batch output. Exactly one of `answer` and `error` is set.
+#[derive(Debug, Serialize)]
+pub struct BatchResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<Value>,
+    pub question: String,
+    pub answer: Option<String>,
+    pub tool_calls: Vec<ToolCall>,
+    pub sources: Vec<Source>,
+    pub mismatches: Vec<Mismatch>,
+    pub latency_ms: u128,
+    #[serde(serialize_with = "serialize_error")]
+    pub error: Option<PokidexError>,
+}
+
+fn serialize_error<S: Serializer>(
+    error: &Option<PokidexError>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    error.as_ref().map(ErrorSummary::from).serialize(serializer)
+}
+
+/// Reads questions from `path`. `.jsonl`/`.ndjson` files hold one
+/// `{"question": ..., "id": ...}` object per line; anything else is plain
+/// text with one question per line. Blank lines and `#` comments are skipped.
+pub fn read_questions(path: &Path) -> Result<Vec<BatchQuestion>> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| PokidexError::InvalidRequest(format!("{}: {}", path.display(), e)))?;
+    let jsonl = matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("jsonl" | "ndjson")
+    );
+    parse_questions(&text, jsonl)
+}
+
+pub(crate) fn parse_questions(text: &str, jsonl: bool) -> Result<Vec<BatchQuestion>> {
+    let mut questions = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if !jsonl {
+            questions.push(BatchQuestion {
+                id: None,
+                question: line.to_string(),
+            });
+            continue;
+        }
+
+        let invalid =
+            |reason: &str| PokidexError::InvalidRequest(format!("line {}: {}", i + 1, reason));
+        let json: Value = serde_json::from_str(line).map_err(|e| invalid(&e.to_string()))?;
+        let question = json
+            .get("question")
+            .and_then(Value::as_str)
+            .filter(|q| !q.trim().is_empty())
+            .ok_or_else(|| invalid("missing \"question\""))?;
+        questions.push(BatchQuestion {
+            id: json.get("id").cloned(),
+            question: question.trim().to_string(),
+        });
+    }
+    Ok(questions)
+}
+
+/// Answers `questions` with at most `concurrency` in flight. Results come
+/// back in input order, each as soon as it and everything before it is done.
+pub fn run_batch<'a>(
+    engine: &'a RAGEngine,
+    questions: &'a [BatchQuestion],
+    concurrency: usize,
+) -> impl Stream<Item = BatchResult> + 'a {
+    stream::iter(questions)
+        .map(move |q| async move {
+            let started = Instant::now();
+            let result = engine.process_query(&q.question).await;
+            let latency_ms = started.elapsed().as_millis();
+            match result {
+                Ok(answer) => BatchResult {
+                    id: q.id.clone(),
+                    question: q.question.clone(),
+                    answer: Some(answer.text),
+                    tool_calls: answer.tool_calls,
+                    sources: answer.sources,
+                    mismatches: answer.mismatches,
+                    latency_ms,
+                    error: None,
+                },
+                Err(e) => BatchResult {
+                    id: q.id.clone(),
+                    question: q.question.clone(),
+                    answer: None,
+                    tool_calls: Vec::new(),
+                    sources: Vec::new(),
+                    mismatches: Vec::new(),
+                    latency_ms,
+                    error: Some(e),
+                },
+            }
+        })
+        .buffered(concurrency.max(1))
+}
+
+/// One row of an `identify-dir` report.
+#[derive(Debug, Serialize)]
+pub struct ImageReport {
+    pub file: PathBuf,
+    /// `pokemon`, `not_pokemon`, `unknown`, or `error` when the image could
+    /// not be checked.
+    pub verdict: String,
+    /// Every Pokémon found, separated by `;`.
+    pub pokemon: Option<String>,
+    /// Confidence of the first Pokémon found, or the model's overall one.
+    pub confidence: Option<f32>,
+    /// The model's reason, or the error message for failed images.
+    pub reason: Option<String>,
+    #[serde(skip)]
+    pub error: Option<PokidexError>,
+}
+
+/// Identifies each image in turn, starting at most `per_minute` model calls
+/// per minute so large folders stay under the API quota.
+pub fn identify_images<'a>(
+    engine: &'a RAGEngine,
+    paths: &'a [PathBuf],
+    per_minute: u32,
+) -> impl Stream<Item = ImageReport> + 'a {
+    let mut ticker = time::interval(Duration::from_secs_f64(60.0 / per_minute.max(1) as f64));
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    stream::unfold(
+        (paths.iter(), ticker),
+        move |(mut paths, mut ticker)| async move {
+            let path = paths.next()?;
+            ticker.tick().await;
+            let result = match ImageInput::from_path(path) {
+                Ok(image) => engine.identify(&image).await,
+                Err(e) => Err(e),
+            };
+            let report = match result {
+                Ok(found) => {
+                    let names = found.pokemon();
+                    let confidence = found
+                        .detections
+                        .iter()
+                        .find(|d| d.pokemon.is_some())
+                        .and_then(|d| d.candidates.first())
+                        .and_then(|c| c.confidence);
+                    ImageReport {
+                        file: path.clone(),
+                        verdict: found.verdict.to_string(),
+                        pokemon: (!names.is_empty()).then(|| names.join(";")),
+                        confidence: confidence.or(found.confidence),
+                        reason: found.reason,
+                        error: None,
+                    }
+                }
+                Err(e) => ImageReport {
+                    file: path.clone(),
+                    verdict: "error".to_string(),
+                    pokemon: None,
+                    confidence: None,
+                    reason: Some(e.to_string()),
+                    error: Some(e),
+                },
+            };
+            Some((report, (paths, ticker)))
+        },
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReportFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+impl FromStr for ReportFormat {
+    type Err = PokidexError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "csv" => Ok(ReportFormat::Csv),
+            "json" => Ok(ReportFormat::Json),
+            other => Err(PokidexError::InvalidRequest(format!(
+                "unknown report format '{}' (expected csv or json)",
+                other
+            ))),
+        }
+    }
+}
+
+/// Writes [`ImageReport`] rows as they arrive: CSV with a header line, or a
+/// JSON array closed by [`ReportWriter::finish`].
+pub struct ReportWriter<W: Write> {
+    out: W,
+    format: ReportFormat,
+    rows: usize,
+}
+
+impl<W: Write> ReportWriter<W> {
+    pub fn new(out: W, format: ReportFormat) -> Self {
+        Self {
+            out,
+            format,
+            rows: 0,
+        }
+    }
+
+    pub fn write(&mut self, report: &ImageReport) -> Result<()> {
+        match self.format {
+            ReportFormat::Csv => {
+                if self.rows == 0 {
+                    writeln!(self.out, "file,verdict,pokemon,confidence,reason")?;
+                }
+                let fields = [
+                    report.file.display().to_string(),
+                    report.verdict.clone(),
+                    report.pokemon.clone().unwrap_or_default(),
+                    report.confidence.map(|c| c.to_string()).unwrap_or_default(),
+                    report.reason.clone().unwrap_or_default(),
+                ];
+                let fields: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+                writeln!(self.out, "{}", fields.join(","))?;
+            }
+            ReportFormat::Json => {
+                let json = serde_json::to_string_pretty(report)
+                    .map_err(|e| PokidexError::ParseFailure(e.to_string()))?;
+                let separator = if self.rows == 0 { "[\n" } else { ",\n" };
+                write!(self.out, "{}{}", separator, json)?;
+            }
+        }
+        self.rows += 1;
+        self.out.flush()?;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<()> {
+        if self.format == ReportFormat::Json {
+            let close = if self.rows == 0 { "[]\n" } else { "\n]\n" };
+            write!(self.out, "{}", close)?;
+        }
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// Quotes a CSV field when it contains a delimiter, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{csv_field, parse_questions, run_batch};
+    use crate::config::Config;
+    use crate::rag::RAGEngine;
+    use crate::test_support::{EmptySource, ScriptedModel};
+    use futures::StreamExt;
+    use serde_json::json;
+    use std::time::Duration;
+
+    /// The N in "Question N?", the one question in `prompt`.
+    fn question_number(prompt: &str) -> u64 {
+        (1..=4)
+            .find(|n| prompt.contains(&format!("Question {}?", n)))
+            .unwrap()
+    }
+
+    #[test]
+    fn parses_text_and_jsonl_questions() {
+        let text =
+            parse_questions("# FAQ\nWhat type is Gengar?\n\n  Is Ditto fast?  \n", false).unwrap();
+        let questions: Vec<&str> = text.iter().map(|q| q.question.as_str()).collect();
+        assert_eq!(questions, ["What type is Gengar?", "Is Ditto fast?"]);
+
+        let jsonl = parse_questions(
+            "{\"id\": 7, \"question\": \"Who evolves into Raichu?\"}\n{\"question\": \"Hi\"}",
+            true,
+        )
+        .unwrap();
+        assert_eq!(jsonl[0].id, Some(json!(7)));
+        assert_eq!(jsonl[1].id, None);
+
+        assert!(parse_questions("{\"q\": \"typo\"}", true).is_err());
+    }
+
+    #[test]
+    fn skips_comments_in_jsonl_and_numbers_bad_lines() {
+        let jsonl = parse_questions(
+            "# exported FAQ\n\n{\"id\": \"gengar-1\", \"question\": \" What type is Gengar? \"}\n",
+            true,
+        )
+        .unwrap();
+        assert_eq!(jsonl.len(), 1);
+        assert_eq!(jsonl[0].id, Some(json!("gengar-1")));
+        assert_eq!(jsonl[0].question, "What type is Gengar?");
+
+        // In a text file, JSON is just a question.
+        let text = parse_questions("{\"q\": \"typo\"}", false).unwrap();
+        assert_eq!(text[0].question, "{\"q\": \"typo\"}");
+
+        let missing = "# header\n{\"question\": \"Hi\"}\n\n{\"id\": 3, \"question\": \"  \"}";
+        let error = parse_questions(missing, true).unwrap_err().to_string();
+        assert!(error.contains("line 4: missing \"question\""), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn returns_results_in_input_order() {
+        let engine = RAGEngine::builder()
+            .config(Config {
+                cache_dir: std::env::temp_dir().join("pokidex-batch-tests"),
+                ..Config::default()
+            })
+            // Earlier questions take longer, so answers finish in reverse order.
+            .llm(
+                ScriptedModel::new(|prompt| {
+                    let answer = format!("Answer {}", question_number(prompt));
+                    json!({"type": "final", "answer": answer}).to_string()
+                })
+                .with_delay(|prompt| Duration::from_millis((5 - question_number(prompt)) * 20)),
+            )
+            .data_source(EmptySource)
+            .build()
+            .unwrap();
+        let questions =
+            parse_questions("Question 1?\nQuestion 2?\nQuestion 3?\nQuestion 4?", false).unwrap();
+
+        for concurrency in [0, 1, 4] {
+            let results: Vec<_> = run_batch(&engine, &questions, concurrency).collect().await;
+            let answers: Vec<_> = results.iter().map(|r| r.answer.as_deref()).collect();
+            assert_eq!(
+                answers,
+                [
+                    Some("Answer 1"),
+                    Some("Answer 2"),
+                    Some("Answer 3"),
+                    Some("Answer 4")
+                ],
+                "concurrency {}",
+                concurrency
+            );
+        }
+    }
+
+    #[test]
+    fn quotes_csv_fields_when_needed() {
+        assert_eq!(csv_field("pikachu"), "pikachu");
+        assert_eq!(
+            csv_field("a cat, not a Pokémon"),
+            "\"a cat, not a Pokémon\""
+        );
+        assert_eq!(csv_field("the \"real\" one"), "\"the \"\"real\"\" one\"");
+    }
+}
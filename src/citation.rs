@@ -0,0 +1,165 @@
+//! Source tags for retrieved data, so answers can cite the PokéAPI
+//! resources their facts came from.
+
+use rustemon::model::moves::Move;
+use rustemon::model::pokemon::{Pokemon, PokemonSpecies};
+use serde::Serialize;
+
+const POKEAPI_BASE_URL: &str = "https://pokeapi.co/api/v2";
+
+/// Asks the model to cite the tags that head each block of retrieved data.
+pub(crate) const CITATION_INSTRUCTION: &str = "\nEach block of retrieved data starts with its source tags, e.g. [S1]. Cite the tag in square brackets after each fact you take from it, e.g. \"Charizard is Fire/Flying [S1].\" Only use tags that appear in the data.";
+
+/// A PokéAPI resource a tool read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Resource {
+    /// Endpoint name, e.g. `pokemon-species`.
+    pub endpoint: &'static str,
+    pub name: String,
+    pub id: i64,
+}
+
+impl Resource {
+    pub fn pokemon(pokemon: &Pokemon) -> Self {
+        Self {
+            endpoint: "pokemon",
+            name: pokemon.name.clone(),
+            id: pokemon.id,
+        }
+    }
+
+    pub fn species(species: &PokemonSpecies) -> Self {
+        Self {
+            endpoint: "pokemon-species",
+            name: species.name.clone(),
+            id: species.id,
+        }
+    }
+
+    pub fn pokemon_move(pokemon_move: &Move) -> Self {
+        Self {
+            endpoint: "move",
+            name: pokemon_move.name.clone(),
+            id: pokemon_move.id,
+        }
+    }
+
+    pub fn url(&self) -> String {
+        format!("{}/{}/{}/", POKEAPI_BASE_URL, self.endpoint, self.id)
+    }
+}
+
+/// A resource that backed an answer, with the tag the model cites it by.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Source {
+    /// e.g. `S1`, cited as `[S1]`.
+    pub tag: String,
+    pub endpoint: String,
+    pub name: String,
+    pub url: String,
+}
+
+/// Sources retrieved while answering one question, tagged in the order
+/// they were first read.
+#[derive(Debug, Default)]
+pub(crate) struct SourceList {
+    sources: Vec<Source>,
+}
+
+impl SourceList {
+    /// Header for a block of data read from `resources`, e.g.
+    /// `Sources: [S1] pokemon/charizard`. A resource read earlier keeps
+    /// its tag.
+    pub fn header(&mut self, resources: &[Resource]) -> String {
+        let mut tags = Vec::with_capacity(resources.len());
+        for resource in resources {
+            let url = resource.url();
+            let tag = match self.sources.iter().find(|s| s.url == url) {
+                Some(source) => source.tag.clone(),
+                None => {
+                    let tag = format!("S{}", self.sources.len() + 1);
+                    self.sources.push(Source {
+                        tag: tag.clone(),
+                        endpoint: resource.endpoint.to_string(),
+                        name: resource.name.clone(),
+                        url,
+                    });
+                    tag
+                }
+            };
+            tags.push(format!("[{}] {}/{}", tag, resource.endpoint, resource.name));
+        }
+        format!("Sources: {}", tags.join(", "))
+    }
+
+    /// The sources `answer` cites, or every source read if it cites none.
+    pub fn cited_in(self, answer: &str) -> Vec<Source> {
+        let words: Vec<&str> = answer.split(|c: char| !c.is_ascii_alphanumeric()).collect();
+        let cited: Vec<Source> = self
+            .sources
+            .iter()
+            .filter(|source| words.contains(&source.tag.as_str()))
+            .cloned()
+            .collect();
+        if cited.is_empty() {
+            self.sources
+        } else {
+            cited
+        }
+    }
+}
+
+/// A "Sources" footer for printing after an answer, if it has sources.
+pub fn format_sources(sources: &[Source]) -> Option<String> {
+    if sources.is_empty() {
+        return None;
+    }
+    let mut out = String::from("Sources:");
+    for source in sources {
+        out.push_str(&format!(
+            "\n  [{}] {}/{} - {}",
+            source.tag, source.endpoint, source.name, source.url
+        ));
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Resource, SourceList};
+
+    fn resource(endpoint: &'static str, name: &str, id: i64) -> Resource {
+        Resource {
+            endpoint,
+            name: name.to_string(),
+            id,
+        }
+    }
+
+    #[test]
+    fn reuses_tags_and_keeps_cited_sources() {
+        let mut sources = SourceList::default();
+        let charizard = resource("pokemon", "charizard", 6);
+        assert_eq!(
+            sources.header(&[
+                charizard.clone(),
+                resource("pokemon-species", "charizard", 6)
+            ]),
+            "Sources: [S1] pokemon/charizard, [S2] pokemon-species/charizard"
+        );
+        assert_eq!(
+            sources.header(&[resource("pokemon", "pikachu", 25), charizard]),
+            "Sources: [S3] pokemon/pikachu, [S1] pokemon/charizard"
+        );
+
+        let cited = sources.cited_in("Charizard is Fire/Flying [S1], Pikachu is Electric [S3].");
+        let urls: Vec<&str> = cited.iter().map(|s| s.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://pokeapi.co/api/v2/pokemon/6/",
+                "https://pokeapi.co/api/v2/pokemon/25/"
+            ]
+        );
+    }
+}
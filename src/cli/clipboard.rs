@@ -0,0 +1,78 @@
+use pokidex::{PokidexError, Result};
+use std::env;
+use std::process::{Command, Output};
+
+/// Clipboard types we ask for, most preferred first; any other `image/*`
+/// type is taken as a last resort.
+const IMAGE_TYPES: &[&str] = &[
+    "image/png",
+    "image/jpeg",
+    "image/webp",
+    "image/gif",
+    "image/bmp",
+    "image/tiff",
+];
+
+/// Reads the image on the clipboard with `wl-paste` (Wayland) or `xclip`
+/// (X11). The bytes still need [`pokidex::ImageInput::from_bytes`].
+pub fn read_image() -> Result<Vec<u8>> {
+    if env::var_os("WAYLAND_DISPLAY").is_some() {
+        let list = ["wl-paste", "--list-types"];
+        if let Some(bytes) = read_with(&list, |t| vec!["wl-paste", "--no-newline", "--type", t])? {
+            return Ok(bytes);
+        }
+    }
+
+    if env::var_os("DISPLAY").is_some() {
+        let list = ["xclip", "-selection", "clipboard", "-t", "TARGETS", "-o"];
+        if let Some(bytes) = read_with(&list, |t| {
+            vec!["xclip", "-selection", "clipboard", "-t", t, "-o"]
+        })? {
+            return Ok(bytes);
+        }
+    }
+
+    Err(PokidexError::InvalidImage(
+        "could not read the clipboard. Install 'wl-clipboard' (Wayland) or 'xclip' (X11), or save the image and use: pokidex identify-image <path>".to_string(),
+    ))
+}
+
+/// Lists the clipboard's types with `list`, then reads the best image type
+/// with the command `read` builds. `None` when the tool isn't installed.
+fn read_with(list: &[&str], read: impl Fn(&str) -> Vec<&str>) -> Result<Option<Vec<u8>>> {
+    let Some(output) = run(list) else {
+        return Ok(None);
+    };
+    // Both tools fail when nothing has been copied yet.
+    if !output.status.success() {
+        return Err(PokidexError::InvalidImage(
+            "the clipboard is empty".to_string(),
+        ));
+    }
+    let types = String::from_utf8_lossy(&output.stdout);
+    let types: Vec<&str> = types.lines().map(str::trim).collect();
+
+    let image_type = IMAGE_TYPES
+        .iter()
+        .copied()
+        .find(|t| types.contains(t))
+        .or_else(|| types.iter().copied().find(|t| t.starts_with("image/")))
+        .ok_or_else(|| {
+            PokidexError::InvalidImage("the clipboard does not hold an image".to_string())
+        })?;
+
+    match run(&read(image_type)) {
+        Some(output) if output.status.success() && !output.stdout.is_empty() => {
+            Ok(Some(output.stdout))
+        }
+        _ => Err(PokidexError::InvalidImage(format!(
+            "could not read {} from the clipboard",
+            image_type
+        ))),
+    }
+}
+
+/// Runs `command`, or `None` if it couldn't be started.
+fn run(command: &[&str]) -> Option<Output> {
+    Command::new(command[0]).args(&command[1..]).output().ok()
+}
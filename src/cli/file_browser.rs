@@ -0,0 +1,334 @@
+//! Terminal file browser for `select-image` on machines without a desktop:
+//! directories and image files only, with fuzzy search as you type.
+
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
+use pokidex::image_input::is_image_path;
+use pokidex::{PokidexError, Result};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Position};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+
+/// Rows moved by PageUp/PageDown.
+const PAGE: usize = 10;
+
+struct Entry {
+    name: String,
+    path: PathBuf,
+    is_dir: bool,
+}
+
+enum Action {
+    Continue,
+    Pick(PathBuf),
+    Cancel,
+}
+
+struct Browser {
+    dir: PathBuf,
+    entries: Vec<Entry>,
+    query: String,
+    /// Entries matching `query`, best first, with the matched character
+    /// positions to highlight.
+    visible: Vec<(usize, Vec<usize>)>,
+    list: ListState,
+    /// Last failure to open a directory, shown in the footer.
+    error: Option<String>,
+}
+
+/// Lets the user browse from the current directory. `None` when stdin or
+/// stdout isn't a terminal, so the next picker can be tried.
+pub fn pick_image_file() -> Result<Option<PathBuf>> {
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Ok(None);
+    }
+
+    let mut browser = Browser::new(env::current_dir()?)?;
+    let mut terminal = ratatui::try_init()?;
+    let picked = browser.run(&mut terminal);
+    ratatui::restore();
+
+    match picked? {
+        Some(path) => Ok(Some(path)),
+        None => Err(PokidexError::FilePicker(
+            "Image selection was cancelled".to_string(),
+        )),
+    }
+}
+
+impl Browser {
+    fn new(dir: PathBuf) -> Result<Self> {
+        let mut browser = Self {
+            entries: read_entries(&dir)?,
+            dir,
+            query: String::new(),
+            visible: Vec::new(),
+            list: ListState::default(),
+            error: None,
+        };
+        browser.refilter();
+        Ok(browser)
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<Option<PathBuf>> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match self.handle_key(key) {
+                Action::Continue => {}
+                Action::Pick(path) => return Ok(Some(path)),
+                Action::Cancel => return Ok(None),
+            }
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Action {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => return Action::Cancel,
+            KeyCode::Char('c') if ctrl => return Action::Cancel,
+            KeyCode::Char('u') if ctrl => {
+                self.query.clear();
+                self.refilter();
+            }
+            KeyCode::Enter | KeyCode::Right => {
+                let Some(entry) = self.selected() else {
+                    return Action::Continue;
+                };
+                if entry.is_dir {
+                    let dir = entry.path.clone();
+                    self.open(dir, None);
+                } else if key.code == KeyCode::Enter {
+                    return Action::Pick(entry.path.clone());
+                }
+            }
+            KeyCode::Left => self.open_parent(),
+            KeyCode::Backspace => {
+                if self.query.pop().is_some() {
+                    self.refilter();
+                } else {
+                    self.open_parent();
+                }
+            }
+            KeyCode::Up => self.move_by(-1),
+            KeyCode::Down => self.move_by(1),
+            KeyCode::PageUp => self.move_by(-(PAGE as isize)),
+            KeyCode::PageDown => self.move_by(PAGE as isize),
+            KeyCode::Home => self.move_by(isize::MIN),
+            KeyCode::End => self.move_by(isize::MAX),
+            KeyCode::Char(c) if !ctrl => {
+                self.query.push(c);
+                self.refilter();
+            }
+            _ => {}
+        }
+        Action::Continue
+    }
+
+    fn selected(&self) -> Option<&Entry> {
+        let (index, _) = self.visible.get(self.list.selected()?)?;
+        self.entries.get(*index)
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        if self.visible.is_empty() {
+            return;
+        }
+        let current = self.list.selected().unwrap_or(0) as isize;
+        let last = self.visible.len() as isize - 1;
+        let next = current.saturating_add(delta).clamp(0, last);
+        self.list.select(Some(next as usize));
+    }
+
+    /// Switches to `dir`, selecting the entry for `select` if given. On
+    /// failure the current directory stays and the error is shown.
+    fn open(&mut self, dir: PathBuf, select: Option<&Path>) {
+        match read_entries(&dir) {
+            Ok(entries) => {
+                self.entries = entries;
+                self.dir = dir;
+                self.query.clear();
+                self.error = None;
+                self.refilter();
+                let position = select.and_then(|path| {
+                    self.visible
+                        .iter()
+                        .position(|(i, _)| self.entries[*i].path == path)
+                });
+                if position.is_some() {
+                    self.list.select(position);
+                }
+            }
+            Err(e) => self.error = Some(format!("{}: {}", dir.display(), e)),
+        }
+    }
+
+    fn open_parent(&mut self) {
+        let current = self.dir.clone();
+        if let Some(parent) = current.parent() {
+            self.open(parent.to_path_buf(), Some(&current));
+        }
+    }
+
+    fn refilter(&mut self) {
+        let names: Vec<&str> = self.entries.iter().map(|e| e.name.as_str()).collect();
+        self.visible = rank(&names, &self.query);
+        self.list.select(if self.visible.is_empty() {
+            None
+        } else {
+            Some(0)
+        });
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [search, list, help] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let title = format!(" Select a Pokémon image: {} ", self.dir.display());
+        let prompt = Line::from(vec!["> ".bold(), Span::raw(&self.query)]);
+        frame.render_widget(
+            Paragraph::new(prompt).block(Block::bordered().title(title)),
+            search,
+        );
+        frame.set_cursor_position(Position::new(
+            search.x + 3 + self.query.chars().count() as u16,
+            search.y + 1,
+        ));
+
+        let items: Vec<ListItem> = self
+            .visible
+            .iter()
+            .map(|(index, matched)| entry_line(&self.entries[*index], matched))
+            .collect();
+        let counts = format!(" {} of {} ", self.visible.len(), self.entries.len());
+        let widget = List::new(items)
+            .block(Block::bordered().title(counts))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("> ");
+        frame.render_stateful_widget(widget, list, &mut self.list);
+
+        let footer = match &self.error {
+            Some(error) => Line::from(error.as_str()).red(),
+            None => Line::from(
+                "type to search · ↑↓ move · Enter open/select · ←/Backspace parent · Esc cancel",
+            )
+            .dim(),
+        };
+        frame.render_widget(footer, help);
+    }
+}
+
+/// A list row: directories in blue with a trailing `/`, matched characters
+/// highlighted.
+fn entry_line<'a>(entry: &'a Entry, matched: &[usize]) -> ListItem<'a> {
+    let base = if entry.is_dir {
+        Style::new().fg(Color::Blue)
+    } else {
+        Style::new()
+    };
+    let mut spans: Vec<Span> = entry
+        .name
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let style = if matched.contains(&i) {
+                base.fg(Color::Yellow).add_modifier(Modifier::BOLD)
+            } else {
+                base
+            };
+            Span::styled(c.to_string(), style)
+        })
+        .collect();
+    if entry.is_dir {
+        spans.push(Span::styled("/", base));
+    }
+    ListItem::new(Line::from(spans))
+}
+
+/// Subdirectories and image files in `dir`, directories first, then by
+/// name. Hidden entries are left out; `..` leads to the parent.
+fn read_entries(dir: &Path) -> io::Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    for item in fs::read_dir(dir)? {
+        let path = item?.path();
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if name.starts_with('.') {
+            continue;
+        }
+        let is_dir = path.is_dir();
+        if is_dir || is_image_path(&path) {
+            entries.push(Entry { name, path, is_dir });
+        }
+    }
+    entries.sort_by_cached_key(|e| (!e.is_dir, e.name.to_lowercase()));
+
+    if let Some(parent) = dir.parent() {
+        entries.insert(
+            0,
+            Entry {
+                name: "..".to_string(),
+                path: parent.to_path_buf(),
+                is_dir: true,
+            },
+        );
+    }
+    Ok(entries)
+}
+
+/// Indices of `names` matching `query`, best match first (ties keep their
+/// listing order), with the positions of the matched characters. An empty
+/// query keeps everything in order.
+pub fn rank(names: &[&str], query: &str) -> Vec<(usize, Vec<usize>)> {
+    if query.is_empty() {
+        return (0..names.len()).map(|i| (i, Vec::new())).collect();
+    }
+    let matcher = SkimMatcherV2::default().smart_case();
+    let mut scored: Vec<(i64, usize, Vec<usize>)> = names
+        .iter()
+        .enumerate()
+        .filter_map(|(i, name)| {
+            matcher
+                .fuzzy_indices(name, query)
+                .map(|(score, matched)| (score, i, matched))
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    scored
+        .into_iter()
+        .map(|(_, i, matched)| (i, matched))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::rank;
+
+    #[test]
+    fn ranks_fuzzy_matches() {
+        let names = ["..", "cards", "charizard.png", "pikachu.jpg", "char.png"];
+        let ranked: Vec<usize> = rank(&names, "chpng").iter().map(|(i, _)| *i).collect();
+        assert_eq!(ranked, [4, 2]);
+
+        assert_eq!(rank(&names, "").len(), names.len());
+        assert!(rank(&names, "zzz").is_empty());
+    }
+}
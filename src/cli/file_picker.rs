@@ -0,0 +1,160 @@
+use super::file_browser;
+use pokidex::{Picker, PokidexError, Result};
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Tries each picker in `order` until one is available. A picker the user
+/// cancels is final; one that isn't installed or has no display to open on
+/// is skipped.
+pub fn pick_image_file(order: &[Picker]) -> Result<PathBuf> {
+    for picker in order {
+        let picked = match picker {
+            Picker::System => pick_file_with_system_dialog()?,
+            Picker::Zenity => pick_file_with_zenity()?,
+            Picker::Kdialog => pick_file_with_kdialog()?,
+            Picker::Terminal => file_browser::pick_image_file()?,
+        };
+        if let Some(path) = picked {
+            return Ok(path);
+        }
+    }
+
+    let tried: Vec<String> = order
+        .iter()
+        .map(|p| format!("{:?}", p).to_lowercase())
+        .collect();
+    Err(PokidexError::FilePicker(format!(
+        "no file picker available (tried: {}). Use: pokidex identify-image <path> (or --clipboard)",
+        tried.join(", ")
+    )))
+}
+
+#[allow(unreachable_code)]
+fn pick_file_with_system_dialog() -> Result<Option<PathBuf>> {
+    #[cfg(target_os = "macos")]
+    {
+        let script = r#"
+            set selectedFile to choose file with prompt "Select a Pokémon image" of type {"public.image"}
+            POSIX path of selectedFile
+        "#;
+
+        let Ok(output) = Command::new("osascript").args(["-e", script]).output() else {
+            return Ok(None);
+        };
+
+        if !output.status.success() {
+            return Err(PokidexError::FilePicker(
+                "Image selection was cancelled or failed on macOS".to_string(),
+            ));
+        }
+
+        let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if path.is_empty() {
+            return Err(PokidexError::FilePicker("No file was selected".to_string()));
+        }
+
+        return Ok(Some(PathBuf::from(path)));
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        let script = r#"
+Add-Type -AssemblyName System.Windows.Forms
+$dialog = New-Object System.Windows.Forms.OpenFileDialog
+$dialog.Filter = "Image files (*.png;*.jpg;*.jpeg;*.webp;*.gif;*.bmp;*.tif;*.tiff;*.heic;*.heif)|*.png;*.jpg;*.jpeg;*.webp;*.gif;*.bmp;*.tif;*.tiff;*.heic;*.heif|All files (*.*)|*.*"
+$dialog.Title = "Select a Pokémon image"
+if ($dialog.ShowDialog() -eq [System.Windows.Forms.DialogResult]::OK) {
+  Write-Output $dialog.FileName
+}
+        "#;
+
+        let Ok(output) = Command::new("powershell")
+            .args(["-NoProfile", "-Command", script])
+            .output()
+        else {
+            return Ok(None);
+        };
+
+        if !output.status.success() {
+            return Err(PokidexError::FilePicker(
+                "Image selection was cancelled or failed on Windows".to_string(),
+            ));
+        }
+
+        let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if path.is_empty() {
+            return Err(PokidexError::FilePicker("No file was selected".to_string()));
+        }
+
+        return Ok(Some(PathBuf::from(path)));
+    }
+
+    Ok(None)
+}
+
+/// Whether a graphical picker has a desktop session to open on.
+fn has_display() -> bool {
+    env::var_os("DISPLAY").is_some() || env::var_os("WAYLAND_DISPLAY").is_some()
+}
+
+fn pick_file_with_zenity() -> Result<Option<PathBuf>> {
+    if !has_display() {
+        return Ok(None);
+    }
+    let output = match Command::new("zenity")
+        .args([
+            "--file-selection",
+            "--title=Select a Pokémon image",
+            "--file-filter=Image files | *.png *.jpg *.jpeg *.webp *.gif *.bmp *.tif *.tiff *.heic *.heif",
+        ])
+        .output()
+    {
+        Ok(output) => output,
+        Err(_) => return Ok(None),
+    };
+
+    selected_path(output)
+}
+
+fn pick_file_with_kdialog() -> Result<Option<PathBuf>> {
+    if !has_display() {
+        return Ok(None);
+    }
+    let output = match Command::new("kdialog")
+        .args([
+            "--getopenfilename",
+            ".",
+            "*.png *.jpg *.jpeg *.webp *.gif *.bmp *.tif *.tiff *.heic *.heif|Image files",
+            "--title",
+            "Select a Pokémon image",
+        ])
+        .output()
+    {
+        Ok(output) => output,
+        Err(_) => return Ok(None),
+    };
+
+    selected_path(output)
+}
+
+/// Reads a zenity/kdialog result. Both exit with 1 when the dialog is
+/// cancelled; any other failure means the picker couldn't run here.
+fn selected_path(output: std::process::Output) -> Result<Option<PathBuf>> {
+    match output.status.code() {
+        Some(0) => {}
+        Some(1) => {
+            return Err(PokidexError::FilePicker(
+                "Image selection was cancelled".to_string(),
+            ))
+        }
+        _ => return Ok(None),
+    }
+
+    let selected = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if selected.is_empty() {
+        Err(PokidexError::FilePicker("No file was selected".to_string()))
+    } else {
+        Ok(Some(PathBuf::from(selected)))
+    }
+}
@@ -0,0 +1,429 @@
+//! Renders the model's Markdown answers for the terminal: colours and
+//! aligned tables on a TTY, plain text when output is piped.
+
+use pulldown_cmark::{Alignment, Event, Options, Parser, Tag, TagEnd};
+use std::env;
+use std::io::{self, IsTerminal};
+
+const BOLD: &str = "\x1b[1m";
+const ITALIC: &str = "\x1b[3m";
+const UNDERLINE: &str = "\x1b[4m";
+const STRIKE: &str = "\x1b[9m";
+const DIM: &str = "\x1b[2m";
+const CODE: &str = "\x1b[36m";
+const HEADING: &str = "\x1b[1;35m";
+const RESET: &str = "\x1b[0m";
+
+/// Width of a horizontal rule.
+const RULE_WIDTH: usize = 40;
+
+/// `text` rendered for stdout: styled when it is a terminal, plain when
+/// it's redirected or `NO_COLOR` is set.
+pub fn render_for_stdout(text: &str) -> String {
+    let styled = io::stdout().is_terminal()
+        && env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+        && env::var("TERM").map_or(true, |term| term != "dumb");
+    render(text, styled)
+}
+
+/// Renders `text`; with `styled` off, Markdown syntax is still removed and
+/// tables are still aligned, but no escape codes are written.
+pub fn render(text: &str, styled: bool) -> String {
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH;
+    let mut renderer = Renderer {
+        styled,
+        ..Renderer::default()
+    };
+    for event in Parser::new_ext(text, options) {
+        renderer.event(event);
+    }
+    renderer.out.trim_end().to_string()
+}
+
+enum Container {
+    Quote,
+    /// A list item whose continuation lines are indented by this much.
+    Item(usize),
+}
+
+#[derive(Default)]
+struct Table {
+    alignments: Vec<Alignment>,
+    rows: Vec<Vec<String>>,
+    /// Rows before this index are the header.
+    header_rows: usize,
+}
+
+#[derive(Default)]
+struct Renderer {
+    styled: bool,
+    out: String,
+    at_line_start: bool,
+    /// Blank line owed before the next block.
+    pending_gap: bool,
+    containers: Vec<Container>,
+    /// Next number of each open list; `None` for bullet lists.
+    lists: Vec<Option<u64>>,
+    /// Active styles, reapplied after each reset.
+    styles: Vec<&'static str>,
+    table: Option<Table>,
+    in_code_block: bool,
+    link: Option<String>,
+}
+
+impl Renderer {
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) if self.in_code_block => self.code_block(&text),
+            Event::Text(text) | Event::Html(text) | Event::InlineHtml(text) => self.text(&text),
+            Event::Code(code) => {
+                self.push_style(CODE);
+                self.text(&code);
+                self.pop_style();
+            }
+            Event::SoftBreak | Event::HardBreak => self.newline(),
+            Event::Rule => {
+                self.block();
+                let rule = if self.styled { "─" } else { "-" };
+                self.text(&rule.repeat(RULE_WIDTH));
+                self.end_block();
+            }
+            Event::TaskListMarker(done) => self.text(if done { "[x] " } else { "[ ] " }),
+            _ => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => self.block(),
+            Tag::Heading { .. } => {
+                self.block();
+                self.push_style(HEADING);
+            }
+            Tag::BlockQuote(_) => {
+                self.block();
+                self.containers.push(Container::Quote);
+            }
+            Tag::CodeBlock(_) => {
+                self.block();
+                self.in_code_block = true;
+            }
+            Tag::List(first) => {
+                // A nested list starts on the item's next line.
+                if self.lists.is_empty() {
+                    self.block();
+                } else if !self.at_line_start {
+                    self.newline();
+                }
+                self.lists.push(first);
+            }
+            Tag::Item => {
+                if !self.at_line_start {
+                    self.newline();
+                }
+                let marker = match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}. ", *n - 1)
+                    }
+                    _ if self.styled => "• ".to_string(),
+                    _ => "- ".to_string(),
+                };
+                self.text(&marker);
+                self.containers
+                    .push(Container::Item(marker.chars().count()));
+            }
+            Tag::Table(alignments) => {
+                self.block();
+                self.table = Some(Table {
+                    alignments,
+                    ..Table::default()
+                });
+            }
+            Tag::TableHead | Tag::TableRow => {
+                if let Some(table) = &mut self.table {
+                    table.rows.push(Vec::new());
+                }
+            }
+            Tag::TableCell => {
+                if let Some(row) = self.table.as_mut().and_then(|t| t.rows.last_mut()) {
+                    row.push(String::new());
+                }
+            }
+            Tag::Emphasis => self.push_style(ITALIC),
+            Tag::Strong => self.push_style(BOLD),
+            Tag::Strikethrough => self.push_style(STRIKE),
+            Tag::Link { dest_url, .. } => {
+                self.push_style(UNDERLINE);
+                self.link = Some(dest_url.to_string());
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph => self.end_block(),
+            TagEnd::Heading(_) => {
+                self.pop_style();
+                self.end_block();
+            }
+            TagEnd::BlockQuote(_) => {
+                self.containers.pop();
+                self.end_block();
+            }
+            TagEnd::CodeBlock => {
+                self.in_code_block = false;
+                self.end_block();
+            }
+            TagEnd::List(_) => {
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.end_block();
+                }
+            }
+            TagEnd::Item => {
+                self.containers.pop();
+                // Items of a tight list hold bare text, with no paragraph
+                // to end them.
+                self.pending_gap = false;
+                if !self.at_line_start {
+                    self.newline();
+                }
+            }
+            TagEnd::TableHead => {
+                if let Some(table) = &mut self.table {
+                    table.header_rows = table.rows.len();
+                }
+            }
+            TagEnd::Table => {
+                if let Some(table) = self.table.take() {
+                    self.table(&table);
+                }
+                self.end_block();
+            }
+            TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough => self.pop_style(),
+            TagEnd::Link => {
+                self.pop_style();
+                if let Some(url) = self.link.take() {
+                    self.push_style(DIM);
+                    self.text(&format!(" ({})", url));
+                    self.pop_style();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Starts a block, leaving a blank line after the previous one.
+    fn block(&mut self) {
+        if self.out.is_empty() {
+            return;
+        }
+        if !self.at_line_start {
+            self.newline();
+        }
+        if self.pending_gap {
+            self.newline();
+        }
+        self.pending_gap = false;
+    }
+
+    fn end_block(&mut self) {
+        self.pending_gap = true;
+    }
+
+    fn text(&mut self, text: &str) {
+        if let Some(table) = &mut self.table {
+            if let Some(cell) = table.rows.last_mut().and_then(|row| row.last_mut()) {
+                cell.push_str(text);
+            }
+            return;
+        }
+        // Links whose text is the URL itself don't need it repeated.
+        if self.link.as_deref() == Some(text) {
+            self.link = None;
+        }
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.newline();
+            }
+            if !line.is_empty() {
+                self.indent();
+                self.out.push_str(line);
+            }
+        }
+    }
+
+    fn code_block(&mut self, code: &str) {
+        self.push_style(CODE);
+        for line in code.trim_end_matches('\n').split('\n') {
+            self.text("    ");
+            self.text(line);
+            self.newline();
+        }
+        self.pop_style();
+    }
+
+    /// Writes the quote bars and list indentation owed at the start of a
+    /// line.
+    fn indent(&mut self) {
+        if !self.at_line_start {
+            return;
+        }
+        self.at_line_start = false;
+        // The last item's marker was written on its first line.
+        let mut prefix = String::new();
+        for container in &self.containers {
+            match container {
+                Container::Quote if self.styled => prefix.push_str("│ "),
+                Container::Quote => prefix.push_str("> "),
+                Container::Item(width) => prefix.push_str(&" ".repeat(*width)),
+            }
+        }
+        self.out.push_str(&prefix);
+    }
+
+    fn newline(&mut self) {
+        if self.table.is_some() {
+            return;
+        }
+        if self.styled && !self.styles.is_empty() {
+            self.out.push_str(RESET);
+        }
+        self.out.push('\n');
+        self.at_line_start = true;
+        if self.styled {
+            // Styles resume on the next line without carrying the reset
+            // into the indentation.
+            let styles: String = self.styles.concat();
+            self.out.push_str(&styles);
+        }
+    }
+
+    fn push_style(&mut self, style: &'static str) {
+        self.styles.push(style);
+        self.write_style(style);
+    }
+
+    fn pop_style(&mut self) {
+        self.styles.pop();
+        if self.styled {
+            let styles = format!("{}{}", RESET, self.styles.concat());
+            self.write_raw(&styles);
+        }
+    }
+
+    fn write_style(&mut self, style: &str) {
+        if self.styled {
+            self.write_raw(style);
+        }
+    }
+
+    /// Writes escape codes, into the current table cell if there is one.
+    fn write_raw(&mut self, codes: &str) {
+        match self.table.as_mut().and_then(|t| t.rows.last_mut()) {
+            Some(row) => {
+                if let Some(cell) = row.last_mut() {
+                    cell.push_str(codes);
+                }
+            }
+            None => self.out.push_str(codes),
+        }
+    }
+
+    /// Lays out a table with every column as wide as its widest cell.
+    fn table(&mut self, table: &Table) {
+        let columns = table.rows.iter().map(Vec::len).max().unwrap_or(0);
+        let mut widths = vec![0; columns];
+        for row in &table.rows {
+            for (i, cell) in row.iter().enumerate() {
+                widths[i] = widths[i].max(visible_width(cell));
+            }
+        }
+        let separator = if self.styled { " │ " } else { "  " };
+
+        for (r, row) in table.rows.iter().enumerate() {
+            let cells: Vec<String> = (0..columns)
+                .map(|i| {
+                    let cell = row.get(i).map_or("", String::as_str);
+                    let alignment = table.alignments.get(i).copied();
+                    let cell = pad(cell, widths[i], alignment.unwrap_or(Alignment::None));
+                    if r < table.header_rows && self.styled {
+                        format!("{}{}{}", BOLD, cell, RESET)
+                    } else {
+                        cell
+                    }
+                })
+                .collect();
+            self.indent();
+            self.out.push_str(cells.join(separator).trim_end());
+            self.newline();
+
+            if r + 1 == table.header_rows {
+                let (line, join) = if self.styled {
+                    ("─", "─┼─")
+                } else {
+                    ("-", "  ")
+                };
+                let rule: Vec<String> = widths.iter().map(|w| line.repeat(*w)).collect();
+                self.indent();
+                self.out.push_str(&rule.join(join));
+                self.newline();
+            }
+        }
+    }
+}
+
+/// Characters that show on screen, skipping escape sequences.
+fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut in_escape = false;
+    for c in text.chars() {
+        match c {
+            '\x1b' => in_escape = true,
+            'm' if in_escape => in_escape = false,
+            _ if in_escape => {}
+            _ => width += 1,
+        }
+    }
+    width
+}
+
+fn pad(cell: &str, width: usize, alignment: Alignment) -> String {
+    let gap = width.saturating_sub(visible_width(cell));
+    let (left, right) = match alignment {
+        Alignment::Right => (gap, 0),
+        Alignment::Center => (gap / 2, gap - gap / 2),
+        Alignment::Left | Alignment::None => (0, gap),
+    };
+    format!("{}{}{}", " ".repeat(left), cell, " ".repeat(right))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render;
+
+    #[test]
+    fn renders_plain_text_with_aligned_tables() {
+        let answer = "## Pikachu\n\n**Pikachu** is an *Electric* type.\n\n\
+                      - Static\n- Lightning Rod\n  1. hidden\n\n\
+                      | Stat | Base |\n|------|-----:|\n| HP | 35 |\n| Speed | 90 |";
+        assert_eq!(
+            render(answer, false),
+            "Pikachu\n\n\
+             Pikachu is an Electric type.\n\n\
+             - Static\n\
+             - Lightning Rod\n  1. hidden\n\n\
+             Stat   Base\n\
+             -----  ----\n\
+             HP       35\n\
+             Speed    90"
+        );
+
+        let styled = render("**HP**: 35", true);
+        assert!(styled.starts_with("\x1b[1mHP\x1b[0m: 35"), "{:?}", styled);
+    }
+}
@@ -0,0 +1,10 @@
+//! Terminal front ends of the `pokidex` binary, built on the library's
+//! public API.
+
+pub mod clipboard;
+pub mod file_browser;
+pub mod file_picker;
+pub mod markdown;
+pub mod repl;
+pub mod terminal_image;
+pub mod tui;
@@ -0,0 +1,513 @@
+//! Interactive chat: line editing, persistent history, completion of
+//! Pokémon names and slash commands that call the engine directly.
+
+use super::markdown;
+use super::terminal_image::SpriteView;
+use crate::load_image;
+use pokidex::citation::format_sources;
+use pokidex::{
+    Answer, ChatSession, PokeApiClient, PokemonDataSource, PokidexError, RAGEngine, Result,
+    ToolCall, Turn,
+};
+use rustyline::completion::{Completer, FilenameCompleter, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::FileHistory;
+use rustyline::validate::Validator;
+use rustyline::{CompletionType, Context, Editor, Helper};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc as std_mpsc, Arc, OnceLock};
+use tokio::sync::mpsc;
+
+const PROMPT: &str = "You: ";
+
+/// Lines kept in the history file.
+const HISTORY_SIZE: usize = 1000;
+
+/// Names offered at once when completing.
+const MAX_COMPLETIONS: usize = 100;
+
+/// Most Pokémon drawn after one chat answer.
+const MAX_CHAT_SPRITES: usize = 3;
+
+/// Transcript written by `/save` without a path.
+const DEFAULT_TRANSCRIPT: &str = "pokidex-chat.md";
+
+/// Slash commands with their arguments and help text.
+const COMMANDS: &[(&str, &str, &str)] = &[
+    ("/info", "<name>", "Pokédex entry, no model"),
+    ("/compare", "<names...>", "stats side by side"),
+    ("/image", "<path|url>", "identify an image"),
+    ("/lang", "[code]", "answer language, e.g. ja"),
+    ("/game", "[game|off]", "preferred game, e.g. scarlet"),
+    ("/reset", "", "forget the conversation"),
+    ("/save", "[path]", "write it to .md or .json"),
+    ("/help", "", "show this help"),
+    ("/quit", "", "leave"),
+];
+
+enum Flow {
+    Continue,
+    Quit,
+}
+
+struct Repl {
+    engine: RAGEngine,
+    session: ChatSession,
+    /// Everything asked and answered, including commands; `session` only
+    /// keeps the recent questions the model needs.
+    transcript: Vec<Turn>,
+    sprites: Option<SpriteView>,
+}
+
+/// Runs the chat until `/quit`, Ctrl-D or end of input.
+pub async fn run(engine: RAGEngine, cache_dir: &Path, sprites: Option<SpriteView>) -> Result<()> {
+    // Completion works from whatever the name index has when Tab is
+    // pressed, so the prompt doesn't wait for it.
+    let names = Arc::new(OnceLock::new());
+    let client = PokeApiClient::with_cache_dir(cache_dir)?;
+    let index = Arc::clone(&names);
+    tokio::spawn(async move {
+        if let Ok(list) = client.pokemon_names().await {
+            index.set(list).ok();
+        }
+    });
+    let mut reader = LineReader::spawn(ReplHelper {
+        names,
+        files: FilenameCompleter::new(),
+    });
+
+    println!("Welcome to Pokidex RAG Agent!");
+    println!("Ask me anything about Pokemon. Type /help for commands, or /quit to leave.\n");
+
+    let mut repl = Repl {
+        engine,
+        session: ChatSession::new(),
+        transcript: Vec::new(),
+        sprites,
+    };
+    loop {
+        let line = match reader.read_line().await {
+            None | Some(Err(ReadlineError::Eof)) => break,
+            Some(Err(ReadlineError::Interrupted)) => continue,
+            Some(Err(e)) => return Err(readline_error(e)),
+            Some(Ok(line)) => line,
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Flow::Quit = repl.handle(line).await {
+            break;
+        }
+        println!();
+    }
+
+    println!("Goodbye!");
+    Ok(())
+}
+
+impl Repl {
+    async fn handle(&mut self, line: &str) -> Flow {
+        let (command, args) = match line.split_once(char::is_whitespace) {
+            Some((command, args)) => (command, args.trim()),
+            None => (line, ""),
+        };
+        let result = match command {
+            "/quit" | "/exit" | "quit" | "exit" => return Flow::Quit,
+            "/help" | "help" => {
+                print_help();
+                return Flow::Continue;
+            }
+            "/info" => self.info(args).await.map(Some),
+            "/compare" => self.compare(args).await.map(Some),
+            "/image" => self.image(args).await.map(Some),
+            "/lang" => self.lang(args).map(|()| None),
+            "/game" => self.game(args).map(|()| None),
+            "/reset" => {
+                self.session.clear();
+                self.transcript.clear();
+                println!("Started a new conversation.");
+                Ok(None)
+            }
+            "/save" => self.save(args).map(|()| None),
+            _ if command.starts_with('/') => Err(PokidexError::InvalidRequest(format!(
+                "unknown command {}; type /help for the list",
+                command
+            ))),
+            _ => self.ask(line).await.map(Some),
+        };
+
+        // Settings commands print their own confirmation and aren't part of
+        // the transcript.
+        match result {
+            Ok(Some(output)) => {
+                self.transcript.push(Turn {
+                    question: line.to_string(),
+                    answer: output,
+                });
+            }
+            Ok(None) => {}
+            Err(e @ PokidexError::NotFound { .. }) => {
+                println!("Sorry, {}. Check the spelling and try again.", e);
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                println!("Please try again or type /help for commands.");
+            }
+        }
+        Flow::Continue
+    }
+
+    async fn ask(&mut self, question: &str) -> Result<String> {
+        let answer = self.session.ask(&self.engine, question).await?;
+        println!("Assistant: {}", markdown::render_for_stdout(&answer.text));
+        if let Some(footer) = format_sources(&answer.sources) {
+            println!("\n{}", footer);
+        }
+        if let Some(sprites) = &self.sprites {
+            show_sprites(&self.engine, sprites, &answer).await;
+        }
+        Ok(answer.text)
+    }
+
+    async fn info(&self, args: &str) -> Result<String> {
+        let name = PokeApiClient::normalize_pokemon_name(args);
+        if name.is_empty() {
+            return Err(usage("/info"));
+        }
+        if let Some(sprites) = &self.sprites {
+            if let Ok(pokemon) = self.engine.data_source().get_pokemon(&name).await {
+                sprites.show(&pokemon).await.ok();
+            }
+        }
+        self.run_tool("get_pokemon", vec![name]).await
+    }
+
+    async fn compare(&self, args: &str) -> Result<String> {
+        let names: Vec<String> = args
+            .split([' ', ','])
+            .map(PokeApiClient::normalize_pokemon_name)
+            .filter(|name| !name.is_empty())
+            .collect();
+        if names.len() < 2 {
+            return Err(usage("/compare"));
+        }
+        self.run_tool("compare_pokemon", names).await
+    }
+
+    /// Runs one of the agent's tools without the model and prints its output.
+    async fn run_tool(&self, tool: &str, names: Vec<String>) -> Result<String> {
+        let call = ToolCall {
+            tool: tool.to_string(),
+            names,
+            args: serde_json::Value::Null,
+        };
+        let output = self.engine.call_tool(&call).await?;
+        println!("{}", output.trim_end());
+        Ok(output)
+    }
+
+    async fn image(&self, args: &str) -> Result<String> {
+        let path = args.trim_matches(|c| c == '"' || c == '\'');
+        if path.is_empty() {
+            return Err(usage("/image"));
+        }
+        let image = load_image(Some(path), false).await?;
+        println!("Analyzing image...");
+        let response = self.engine.process_image(&image).await?;
+        println!("Assistant: {}", markdown::render_for_stdout(&response));
+        Ok(response)
+    }
+
+    fn lang(&mut self, args: &str) -> Result<()> {
+        let message = if args.is_empty() {
+            format!("Language: {}", self.engine.format_options().language)
+        } else {
+            let valid =
+                args.len() <= 10 && args.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+            if !valid {
+                return Err(PokidexError::InvalidRequest(format!(
+                    "'{}' is not a language code (e.g. en, ja, fr, zh-hans)",
+                    args
+                )));
+            }
+            self.engine.set_language(args.to_lowercase());
+            format!("Language set to {}.", args.to_lowercase())
+        };
+        println!("{}", message);
+        Ok(())
+    }
+
+    fn game(&mut self, args: &str) -> Result<()> {
+        let message = match args {
+            "" => match &self.engine.format_options().game_version {
+                Some(game) => format!("Game: {}", game),
+                None => "No preferred game.".to_string(),
+            },
+            "off" | "none" | "any" => {
+                self.engine.set_game_version(None);
+                "No preferred game.".to_string()
+            }
+            game => {
+                let game = PokeApiClient::normalize_pokemon_name(game);
+                self.engine.set_game_version(Some(game.clone()));
+                format!("Preferring {} for flavor text and learnsets.", game)
+            }
+        };
+        println!("{}", message);
+        Ok(())
+    }
+
+    /// Writes the transcript as JSON for `.json` paths, Markdown otherwise.
+    fn save(&self, args: &str) -> Result<()> {
+        let path = Path::new(if args.is_empty() {
+            DEFAULT_TRANSCRIPT
+        } else {
+            args
+        });
+        let text = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::to_string_pretty(&self.transcript)
+                .map_err(|e| PokidexError::ParseFailure(e.to_string()))?
+        } else {
+            self.transcript
+                .iter()
+                .map(|turn| format!("**You:** {}\n\n{}\n", turn.question, turn.answer.trim_end()))
+                .collect::<Vec<_>>()
+                .join("\n---\n\n")
+        };
+        fs::write(path, text)?;
+
+        println!(
+            "Saved {} exchanges to {}.",
+            self.transcript.len(),
+            path.display()
+        );
+        Ok(())
+    }
+}
+
+fn usage(command: &str) -> PokidexError {
+    let args = COMMANDS
+        .iter()
+        .find(|(name, _, _)| *name == command)
+        .map_or("", |(_, args, _)| *args);
+    PokidexError::InvalidRequest(format!("usage: {} {}", command, args))
+}
+
+fn print_help() {
+    println!("Ask me questions about Pokemon! Examples:");
+    println!("  - What are Pikachu's stats?");
+    println!("  - What type is Charizard?");
+    println!("  - What moves can Pikachu learn?");
+    println!("\nCommands:");
+    for (name, args, help) in COMMANDS {
+        println!("  {:<20} {}", format!("{} {}", name, args), help);
+    }
+    println!("\nTab completes commands and Pokémon names; ↑/↓ and Ctrl-R search the history.");
+}
+
+/// Draws the Pokémon the answer looked up. Failures are ignored: the
+/// answer has already been printed.
+async fn show_sprites(engine: &RAGEngine, sprites: &SpriteView, answer: &Answer) {
+    let mut names: Vec<String> = Vec::new();
+    for name in answer.tool_calls.iter().flat_map(|call| &call.names) {
+        let name = PokeApiClient::normalize_pokemon_name(name);
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+
+    for name in names.iter().take(MAX_CHAT_SPRITES) {
+        if let Ok(pokemon) = engine.data_source().get_pokemon(name).await {
+            sprites.show(&pokemon).await.ok();
+        }
+    }
+}
+
+/// The line editor, on a plain thread so a pending read never keeps the
+/// runtime from noticing Ctrl-C or from shutting down. Each `read_line`
+/// asks it for one line.
+struct LineReader {
+    requests: std_mpsc::Sender<()>,
+    lines: mpsc::UnboundedReceiver<rustyline::Result<String>>,
+}
+
+impl LineReader {
+    fn spawn(helper: ReplHelper) -> Self {
+        let (requests, pending) = std_mpsc::channel::<()>();
+        let (tx, lines) = mpsc::unbounded_channel();
+        std::thread::spawn(move || {
+            let mut editor = match new_editor(helper) {
+                Ok(editor) => editor,
+                Err(e) => {
+                    tx.send(Err(e)).ok();
+                    return;
+                }
+            };
+            let history = history_path();
+            if let Some(path) = &history {
+                editor.load_history(path).ok();
+            }
+
+            while pending.recv().is_ok() {
+                let line = editor.readline(PROMPT);
+                if let Ok(line) = &line {
+                    if !line.trim().is_empty() {
+                        editor.add_history_entry(line.trim()).ok();
+                        if let Some(path) = &history {
+                            save_history(&mut editor, path);
+                        }
+                    }
+                }
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Self { requests, lines }
+    }
+
+    /// The next line, or `None` once the editor has gone away.
+    async fn read_line(&mut self) -> Option<rustyline::Result<String>> {
+        self.requests.send(()).ok()?;
+        self.lines.recv().await
+    }
+}
+
+fn new_editor(helper: ReplHelper) -> rustyline::Result<Editor<ReplHelper, FileHistory>> {
+    let config = rustyline::Config::builder()
+        .max_history_size(HISTORY_SIZE)?
+        .history_ignore_dups(true)?
+        .completion_type(CompletionType::List)
+        .build();
+    let mut editor = Editor::with_config(config)?;
+    editor.set_helper(Some(helper));
+    Ok(editor)
+}
+
+/// `$XDG_STATE_HOME/pokidex/history`, falling back to
+/// `~/.local/state/pokidex/history`.
+fn history_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))?;
+    Some(base.join("pokidex").join("history"))
+}
+
+/// Appends the newest entry; history is a convenience, so failures are
+/// ignored.
+fn save_history(editor: &mut Editor<ReplHelper, FileHistory>, path: &Path) {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).ok();
+    }
+    editor.append_history(path).ok();
+}
+
+fn readline_error(e: ReadlineError) -> PokidexError {
+    match e {
+        ReadlineError::Io(e) => PokidexError::Io(e),
+        e => PokidexError::Io(io::Error::other(e)),
+    }
+}
+
+struct ReplHelper {
+    /// Filled in once the name index has loaded.
+    names: Arc<OnceLock<Vec<String>>>,
+    files: FilenameCompleter,
+}
+
+impl Completer for ReplHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        if line.starts_with("/image ") {
+            return self.files.complete(line, pos, ctx);
+        }
+        let names = self.names.get().map_or(&[][..], Vec::as_slice);
+        let (start, words) = complete_word(&line[..pos], names);
+        let pairs = words
+            .into_iter()
+            .map(|word| Pair {
+                display: word.clone(),
+                replacement: word,
+            })
+            .collect();
+        Ok((start, pairs))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+/// Completions for the word ending at the cursor: slash commands at the
+/// start of the line, Pokémon names anywhere else. Returns where the word
+/// starts along with the candidates.
+fn complete_word(line: &str, names: &[String]) -> (usize, Vec<String>) {
+    let start = line
+        .char_indices()
+        .rev()
+        .find(|(_, c)| c.is_whitespace())
+        .map_or(0, |(i, c)| i + c.len_utf8());
+    let word = &line[start..];
+
+    if start == 0 && word.starts_with('/') {
+        let commands = COMMANDS
+            .iter()
+            .filter(|(name, _, _)| name.starts_with(word))
+            .map(|(name, _, _)| name.to_string())
+            .collect();
+        return (start, commands);
+    }
+    if word.is_empty() {
+        return (start, Vec::new());
+    }
+
+    let prefix = word.to_lowercase();
+    let matches = names
+        .iter()
+        .filter(|name| name.starts_with(&prefix))
+        .take(MAX_COMPLETIONS)
+        .cloned()
+        .collect();
+    (start, matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::complete_word;
+
+    #[test]
+    fn completes_commands_and_names() {
+        let names: Vec<String> = ["pikachu", "pichu", "raichu"].map(String::from).to_vec();
+
+        assert_eq!(
+            complete_word("/co", &names),
+            (0, vec!["/compare".to_string()])
+        );
+        assert_eq!(
+            complete_word("/compare Pi", &names),
+            (9, vec!["pikachu".to_string(), "pichu".to_string()])
+        );
+        assert_eq!(complete_word("what evolves from rai", &names).1, ["raichu"]);
+        assert!(complete_word("what ", &names).1.is_empty());
+    }
+}
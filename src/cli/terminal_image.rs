@@ -0,0 +1,313 @@
+//! Draws sprites inline in the terminal for `info --sprite` and
+//! `chat --sprites`.
+
+use base64::Engine;
+use image::imageops::FilterType;
+use image::{DynamicImage, RgbaImage};
+use pokidex::sprites::{self, SpriteCache, SpriteKind};
+use pokidex::{PokidexError, Result, SpriteProtocol};
+use rustemon::model::pokemon::Pokemon;
+use std::env;
+use std::fmt::Write as _;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+
+/// Width of a drawn sprite in terminal columns.
+const SPRITE_COLUMNS: u32 = 32;
+
+/// Pixel width used for Sixel output, where we pick the size ourselves.
+const SIXEL_WIDTH: u32 = 256;
+
+/// Kitty takes base64 payloads in chunks of at most this many bytes.
+const KITTY_CHUNK: usize = 4096;
+
+/// Alpha below which a pixel counts as transparent.
+const OPAQUE: u8 = 128;
+
+/// Fetches and draws sprites for one CLI run.
+pub struct SpriteView {
+    cache: SpriteCache,
+    protocol: SpriteProtocol,
+    kind: SpriteKind,
+}
+
+impl SpriteView {
+    /// `None` when stdout isn't a terminal: escape sequences would only end
+    /// up as noise in a file or pipe.
+    pub fn new(cache_dir: &Path, protocol: SpriteProtocol, kind: SpriteKind) -> Option<Self> {
+        if !io::stdout().is_terminal() {
+            return None;
+        }
+        Some(Self {
+            cache: SpriteCache::new(cache_dir),
+            protocol: detect(protocol),
+            kind,
+        })
+    }
+
+    /// Prints the sprite for `pokemon`, followed by a blank line.
+    pub async fn show(&self, pokemon: &Pokemon) -> Result<()> {
+        let url =
+            sprites::sprite_url(pokemon, self.kind).ok_or_else(|| PokidexError::NotFound {
+                kind: "sprite",
+                name: pokemon.name.clone(),
+            })?;
+        let png = self.cache.get(url).await?;
+        let columns = match ratatui::crossterm::terminal::size() {
+            Ok((width, _)) => SPRITE_COLUMNS.min(u32::from(width)),
+            Err(_) => SPRITE_COLUMNS,
+        };
+
+        let drawn = render(&png, self.protocol, columns)?;
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(drawn.as_bytes())?;
+        writeln!(stdout)?;
+        stdout.flush()?;
+        Ok(())
+    }
+}
+
+/// Resolves [`SpriteProtocol::Auto`] from the terminal's environment.
+/// Inside tmux or screen only half blocks pass through reliably.
+fn detect(configured: SpriteProtocol) -> SpriteProtocol {
+    if configured != SpriteProtocol::Auto {
+        return configured;
+    }
+    let var = |key: &str| env::var(key).unwrap_or_default();
+    let term = var("TERM");
+    let program = var("TERM_PROGRAM");
+
+    if env::var_os("TMUX").is_some() || term.starts_with("screen") {
+        SpriteProtocol::Blocks
+    } else if env::var_os("KITTY_WINDOW_ID").is_some()
+        || term == "xterm-kitty"
+        || program == "ghostty"
+    {
+        SpriteProtocol::Kitty
+    } else if program == "iTerm.app" || program == "WezTerm" {
+        SpriteProtocol::Iterm
+    } else if term.contains("sixel") || term.starts_with("foot") || term.starts_with("mlterm") {
+        SpriteProtocol::Sixel
+    } else {
+        SpriteProtocol::Blocks
+    }
+}
+
+/// The escape sequences (or text) that draw `png` about `columns` wide.
+fn render(png: &[u8], protocol: SpriteProtocol, columns: u32) -> Result<String> {
+    let b64 = base64::engine::general_purpose::STANDARD;
+    match protocol {
+        SpriteProtocol::Kitty => {
+            let payload = b64.encode(png);
+            let chunks: Vec<&[u8]> = payload.as_bytes().chunks(KITTY_CHUNK).collect();
+            let mut out = String::new();
+            for (i, chunk) in chunks.iter().enumerate() {
+                let more = u8::from(i + 1 < chunks.len());
+                let chunk = std::str::from_utf8(chunk).unwrap_or_default();
+                if i == 0 {
+                    write!(
+                        out,
+                        "\x1b_Ga=T,f=100,q=2,c={},m={};{}\x1b\\",
+                        columns, more, chunk
+                    )
+                } else {
+                    write!(out, "\x1b_Gm={};{}\x1b\\", more, chunk)
+                }
+                .ok();
+            }
+            Ok(out)
+        }
+        SpriteProtocol::Iterm => Ok(format!(
+            "\x1b]1337;File=inline=1;size={};width={};preserveAspectRatio=1:{}\x07",
+            png.len(),
+            columns,
+            b64.encode(png)
+        )),
+        SpriteProtocol::Sixel => {
+            let image = trimmed(png)?;
+            let image = image.resize(SIXEL_WIDTH, SIXEL_WIDTH, FilterType::Nearest);
+            Ok(sixel(&image.to_rgba8()))
+        }
+        SpriteProtocol::Blocks | SpriteProtocol::Auto => {
+            Ok(half_blocks(&sprite_cells(png, columns)?))
+        }
+    }
+}
+
+/// Decodes `png` and crops the transparent margin most sprites have.
+fn trimmed(png: &[u8]) -> Result<DynamicImage> {
+    let image =
+        image::load_from_memory(png).map_err(|e| PokidexError::InvalidImage(e.to_string()))?;
+    let rgba = image.to_rgba8();
+    let opaque = rgba.enumerate_pixels().filter(|(_, _, p)| p[3] >= OPAQUE);
+    let (mut left, mut top, mut right, mut bottom) = (u32::MAX, u32::MAX, 0, 0);
+    for (x, y, _) in opaque {
+        left = left.min(x);
+        top = top.min(y);
+        right = right.max(x);
+        bottom = bottom.max(y);
+    }
+    if left > right {
+        return Ok(image);
+    }
+    Ok(image.crop_imm(left, top, right - left + 1, bottom - top + 1))
+}
+
+/// Colour of a pixel, `None` where it's transparent.
+pub type Pixel = Option<[u8; 3]>;
+
+/// A sprite as rows of character cells, each holding the colours of its top
+/// and bottom half. Used by the TUI, which draws through ratatui instead of
+/// escape sequences.
+pub fn sprite_cells(png: &[u8], columns: u32) -> Result<Vec<Vec<(Pixel, Pixel)>>> {
+    let image = trimmed(png)?;
+    // Each cell holds two pixels stacked, so a square sprite comes out
+    // square.
+    let image = image.resize(columns, columns * 2, FilterType::Triangle);
+    Ok(cells(&image.to_rgba8()))
+}
+
+/// Pairs pixel rows into character cells.
+fn cells(image: &RgbaImage) -> Vec<Vec<(Pixel, Pixel)>> {
+    let pixel = |x: u32, y: u32| {
+        let p = image.get_pixel(x, y);
+        (p[3] >= OPAQUE).then_some([p[0], p[1], p[2]])
+    };
+    (0..image.height())
+        .step_by(2)
+        .map(|y| {
+            (0..image.width())
+                .map(|x| {
+                    let bottom = if y + 1 < image.height() {
+                        pixel(x, y + 1)
+                    } else {
+                        None
+                    };
+                    (pixel(x, y), bottom)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// One line per row of cells: `▀` with the top pixel as foreground and the
+/// bottom one as background, leaving transparent cells blank.
+fn half_blocks(cells: &[Vec<(Pixel, Pixel)>]) -> String {
+    let mut out = String::new();
+    for row in cells {
+        for cell in row {
+            match cell {
+                (Some(t), Some(b)) => write!(
+                    out,
+                    "\x1b[38;2;{};{};{};48;2;{};{};{}m▀",
+                    t[0], t[1], t[2], b[0], b[1], b[2]
+                ),
+                (Some(t), None) => write!(out, "\x1b[38;2;{};{};{}m▀", t[0], t[1], t[2]),
+                (None, Some(b)) => write!(out, "\x1b[38;2;{};{};{}m▄", b[0], b[1], b[2]),
+                (None, None) => {
+                    out.push(' ');
+                    continue;
+                }
+            }
+            .ok();
+            out.push_str("\x1b[0m");
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Sixel image using a 6×6×6 colour cube; transparent pixels are left
+/// unpainted.
+fn sixel(image: &RgbaImage) -> String {
+    let (width, height) = image.dimensions();
+    let level = |c: u8| (u16::from(c) * 5 + 127) / 255;
+    let colour = |x: u32, y: u32| {
+        let p = image.get_pixel(x, y);
+        (p[3] >= OPAQUE).then(|| (level(p[0]) * 36 + level(p[1]) * 6 + level(p[2])) as usize)
+    };
+
+    // P2=1 keeps unpainted pixels transparent.
+    let mut out = format!("\x1bP0;1q\"1;1;{};{}", width, height);
+    for i in 0..216u16 {
+        let percent = |v: u16| v * 100 / 5;
+        write!(
+            out,
+            "#{};2;{};{};{}",
+            i,
+            percent(i / 36),
+            percent(i / 6 % 6),
+            percent(i % 6)
+        )
+        .ok();
+    }
+
+    for band in (0..height).step_by(6) {
+        let rows = (band..height.min(band + 6)).collect::<Vec<_>>();
+        let mut used = [false; 216];
+        for x in 0..width {
+            for &y in &rows {
+                if let Some(c) = colour(x, y) {
+                    used[c] = true;
+                }
+            }
+        }
+        for c in (0..216).filter(|&c| used[c]) {
+            write!(out, "#{}", c).ok();
+            let columns: Vec<u8> = (0..width)
+                .map(|x| {
+                    let bits = rows
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, &y)| colour(x, y) == Some(c))
+                        .fold(0u8, |bits, (bit, _)| bits | (1 << bit));
+                    b'?' + bits
+                })
+                .collect();
+            push_run_length(&mut out, &columns);
+            out.push('$');
+        }
+        out.push('-');
+    }
+    out.push_str("\x1b\\");
+    out
+}
+
+/// Appends sixel characters, folding runs into `!<count><char>`.
+fn push_run_length(out: &mut String, columns: &[u8]) {
+    let mut i = 0;
+    while i < columns.len() {
+        let run = columns[i..]
+            .iter()
+            .take_while(|&&c| c == columns[i])
+            .count();
+        if run > 3 {
+            write!(out, "!{}{}", run, columns[i] as char).ok();
+        } else {
+            out.extend(std::iter::repeat_n(columns[i] as char, run));
+        }
+        i += run;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cells, half_blocks, push_run_length};
+    use image::{Rgba, RgbaImage};
+
+    #[test]
+    fn draws_half_blocks_and_sixel_runs() {
+        let mut image = RgbaImage::new(2, 2);
+        image.put_pixel(0, 0, Rgba([255, 0, 0, 255]));
+        image.put_pixel(0, 1, Rgba([0, 0, 255, 255]));
+        image.put_pixel(1, 1, Rgba([0, 255, 0, 255]));
+        assert_eq!(
+            half_blocks(&cells(&image)),
+            "\x1b[38;2;255;0;0;48;2;0;0;255m▀\x1b[0m\x1b[38;2;0;255;0m▄\x1b[0m\n"
+        );
+
+        let mut out = String::new();
+        push_run_length(&mut out, b"~~~~~??@");
+        assert_eq!(out, "!5~??@");
+    }
+}
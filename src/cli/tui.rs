@@ -0,0 +1,750 @@
+//! `pokidex tui`: a searchable Pokédex list, the selected Pokémon's entry
+//! and a chat pane that asks the engine about it.
+
+use super::file_browser::rank;
+use super::terminal_image::{self, Pixel};
+use pokidex::format::{self, FormatOptions};
+use pokidex::sprites::{self, SpriteCache, SpriteKind};
+use pokidex::{ChatSession, PokeApiClient, PokidexError, RAGEngine, Result};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Position, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use rustemon::model::pokemon::{Pokemon, PokemonSpecies};
+use std::collections::{HashMap, HashSet};
+use std::io::{self, IsTerminal};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Mutex};
+
+/// Width of the sprite in the detail pane, in columns.
+const SPRITE_COLUMNS: u32 = 24;
+
+/// Width of the list column.
+const LIST_WIDTH: u16 = 28;
+
+/// How long the selection has to rest before its entry is fetched, so
+/// scrolling through the list doesn't start a lookup per row.
+const SETTLE: Duration = Duration::from_millis(150);
+
+/// How often the screen is refreshed while waiting for input.
+const TICK: Duration = Duration::from_millis(50);
+
+/// Rows moved by PageUp/PageDown.
+const PAGE: usize = 10;
+
+/// Characters in a full stat bar, and the base stat that fills it.
+const STAT_BAR_WIDTH: usize = 20;
+const MAX_BASE_STAT: i64 = 255;
+
+type Cells = Vec<Vec<(Pixel, Pixel)>>;
+
+/// Results of work spawned off the UI loop.
+enum Message {
+    Names(Result<Vec<String>>),
+    Detail(String, Result<Box<Detail>>),
+    Answer(Result<String>),
+}
+
+struct Detail {
+    pokemon: Pokemon,
+    species: Option<PokemonSpecies>,
+    evolution: Option<String>,
+    sprite: Option<Cells>,
+}
+
+#[derive(PartialEq, Eq)]
+enum Focus {
+    List,
+    Chat,
+}
+
+enum ChatLine {
+    Question(String),
+    Answer(String),
+    Error(String),
+}
+
+struct App {
+    engine: Arc<RAGEngine>,
+    sprites: Arc<SpriteCache>,
+    session: Arc<Mutex<ChatSession>>,
+    options: FormatOptions,
+    tx: mpsc::UnboundedSender<Message>,
+
+    /// `None` until the name list has arrived.
+    names: Option<Vec<String>>,
+    names_error: Option<String>,
+    query: String,
+    visible: Vec<(usize, Vec<usize>)>,
+    list: ListState,
+    /// A name typed into the search box and looked up directly, for when
+    /// the list can't be loaded or doesn't have it.
+    lookup: Option<String>,
+    selected_at: Instant,
+
+    details: HashMap<String, std::result::Result<Box<Detail>, String>>,
+    loading: HashSet<String>,
+
+    focus: Focus,
+    chat: Vec<ChatLine>,
+    input: String,
+    pending: bool,
+    /// Chat lines scrolled up from the bottom.
+    chat_scroll: usize,
+}
+
+/// Runs the TUI until the user quits.
+pub async fn run(engine: RAGEngine, cache_dir: &Path) -> Result<()> {
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Err(PokidexError::InvalidRequest(
+            "pokidex tui needs an interactive terminal".to_string(),
+        ));
+    }
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut app = App::new(engine, cache_dir, tx);
+    app.load_names();
+
+    let mut terminal = ratatui::try_init()?;
+    let result = app.run(&mut terminal, &mut rx).await;
+    ratatui::restore();
+    result
+}
+
+impl App {
+    fn new(engine: RAGEngine, cache_dir: &Path, tx: mpsc::UnboundedSender<Message>) -> Self {
+        Self {
+            options: engine.format_options().clone(),
+            engine: Arc::new(engine),
+            sprites: Arc::new(SpriteCache::new(cache_dir)),
+            session: Arc::new(Mutex::new(ChatSession::new())),
+            tx,
+            names: None,
+            names_error: None,
+            query: String::new(),
+            visible: Vec::new(),
+            list: ListState::default(),
+            lookup: None,
+            selected_at: Instant::now(),
+            details: HashMap::new(),
+            loading: HashSet::new(),
+            focus: Focus::List,
+            chat: Vec::new(),
+            input: String::new(),
+            pending: false,
+            chat_scroll: 0,
+        }
+    }
+
+    async fn run(
+        &mut self,
+        terminal: &mut DefaultTerminal,
+        rx: &mut mpsc::UnboundedReceiver<Message>,
+    ) -> Result<()> {
+        loop {
+            self.load_selected();
+            terminal.draw(|frame| self.draw(frame))?;
+
+            // Keys first, so typing never waits for the next tick.
+            if event::poll(Duration::ZERO)? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press && self.handle_key(key) {
+                        return Ok(());
+                    }
+                }
+                continue;
+            }
+            tokio::select! {
+                Some(message) = rx.recv() => self.receive(message),
+                _ = tokio::time::sleep(TICK) => {}
+            }
+        }
+    }
+
+    fn load_names(&self) {
+        let engine = Arc::clone(&self.engine);
+        let tx = self.tx.clone();
+        tokio::spawn(async move {
+            let names = engine.data_source().pokemon_names().await;
+            tx.send(Message::Names(names)).ok();
+        });
+    }
+
+    /// Fetches the selected Pokémon's entry once the selection has settled.
+    fn load_selected(&mut self) {
+        let Some(name) = self.selected_name() else {
+            return;
+        };
+        if self.details.contains_key(&name)
+            || self.loading.contains(&name)
+            || self.selected_at.elapsed() < SETTLE
+        {
+            return;
+        }
+        self.loading.insert(name.clone());
+
+        let engine = Arc::clone(&self.engine);
+        let sprites = Arc::clone(&self.sprites);
+        let tx = self.tx.clone();
+        tokio::spawn(async move {
+            let detail = fetch_detail(&engine, &sprites, &name).await;
+            tx.send(Message::Detail(name, detail)).ok();
+        });
+    }
+
+    fn receive(&mut self, message: Message) {
+        match message {
+            Message::Names(Ok(names)) if names.is_empty() => {
+                self.names_error = Some("this data source can't list Pokémon".to_string());
+                self.names = Some(names);
+            }
+            Message::Names(Ok(names)) => {
+                self.names = Some(names);
+                self.refilter();
+            }
+            Message::Names(Err(e)) => {
+                self.names_error = Some(e.to_string());
+                self.names = Some(Vec::new());
+            }
+            Message::Detail(name, detail) => {
+                self.loading.remove(&name);
+                self.details.insert(name, detail.map_err(|e| e.to_string()));
+            }
+            Message::Answer(answer) => {
+                self.pending = false;
+                self.chat_scroll = 0;
+                self.chat.push(match answer {
+                    Ok(text) => ChatLine::Answer(text),
+                    Err(e) => ChatLine::Error(e.to_string()),
+                });
+            }
+        }
+    }
+
+    /// Handles one key press; `true` means quit.
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Char('c') if ctrl => return true,
+            KeyCode::Tab | KeyCode::BackTab => {
+                self.focus = match self.focus {
+                    Focus::List => Focus::Chat,
+                    Focus::Chat => Focus::List,
+                };
+                return false;
+            }
+            _ => {}
+        }
+        match self.focus {
+            Focus::List => self.handle_list_key(key, ctrl),
+            Focus::Chat => {
+                self.handle_chat_key(key, ctrl);
+                false
+            }
+        }
+    }
+
+    fn handle_list_key(&mut self, key: KeyEvent, ctrl: bool) -> bool {
+        match key.code {
+            KeyCode::Esc if self.query.is_empty() => return true,
+            KeyCode::Esc => {
+                self.query.clear();
+                self.refilter();
+            }
+            KeyCode::Char('u') if ctrl => {
+                self.query.clear();
+                self.refilter();
+            }
+            KeyCode::Enter => {
+                if self.visible.is_empty() && !self.query.is_empty() {
+                    self.lookup = Some(PokeApiClient::normalize_pokemon_name(&self.query));
+                    self.selected_at = Instant::now();
+                } else {
+                    self.focus = Focus::Chat;
+                }
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.refilter();
+            }
+            KeyCode::Up => self.move_by(-1),
+            KeyCode::Down => self.move_by(1),
+            KeyCode::PageUp => self.move_by(-(PAGE as isize)),
+            KeyCode::PageDown => self.move_by(PAGE as isize),
+            KeyCode::Home => self.move_by(isize::MIN),
+            KeyCode::End => self.move_by(isize::MAX),
+            KeyCode::Char(c) if !ctrl => {
+                self.query.push(c);
+                self.refilter();
+            }
+            _ => {}
+        }
+        false
+    }
+
+    fn handle_chat_key(&mut self, key: KeyEvent, ctrl: bool) {
+        match key.code {
+            KeyCode::Esc => self.focus = Focus::List,
+            KeyCode::Char('u') if ctrl => self.input.clear(),
+            KeyCode::Enter => self.ask(),
+            KeyCode::Backspace => {
+                self.input.pop();
+            }
+            KeyCode::PageUp => self.chat_scroll += PAGE,
+            KeyCode::PageDown => self.chat_scroll = self.chat_scroll.saturating_sub(PAGE),
+            KeyCode::Char(c) if !ctrl => self.input.push(c),
+            _ => {}
+        }
+    }
+
+    /// Sends the typed question, about the selected Pokémon, to the engine.
+    fn ask(&mut self) {
+        let question = self.input.trim().to_string();
+        if question.is_empty() || self.pending {
+            return;
+        }
+        self.input.clear();
+        self.pending = true;
+        self.chat_scroll = 0;
+        self.chat.push(ChatLine::Question(question.clone()));
+
+        let question = match self.selected_name() {
+            Some(name) => about(&name, &question),
+            None => question,
+        };
+        let engine = Arc::clone(&self.engine);
+        let session = Arc::clone(&self.session);
+        let tx = self.tx.clone();
+        tokio::spawn(async move {
+            let mut session = session.lock().await;
+            let answer = session.ask(&engine, &question).await;
+            tx.send(Message::Answer(answer.map(|a| a.text))).ok();
+        });
+    }
+
+    fn selected_name(&self) -> Option<String> {
+        let from_list = self.list.selected().and_then(|i| {
+            let (index, _) = self.visible.get(i)?;
+            self.names.as_ref()?.get(*index).cloned()
+        });
+        from_list.or_else(|| self.lookup.clone())
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        if self.visible.is_empty() {
+            return;
+        }
+        let current = self.list.selected().unwrap_or(0) as isize;
+        let last = self.visible.len() as isize - 1;
+        let next = current.saturating_add(delta).clamp(0, last);
+        self.list.select(Some(next as usize));
+        self.selected_at = Instant::now();
+    }
+
+    fn refilter(&mut self) {
+        let names: Vec<&str> = self.names.iter().flatten().map(String::as_str).collect();
+        self.visible = rank(&names, &self.query);
+        self.list.select(if self.visible.is_empty() {
+            None
+        } else {
+            Some(0)
+        });
+        self.selected_at = Instant::now();
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, help] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+        let [left, right] =
+            Layout::horizontal([Constraint::Length(LIST_WIDTH), Constraint::Min(20)]).areas(main);
+        let [detail, chat] =
+            Layout::vertical([Constraint::Percentage(60), Constraint::Min(8)]).areas(right);
+
+        self.draw_list(frame, left);
+        self.draw_detail(frame, detail);
+        self.draw_chat(frame, chat);
+
+        let keys = match self.focus {
+            Focus::List => "type to search · ↑↓ move · Enter chat · Tab switch pane · Esc quit",
+            Focus::Chat => "Enter ask · PgUp/PgDn scroll · Tab/Esc back to list · Ctrl-C quit",
+        };
+        frame.render_widget(Line::from(keys).dim(), help);
+    }
+
+    fn draw_list(&mut self, frame: &mut Frame, area: Rect) {
+        let [search, list] =
+            Layout::vertical([Constraint::Length(3), Constraint::Min(1)]).areas(area);
+
+        let prompt = Line::from(vec!["> ".bold(), Span::raw(&self.query)]);
+        frame.render_widget(
+            Paragraph::new(prompt).block(focused_block(" Pokédex ", self.focus == Focus::List)),
+            search,
+        );
+        if self.focus == Focus::List {
+            frame.set_cursor_position(Position::new(
+                search.x + 3 + self.query.chars().count() as u16,
+                search.y + 1,
+            ));
+        }
+
+        let Some(names) = &self.names else {
+            frame.render_widget(Paragraph::new("Loading…").block(Block::bordered()), list);
+            return;
+        };
+        if let Some(error) = &self.names_error {
+            let text = vec![
+                Line::from(error.as_str()).red(),
+                Line::from(""),
+                Line::from("Type a name and press Enter to look it up."),
+            ];
+            frame.render_widget(
+                Paragraph::new(text)
+                    .wrap(Wrap { trim: true })
+                    .block(Block::bordered()),
+                list,
+            );
+            return;
+        }
+
+        let items: Vec<ListItem> = self
+            .visible
+            .iter()
+            .map(|(index, matched)| name_line(&names[*index], matched))
+            .collect();
+        let counts = format!(" {} of {} ", self.visible.len(), names.len());
+        let widget = List::new(items)
+            .block(Block::bordered().title(counts))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(widget, list, &mut self.list);
+    }
+
+    fn draw_detail(&self, frame: &mut Frame, area: Rect) {
+        let Some(name) = self.selected_name() else {
+            frame.render_widget(Block::bordered(), area);
+            return;
+        };
+        let detail = match self.details.get(&name) {
+            Some(Ok(detail)) => detail,
+            Some(Err(error)) => {
+                let text = Paragraph::new(Line::from(error.as_str()).red())
+                    .wrap(Wrap { trim: true })
+                    .block(Block::bordered().title(format!(" {} ", name)));
+                frame.render_widget(text, area);
+                return;
+            }
+            None => {
+                let text = Paragraph::new("Loading…")
+                    .block(Block::bordered().title(format!(" {} ", name)));
+                frame.render_widget(text, area);
+                return;
+            }
+        };
+
+        let pokemon = &detail.pokemon;
+        let block = Block::bordered().title(format!(" #{:03} {} ", pokemon.id, pokemon.name));
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let sprite_width = if detail.sprite.is_some() {
+            SPRITE_COLUMNS as u16 + 1
+        } else {
+            0
+        };
+        let [sprite, text] =
+            Layout::horizontal([Constraint::Length(sprite_width), Constraint::Min(10)])
+                .areas(inner);
+        if let Some(cells) = &detail.sprite {
+            frame.render_widget(Paragraph::new(sprite_lines(cells)), sprite);
+        }
+        frame.render_widget(
+            Paragraph::new(self.detail_lines(detail)).wrap(Wrap { trim: false }),
+            text,
+        );
+    }
+
+    fn detail_lines<'a>(&self, detail: &'a Detail) -> Vec<Line<'a>> {
+        let pokemon = &detail.pokemon;
+        let types: Vec<&str> = pokemon
+            .types
+            .iter()
+            .map(|t| t.type_.name.as_str())
+            .collect();
+        let abilities: Vec<String> = pokemon
+            .abilities
+            .iter()
+            .map(|a| {
+                if a.is_hidden {
+                    format!("{} (hidden)", a.ability.name)
+                } else {
+                    a.ability.name.clone()
+                }
+            })
+            .collect();
+
+        let mut lines = vec![
+            Line::from(vec!["Types: ".bold(), Span::raw(types.join(" / "))]),
+            Line::from(vec!["Abilities: ".bold(), Span::raw(abilities.join(", "))]),
+            Line::from(format!(
+                "Height {:.1} m · Weight {:.1} kg",
+                pokemon.height as f32 / 10.0,
+                pokemon.weight as f32 / 10.0
+            )),
+            Line::from(""),
+        ];
+
+        let mut total = 0;
+        for stat in &pokemon.stats {
+            total += stat.base_stat;
+            lines.push(stat_line(&stat.stat.name, stat.base_stat));
+        }
+        lines.push(Line::from(format!("{:<4} {:>3}", "Tot", total)).bold());
+
+        if let Some(evolution) = &detail.evolution {
+            lines.push(Line::from(""));
+            lines.push(Line::from("Evolution").bold());
+            lines.extend(evolution.lines().map(Line::from));
+        }
+        if let Some(flavor) = detail
+            .species
+            .as_ref()
+            .and_then(|s| self.options.flavor_text(s))
+        {
+            lines.push(Line::from(""));
+            lines.push(Line::from(flavor).italic());
+        }
+        lines
+    }
+
+    fn draw_chat(&self, frame: &mut Frame, area: Rect) {
+        let [history, input] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(3)]).areas(area);
+
+        let title = match self.selected_name() {
+            Some(name) => format!(" Ask about {} ", name),
+            None => " Ask ".to_string(),
+        };
+        let block = Block::bordered().title(title);
+        let inner = block.inner(history);
+        frame.render_widget(block, history);
+
+        // Wrapped by hand so the newest line can be kept at the bottom.
+        let width = inner.width.max(1) as usize;
+        let mut lines: Vec<Line> = Vec::new();
+        for entry in &self.chat {
+            let (prefix, text, style) = match entry {
+                ChatLine::Question(text) => ("You: ", text, Style::new().cyan().bold()),
+                ChatLine::Answer(text) => ("", text, Style::new()),
+                ChatLine::Error(text) => ("Error: ", text, Style::new().red()),
+            };
+            let text = format!("{}{}", prefix, text);
+            lines.extend(
+                wrap(&text, width)
+                    .into_iter()
+                    .map(|l| Line::styled(l, style)),
+            );
+            lines.push(Line::from(""));
+        }
+        if self.pending {
+            lines.push(Line::from("Thinking…").dim());
+        }
+
+        let height = inner.height as usize;
+        let bottom = lines
+            .len()
+            .saturating_sub(self.chat_scroll.min(lines.len()));
+        let top = bottom.saturating_sub(height);
+        frame.render_widget(Paragraph::new(lines[top..bottom].to_vec()), inner);
+
+        let focused = self.focus == Focus::Chat;
+        frame.render_widget(
+            Paragraph::new(self.input.as_str()).block(focused_block(" Question ", focused)),
+            input,
+        );
+        if focused {
+            frame.set_cursor_position(Position::new(
+                input.x + 1 + self.input.chars().count() as u16,
+                input.y + 1,
+            ));
+        }
+    }
+}
+
+/// Loads everything the detail pane shows. Only the Pokémon itself is
+/// required; the rest is left out when it can't be had.
+async fn fetch_detail(
+    engine: &RAGEngine,
+    sprites: &SpriteCache,
+    name: &str,
+) -> Result<Box<Detail>> {
+    let source = engine.data_source();
+    let (pokemon, species) = source.get_pokemon_with_species(name).await?;
+
+    let evolution = async {
+        let chain = source.get_evolution_chain(species.as_ref()?).await.ok()?;
+        Some(format::format_evolution_chain(&chain))
+    };
+    let sprite = async {
+        let url = sprites::sprite_url(&pokemon, SpriteKind::Artwork)?;
+        let png = sprites.get(url).await.ok()?;
+        terminal_image::sprite_cells(&png, SPRITE_COLUMNS).ok()
+    };
+    let (evolution, sprite) = tokio::join!(evolution, sprite);
+
+    Ok(Box::new(Detail {
+        pokemon,
+        species,
+        evolution,
+        sprite,
+    }))
+}
+
+/// Adds the selected Pokémon to a question that doesn't already name it,
+/// so "what does it evolve into?" is asked about the right one.
+fn about(name: &str, question: &str) -> String {
+    let lower = question.to_lowercase();
+    if lower.contains(name) || lower.contains(&name.replace('-', " ")) {
+        question.to_string()
+    } else {
+        format!("About {}: {}", name, question)
+    }
+}
+
+fn focused_block(title: &str, focused: bool) -> Block<'_> {
+    let block = Block::bordered().title(title);
+    if focused {
+        block.border_style(Style::new().fg(Color::Yellow))
+    } else {
+        block
+    }
+}
+
+fn name_line<'a>(name: &'a str, matched: &[usize]) -> ListItem<'a> {
+    let spans: Vec<Span> = name
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            if matched.contains(&i) {
+                Span::styled(c.to_string(), Style::new().yellow().bold())
+            } else {
+                Span::raw(c.to_string())
+            }
+        })
+        .collect();
+    ListItem::new(Line::from(spans))
+}
+
+/// `Atk  84 ███████░░░░` with the bar coloured by how high the stat is.
+fn stat_line(stat: &str, value: i64) -> Line<'static> {
+    let label = match stat {
+        "hp" => "HP",
+        "attack" => "Atk",
+        "defense" => "Def",
+        "special-attack" => "SpA",
+        "special-defense" => "SpD",
+        "speed" => "Spe",
+        other => other,
+    };
+    let filled =
+        (value.clamp(0, MAX_BASE_STAT) as usize * STAT_BAR_WIDTH).div_ceil(MAX_BASE_STAT as usize);
+    let colour = match value {
+        ..=49 => Color::Red,
+        50..=79 => Color::LightRed,
+        80..=99 => Color::Yellow,
+        100..=129 => Color::Green,
+        _ => Color::Cyan,
+    };
+    Line::from(vec![
+        Span::raw(format!("{:<4} {:>3} ", label, value)),
+        Span::styled("█".repeat(filled), Style::new().fg(colour)),
+        Span::styled(
+            "░".repeat(STAT_BAR_WIDTH - filled),
+            Style::new().fg(Color::DarkGray),
+        ),
+    ])
+}
+
+fn sprite_lines(cells: &Cells) -> Vec<Line<'static>> {
+    let rgb = |[r, g, b]: [u8; 3]| Color::Rgb(r, g, b);
+    cells
+        .iter()
+        .map(|row| {
+            let spans: Vec<Span> = row
+                .iter()
+                .map(|cell| match *cell {
+                    (Some(top), Some(bottom)) => {
+                        Span::styled("▀", Style::new().fg(rgb(top)).bg(rgb(bottom)))
+                    }
+                    (Some(top), None) => Span::styled("▀", Style::new().fg(rgb(top))),
+                    (None, Some(bottom)) => Span::styled("▄", Style::new().fg(rgb(bottom))),
+                    (None, None) => Span::raw(" "),
+                })
+                .collect();
+            Line::from(spans)
+        })
+        .collect()
+}
+
+/// Greedy word wrap to `width` characters, keeping the text's own line
+/// breaks. Words longer than a line are split.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let mut word = word;
+            loop {
+                let used = line.chars().count();
+                let needed = word.chars().count() + usize::from(used > 0);
+                if used + needed <= width {
+                    if used > 0 {
+                        line.push(' ');
+                    }
+                    line.push_str(word);
+                    break;
+                }
+                if used > 0 {
+                    lines.push(std::mem::take(&mut line));
+                    continue;
+                }
+                let split = word
+                    .char_indices()
+                    .nth(width)
+                    .map_or(word.len(), |(i, _)| i);
+                lines.push(word[..split].to_string());
+                word = &word[split..];
+                if word.is_empty() {
+                    break;
+                }
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{about, wrap};
+
+    #[test]
+    fn wraps_chat_text_and_adds_context() {
+        assert_eq!(
+            wrap("Charizard is a Fire/Flying type\n\nabcdefghij", 12),
+            ["Charizard is", "a", "Fire/Flying", "type", "", "abcdefghij"]
+        );
+        assert_eq!(wrap("abcdefghijklmno", 6), ["abcdef", "ghijkl", "mno"]);
+
+        assert_eq!(
+            about("mr-mime", "What type is Mr Mime?"),
+            "What type is Mr Mime?"
+        );
+        assert_eq!(
+            about("pikachu", "What does it evolve into?"),
+            "About pikachu: What does it evolve into?"
+        );
+    }
+}
@@ -0,0 +1,321 @@
+use crate::error::{PokidexError, Result};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+const CONFIG_FILE_NAME: &str = "pokidex.toml";
+
+/// Which language model backend answers questions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    #[default]
+    Gemini,
+}
+
+impl FromStr for Backend {
+    type Err = PokidexError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "gemini" => Ok(Backend::Gemini),
+            other => Err(PokidexError::Config(format!("unknown backend '{}'", other))),
+        }
+    }
+}
+
+/// A way for `select-image` to ask for a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Picker {
+    /// The macOS or Windows open dialog.
+    System,
+    Zenity,
+    Kdialog,
+    /// The built-in file browser, for terminals without a desktop.
+    Terminal,
+}
+
+impl FromStr for Picker {
+    type Err = PokidexError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "system" => Ok(Picker::System),
+            "zenity" => Ok(Picker::Zenity),
+            "kdialog" => Ok(Picker::Kdialog),
+            "terminal" => Ok(Picker::Terminal),
+            other => Err(PokidexError::Config(format!(
+                "unknown file picker '{}' (expected system, zenity, kdialog or terminal)",
+                other
+            ))),
+        }
+    }
+}
+
+/// How sprites are drawn in the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpriteProtocol {
+    /// Pick from the terminal's environment variables.
+    #[default]
+    Auto,
+    Kitty,
+    /// iTerm2 inline images, also understood by WezTerm.
+    Iterm,
+    Sixel,
+    /// Coloured Unicode half blocks; works in any true-colour terminal.
+    Blocks,
+}
+
+impl FromStr for SpriteProtocol {
+    type Err = PokidexError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "auto" => Ok(SpriteProtocol::Auto),
+            "kitty" => Ok(SpriteProtocol::Kitty),
+            "iterm" => Ok(SpriteProtocol::Iterm),
+            "sixel" => Ok(SpriteProtocol::Sixel),
+            "blocks" => Ok(SpriteProtocol::Blocks),
+            other => Err(PokidexError::Config(format!(
+                "unknown sprite protocol '{}' (expected auto, kitty, iterm, sixel or blocks)",
+                other
+            ))),
+        }
+    }
+}
+
+/// What embeds documents and questions for semantic search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Embeddings {
+    /// The embedding model of the configured backend.
+    #[default]
+    Llm,
+    /// Hashed TF-IDF vectors computed locally; no API calls.
+    Local,
+}
+
+impl FromStr for Embeddings {
+    type Err = PokidexError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "llm" => Ok(Embeddings::Llm),
+            "local" => Ok(Embeddings::Local),
+            other => Err(PokidexError::Config(format!(
+                "unknown embeddings '{}' (expected llm or local)",
+                other
+            ))),
+        }
+    }
+}
+
+/// Settings read from `pokidex.toml`.
+///
+/// Values are layered: built-in defaults, then the config file, then
+/// `POKIDEX_*` environment variables, then CLI flags.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub backend: Backend,
+    pub model: String,
+    pub temperature: Option<f32>,
+    pub max_output_tokens: Option<i32>,
+    /// PokéAPI language code used for flavor text and answers (e.g. `en`, `ja`).
+    pub language: String,
+    /// Game to prefer for flavor text and move learnsets (e.g. `scarlet`).
+    pub game_version: Option<String>,
+    /// Directory for the on-disk PokéAPI HTTP cache.
+    pub cache_dir: PathBuf,
+    /// Maximum number of tool calls the model may make for one question.
+    pub max_tool_steps: usize,
+    /// Replaces the model's default system instruction.
+    pub system_prompt: Option<String>,
+    /// How many candidate Pokémon the model may name for an image.
+    pub max_image_candidates: usize,
+    /// File pickers `select-image` tries, in order, until one is available.
+    pub file_pickers: Vec<Picker>,
+    /// Terminal graphics protocol for `--sprite`.
+    pub sprite_protocol: SpriteProtocol,
+    /// How `pokidex index` embeds species entries. Questions are embedded
+    /// with whatever the index was built with.
+    pub embeddings: Embeddings,
+    /// The backend's embedding model, e.g. `text-embedding-004`.
+    pub embedding_model: String,
+    /// Index entries given to the model for questions that name no
+    /// Pokémon; 0 turns semantic search off.
+    pub retrieval_top_k: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            backend: Backend::Gemini,
+            model: "gemini-2.5-flash".to_string(),
+            temperature: None,
+            max_output_tokens: None,
+            language: "en".to_string(),
+            game_version: None,
+            cache_dir: PathBuf::from("./rustemon-cache"),
+            max_tool_steps: 3,
+            system_prompt: None,
+            max_image_candidates: 3,
+            file_pickers: vec![
+                Picker::System,
+                Picker::Zenity,
+                Picker::Kdialog,
+                Picker::Terminal,
+            ],
+            sprite_protocol: SpriteProtocol::Auto,
+            embeddings: Embeddings::Llm,
+            embedding_model: "text-embedding-004".to_string(),
+            retrieval_top_k: 5,
+        }
+    }
+}
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/pokidex/pokidex.toml`, falling back to
+    /// `~/.config/pokidex/pokidex.toml`.
+    pub fn default_path() -> Option<PathBuf> {
+        let base = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(base.join("pokidex").join(CONFIG_FILE_NAME))
+    }
+
+    /// Loads the config file and applies environment overrides. An explicit
+    /// `path` must exist; the default location is optional. Returns the file
+    /// that was read, if any.
+    pub fn load(path: Option<&Path>) -> Result<(Self, Option<PathBuf>)> {
+        let (mut config, source) = match path {
+            Some(path) => (Self::from_file(path)?, Some(path.to_path_buf())),
+            None => match Self::default_path().filter(|p| p.is_file()) {
+                Some(path) => (Self::from_file(&path)?, Some(path)),
+                None => (Self::default(), None),
+            },
+        };
+        config.apply_env()?;
+        Ok((config, source))
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| PokidexError::Config(format!("{}: {}", path.display(), e)))?;
+        toml::from_str(&text)
+            .map_err(|e| PokidexError::Config(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Overrides fields from `POKIDEX_*` environment variables.
+    pub fn apply_env(&mut self) -> Result<()> {
+        if let Some(v) = env_var("POKIDEX_BACKEND") {
+            self.backend = v.parse()?;
+        }
+        if let Some(v) = env_var("POKIDEX_MODEL") {
+            self.model = v;
+        }
+        if let Some(v) = env_var("POKIDEX_TEMPERATURE") {
+            self.temperature = Some(parse_env("POKIDEX_TEMPERATURE", &v)?);
+        }
+        if let Some(v) = env_var("POKIDEX_MAX_OUTPUT_TOKENS") {
+            self.max_output_tokens = Some(parse_env("POKIDEX_MAX_OUTPUT_TOKENS", &v)?);
+        }
+        if let Some(v) = env_var("POKIDEX_LANGUAGE") {
+            self.language = v;
+        }
+        if let Some(v) = env_var("POKIDEX_GAME_VERSION") {
+            self.game_version = Some(v);
+        }
+        if let Some(v) = env_var("POKIDEX_CACHE_DIR") {
+            self.cache_dir = PathBuf::from(v);
+        }
+        if let Some(v) = env_var("POKIDEX_MAX_TOOL_STEPS") {
+            self.max_tool_steps = parse_env("POKIDEX_MAX_TOOL_STEPS", &v)?;
+        }
+        if let Some(v) = env_var("POKIDEX_SYSTEM_PROMPT") {
+            self.system_prompt = Some(v);
+        }
+        if let Some(v) = env_var("POKIDEX_MAX_IMAGE_CANDIDATES") {
+            self.max_image_candidates = parse_env("POKIDEX_MAX_IMAGE_CANDIDATES", &v)?;
+        }
+        if let Some(v) = env_var("POKIDEX_FILE_PICKERS") {
+            self.file_pickers = v.split(',').map(str::parse).collect::<Result<_>>()?;
+        }
+        if let Some(v) = env_var("POKIDEX_SPRITE_PROTOCOL") {
+            self.sprite_protocol = v.parse()?;
+        }
+        if let Some(v) = env_var("POKIDEX_EMBEDDINGS") {
+            self.embeddings = v.parse()?;
+        }
+        if let Some(v) = env_var("POKIDEX_EMBEDDING_MODEL") {
+            self.embedding_model = v;
+        }
+        if let Some(v) = env_var("POKIDEX_RETRIEVAL_TOP_K") {
+            self.retrieval_top_k = parse_env("POKIDEX_RETRIEVAL_TOP_K", &v)?;
+        }
+        Ok(())
+    }
+
+    pub fn to_toml(&self) -> Result<String> {
+        toml::to_string_pretty(self)
+            .map_err(|e| PokidexError::Config(format!("can't write config as TOML: {}", e)))
+    }
+}
+
+fn env_var(key: &str) -> Option<String> {
+    env::var(key).ok().filter(|v| !v.trim().is_empty())
+}
+
+fn parse_env<T: FromStr>(key: &str, value: &str) -> Result<T> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| PokidexError::Config(format!("{}: invalid value '{}'", key, value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Backend, Config, Picker};
+
+    #[test]
+    fn partial_file_keeps_defaults() {
+        let config: Config = toml::from_str(
+            r#"
+model = "gemini-2.5-pro"
+temperature = 0.2
+game_version = "scarlet"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.model, "gemini-2.5-pro");
+        assert_eq!(config.temperature, Some(0.2));
+        assert_eq!(config.game_version.as_deref(), Some("scarlet"));
+        assert_eq!(config.language, "en");
+        assert_eq!(config.backend, Backend::Gemini);
+        assert_eq!(config.max_tool_steps, 3);
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        assert!(toml::from_str::<Config>("modle = \"typo\"").is_err());
+    }
+
+    #[test]
+    fn round_trips_through_toml() {
+        let config = Config {
+            system_prompt: Some("Answer like Professor Oak.".to_string()),
+            file_pickers: vec![Picker::Terminal, Picker::Zenity],
+            ..Config::default()
+        };
+        assert_eq!(
+            toml::from_str::<Config>(&config.to_toml().unwrap()).unwrap(),
+            config
+        );
+    }
+}
@@ -0,0 +1,810 @@
+//! Damage calculation with the main-series formula, for "does X OHKO Y"
+//! questions. Stats, modifier order and rounding follow Generation VI
+//! onward (1.5× critical hits); weather, terrain, screens and spread moves
+//! are left out.
+
+use crate::citation::Resource;
+use crate::error::{PokidexError, Result};
+use crate::pokeapi::PokeApiClient;
+use crate::search::{BaseStats, Stat};
+use crate::source::PokemonDataSource;
+use crate::text::title_case;
+use crate::types;
+use rustemon::model::moves::Move;
+use rustemon::model::pokemon::Pokemon;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+/// Level used when none is given, as on Smogon's calculator.
+pub const DEFAULT_LEVEL: u32 = 100;
+
+const MAX_IV: u32 = 31;
+const MAX_EV: u32 = 252;
+const MAX_TOTAL_EVS: u32 = 510;
+
+/// The random factor, in percent. Each value is one damage roll.
+const ROLLS: RangeInclusive<u64> = 85..=100;
+
+/// Natures with the stats they raise and lower by 10%.
+const NATURES: &[(&str, Option<Stat>, Option<Stat>)] = &[
+    ("hardy", None, None),
+    ("docile", None, None),
+    ("serious", None, None),
+    ("bashful", None, None),
+    ("quirky", None, None),
+    ("lonely", Some(Stat::Attack), Some(Stat::Defense)),
+    ("brave", Some(Stat::Attack), Some(Stat::Speed)),
+    ("adamant", Some(Stat::Attack), Some(Stat::SpecialAttack)),
+    ("naughty", Some(Stat::Attack), Some(Stat::SpecialDefense)),
+    ("bold", Some(Stat::Defense), Some(Stat::Attack)),
+    ("relaxed", Some(Stat::Defense), Some(Stat::Speed)),
+    ("impish", Some(Stat::Defense), Some(Stat::SpecialAttack)),
+    ("lax", Some(Stat::Defense), Some(Stat::SpecialDefense)),
+    ("timid", Some(Stat::Speed), Some(Stat::Attack)),
+    ("hasty", Some(Stat::Speed), Some(Stat::Defense)),
+    ("jolly", Some(Stat::Speed), Some(Stat::SpecialAttack)),
+    ("naive", Some(Stat::Speed), Some(Stat::SpecialDefense)),
+    ("modest", Some(Stat::SpecialAttack), Some(Stat::Attack)),
+    ("mild", Some(Stat::SpecialAttack), Some(Stat::Defense)),
+    ("quiet", Some(Stat::SpecialAttack), Some(Stat::Speed)),
+    (
+        "rash",
+        Some(Stat::SpecialAttack),
+        Some(Stat::SpecialDefense),
+    ),
+    ("calm", Some(Stat::SpecialDefense), Some(Stat::Attack)),
+    ("gentle", Some(Stat::SpecialDefense), Some(Stat::Defense)),
+    ("sassy", Some(Stat::SpecialDefense), Some(Stat::Speed)),
+    (
+        "careful",
+        Some(Stat::SpecialDefense),
+        Some(Stat::SpecialAttack),
+    ),
+];
+
+/// Abilities that make their holder immune to a move type.
+const IMMUNITY_ABILITIES: &[(&str, &str)] = &[
+    ("levitate", "ground"),
+    ("earth-eater", "ground"),
+    ("flash-fire", "fire"),
+    ("well-baked-body", "fire"),
+    ("water-absorb", "water"),
+    ("storm-drain", "water"),
+    ("dry-skin", "water"),
+    ("volt-absorb", "electric"),
+    ("lightning-rod", "electric"),
+    ("motor-drive", "electric"),
+    ("sap-sipper", "grass"),
+];
+
+/// A nature, e.g. Adamant (+Atk, −SpA).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Nature {
+    pub name: &'static str,
+    pub raised: Option<Stat>,
+    pub lowered: Option<Stat>,
+}
+
+impl Nature {
+    /// The stat multiplier in tenths: 11, 10 or 9.
+    fn tenths(self, stat: Stat) -> u32 {
+        if self.raised == self.lowered {
+            10
+        } else if self.raised == Some(stat) {
+            11
+        } else if self.lowered == Some(stat) {
+            9
+        } else {
+            10
+        }
+    }
+}
+
+impl FromStr for Nature {
+    type Err = PokidexError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let name = s.trim().to_lowercase();
+        NATURES
+            .iter()
+            .find(|(n, ..)| *n == name)
+            .map(|&(name, raised, lowered)| Nature {
+                name,
+                raised,
+                lowered,
+            })
+            .ok_or_else(|| PokidexError::InvalidRequest(format!("unknown nature '{}'", s)))
+    }
+}
+
+impl<'de> Deserialize<'de> for Nature {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// EVs or IVs by stat, written like `252 Atk / 4 HP`. Stats left out take
+/// the default: 0 EVs, 31 IVs.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(transparent)]
+pub struct Spread(pub BTreeMap<Stat, u32>);
+
+impl Spread {
+    fn get(&self, stat: Stat, default: u32) -> u32 {
+        self.0.get(&stat).copied().unwrap_or(default)
+    }
+}
+
+impl FromStr for Spread {
+    type Err = PokidexError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || {
+            PokidexError::InvalidRequest(format!(
+                "invalid spread '{}' (expected e.g. \"252 Atk / 4 HP\")",
+                s
+            ))
+        };
+        let mut spread = BTreeMap::new();
+        for part in s.split(['/', ',']).filter(|p| !p.trim().is_empty()) {
+            let (value, stat) = part.trim().split_once(' ').ok_or_else(invalid)?;
+            let value = value.parse::<u32>().map_err(|_| invalid())?;
+            spread.insert(stat.parse()?, value);
+        }
+        Ok(Self(spread))
+    }
+}
+
+/// One side of a calculation: which Pokémon and how it is built.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Combatant {
+    /// Pokémon name or National Dex ID.
+    pub name: String,
+    pub level: u32,
+    /// Neutral when unset.
+    pub nature: Option<Nature>,
+    pub evs: Spread,
+    pub ivs: Spread,
+    /// e.g. `choice-band`. Items the calculator doesn't model are ignored.
+    pub item: Option<String>,
+    /// e.g. `adaptability`. Abilities the calculator doesn't model are ignored.
+    pub ability: Option<String>,
+    /// Stat stage of the attacking or defending stat, −6 to +6.
+    pub boost: i32,
+}
+
+impl Default for Combatant {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            level: DEFAULT_LEVEL,
+            nature: None,
+            evs: Spread::default(),
+            ivs: Spread::default(),
+            item: None,
+            ability: None,
+            boost: 0,
+        }
+    }
+}
+
+impl Combatant {
+    fn validate(&self) -> Result<()> {
+        let invalid = |msg: String| Err(PokidexError::InvalidRequest(msg));
+        if !(1..=100).contains(&self.level) {
+            return invalid(format!("level {} is outside 1–100", self.level));
+        }
+        if !(-6..=6).contains(&self.boost) {
+            return invalid(format!("boost {} is outside −6 to +6", self.boost));
+        }
+        if self.evs.0.contains_key(&Stat::Total) || self.ivs.0.contains_key(&Stat::Total) {
+            return invalid("EVs and IVs are set per stat, not for the total".to_string());
+        }
+        if let Some((stat, ev)) = self.evs.0.iter().find(|(_, ev)| **ev > MAX_EV) {
+            return invalid(format!(
+                "{} EVs in {} (at most {})",
+                ev,
+                stat.label(),
+                MAX_EV
+            ));
+        }
+        let total: u32 = self.evs.0.values().sum();
+        if total > MAX_TOTAL_EVS {
+            return invalid(format!(
+                "{} EVs in total (at most {})",
+                total, MAX_TOTAL_EVS
+            ));
+        }
+        if let Some((stat, iv)) = self.ivs.0.iter().find(|(_, iv)| **iv > MAX_IV) {
+            return invalid(format!(
+                "{} IVs in {} (at most {})",
+                iv,
+                stat.label(),
+                MAX_IV
+            ));
+        }
+        Ok(())
+    }
+
+    /// The actual stat at this level, before boosts.
+    fn stat(&self, battler: &Battler, stat: Stat) -> u32 {
+        let base = battler.base.get(stat);
+        let points = 2 * base + self.ivs.get(stat, MAX_IV) + self.evs.get(stat, 0) / 4;
+        let raw = points * self.level / 100;
+        match stat {
+            // Shedinja always has 1 HP.
+            Stat::Hp if base == 1 => 1,
+            Stat::Hp => raw + self.level + 10,
+            _ => (raw + 5) * self.nature.map_or(10, |n| n.tenths(stat)) / 10,
+        }
+    }
+
+    fn item(&self) -> Option<String> {
+        self.item
+            .as_deref()
+            .map(PokeApiClient::normalize_pokemon_name)
+    }
+
+    fn ability(&self) -> Option<String> {
+        self.ability
+            .as_deref()
+            .map(PokeApiClient::normalize_pokemon_name)
+    }
+
+    /// e.g. `+1 Lvl 50 252+ Atk Huge Power Choice Band Azumarill`.
+    fn describe(&self, battler: &Battler, stats: &[Stat]) -> String {
+        let mut parts = Vec::new();
+        if self.boost != 0 {
+            parts.push(format!("{:+}", self.boost));
+        }
+        if self.level != DEFAULT_LEVEL {
+            parts.push(format!("Lvl {}", self.level));
+        }
+        let evs: Vec<String> = stats
+            .iter()
+            .map(|&stat| {
+                let sign = match self.nature.map_or(10, |n| n.tenths(stat)) {
+                    11 => "+",
+                    9 => "-",
+                    _ => "",
+                };
+                format!("{}{} {}", self.evs.get(stat, 0), sign, stat.label())
+            })
+            .collect();
+        parts.push(evs.join(" / "));
+        parts.extend(self.ability().map(|a| title_case(&a)));
+        parts.extend(self.item().map(|i| title_case(&i)));
+        parts.push(title_case(&battler.name));
+        parts.join(" ")
+    }
+}
+
+/// The attacker, defender and move to calculate for.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CalcRequest {
+    pub attacker: Combatant,
+    pub defender: Combatant,
+    /// Move name or ID, e.g. `earthquake`.
+    #[serde(rename = "move")]
+    pub move_name: String,
+    pub critical: bool,
+    /// The attacker is burned, halving its physical damage (unless it has Guts).
+    pub burned: bool,
+}
+
+impl CalcRequest {
+    fn validate(&self) -> Result<()> {
+        self.attacker.validate()?;
+        self.defender.validate()
+    }
+}
+
+/// What the formula needs from a Pokémon's data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Battler {
+    pub name: String,
+    pub types: Vec<String>,
+    pub base: BaseStats,
+}
+
+impl Battler {
+    pub fn new(pokemon: &Pokemon) -> Self {
+        Self {
+            name: pokemon.name.clone(),
+            types: pokemon.types.iter().map(|t| t.type_.name.clone()).collect(),
+            base: BaseStats::of(pokemon),
+        }
+    }
+}
+
+/// Which attacking and defending stats a move uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    Physical,
+    Special,
+}
+
+/// What the formula needs from a move's data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MoveData {
+    pub name: String,
+    pub type_: String,
+    pub category: Category,
+    pub power: u32,
+}
+
+impl MoveData {
+    /// Fails for status moves and moves without a fixed power (Low Kick,
+    /// Seismic Toss), which the formula can't handle.
+    pub fn new(pokemon_move: &Move) -> Result<Self> {
+        let category = match pokemon_move.damage_class.name.as_str() {
+            "physical" => Category::Physical,
+            "special" => Category::Special,
+            _ => {
+                return Err(PokidexError::InvalidRequest(format!(
+                    "{} is a status move and deals no damage",
+                    pokemon_move.name
+                )))
+            }
+        };
+        let power = pokemon_move
+            .power
+            .and_then(|p| u32::try_from(p).ok())
+            .ok_or_else(|| {
+                PokidexError::InvalidRequest(format!(
+                    "{} has no fixed power, which the damage formula needs",
+                    pokemon_move.name
+                ))
+            })?;
+        Ok(Self {
+            name: pokemon_move.name.clone(),
+            type_: pokemon_move.type_.name.clone(),
+            category,
+            power,
+        })
+    }
+}
+
+/// The result of a calculation.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Damage {
+    pub attacker: String,
+    pub defender: String,
+    #[serde(rename = "move")]
+    pub move_name: String,
+    pub move_type: String,
+    pub category: Category,
+    pub power: u32,
+    pub effectiveness: f32,
+    /// Damage for each random factor from 85% to 100%, lowest first.
+    pub rolls: Vec<u32>,
+    pub defender_hp: u32,
+    pub min: u32,
+    pub max: u32,
+    pub min_percent: f64,
+    pub max_percent: f64,
+    /// e.g. `guaranteed OHKO` or `43.8% chance to 2HKO`.
+    pub ko: String,
+    /// One-line summary in the style of Smogon's calculator.
+    pub description: String,
+}
+
+impl Damage {
+    /// The summary and every damage roll.
+    pub fn format(&self) -> String {
+        let mut out = format!("{}\n", self.description);
+        let rolls: Vec<String> = self.rolls.iter().map(u32::to_string).collect();
+        let _ = writeln!(out, "Possible damage amounts: ({})", rolls.join(", "));
+        out
+    }
+}
+
+/// Runs the damage formula for `request` with the looked-up data of both
+/// Pokémon and the move.
+pub(crate) fn calculate(
+    request: &CalcRequest,
+    attacker: &Battler,
+    defender: &Battler,
+    pokemon_move: &MoveData,
+) -> Result<Damage> {
+    request.validate()?;
+    let (atk, def) = (&request.attacker, &request.defender);
+    let attacker_ability = atk.ability().unwrap_or_default();
+    let attacker_item = atk.item().unwrap_or_default();
+    let defender_ability = def.ability().unwrap_or_default();
+    let defender_item = def.item().unwrap_or_default();
+    let physical = pokemon_move.category == Category::Physical;
+    let move_type = pokemon_move.type_.as_str();
+
+    let defending_types: Vec<&str> = defender.types.iter().map(String::as_str).collect();
+    let mut effectiveness = types::effectiveness_against(move_type, &defending_types);
+    let immune = IMMUNITY_ABILITIES
+        .iter()
+        .any(|&(ability, t)| ability == defender_ability && t == move_type)
+        || (defender_item == "air-balloon" && move_type == "ground");
+    if immune {
+        effectiveness = 0.0;
+    }
+
+    let mut power_modifiers = Vec::new();
+    if attacker_ability == "technician" && pokemon_move.power <= 60 {
+        power_modifiers.push(6144);
+    }
+    let power = modify(pokemon_move.power.into(), chain(&power_modifiers));
+
+    let (attack_stat, defense_stat) = if physical {
+        (Stat::Attack, Stat::Defense)
+    } else {
+        (Stat::SpecialAttack, Stat::SpecialDefense)
+    };
+    // Critical hits ignore the attacker's drops and the defender's boosts.
+    let attack_boost = if request.critical {
+        atk.boost.max(0)
+    } else {
+        atk.boost
+    };
+    let defense_boost = if request.critical {
+        def.boost.min(0)
+    } else {
+        def.boost
+    };
+
+    let mut attack_modifiers = Vec::new();
+    match attacker_ability.as_str() {
+        "huge-power" | "pure-power" if physical => attack_modifiers.push(8192),
+        "hustle" if physical => attack_modifiers.push(6144),
+        "guts" if physical && request.burned => attack_modifiers.push(6144),
+        _ => {}
+    }
+    match attacker_item.as_str() {
+        "choice-band" if physical => attack_modifiers.push(6144),
+        "choice-specs" if !physical => attack_modifiers.push(6144),
+        _ => {}
+    }
+    if defender_ability == "thick-fat" && matches!(move_type, "fire" | "ice") {
+        attack_modifiers.push(2048);
+    }
+    let attack = modify(
+        boosted(atk.stat(attacker, attack_stat), attack_boost),
+        chain(&attack_modifiers),
+    );
+
+    let mut defense_modifiers = Vec::new();
+    if defender_ability == "fur-coat" && physical {
+        defense_modifiers.push(8192);
+    }
+    match defender_item.as_str() {
+        "eviolite" => defense_modifiers.push(6144),
+        "assault-vest" if !physical => defense_modifiers.push(6144),
+        _ => {}
+    }
+    let defense = modify(
+        boosted(def.stat(defender, defense_stat), defense_boost),
+        chain(&defense_modifiers),
+    )
+    .max(1);
+
+    let level = u64::from(atk.level);
+    let mut base = (2 * level / 5 + 2) * power * attack / defense / 50 + 2;
+    if request.critical {
+        base = base * 3 / 2;
+    }
+
+    let stab = if attacker.types.iter().any(|t| t == move_type) {
+        if attacker_ability == "adaptability" {
+            8192
+        } else {
+            6144
+        }
+    } else {
+        4096
+    };
+    // In the order the games chain them.
+    let mut final_modifiers = Vec::new();
+    if matches!(defender_ability.as_str(), "multiscale" | "shadow-shield") {
+        final_modifiers.push(2048);
+    }
+    if matches!(
+        defender_ability.as_str(),
+        "filter" | "solid-rock" | "prism-armor"
+    ) && effectiveness > 1.0
+    {
+        final_modifiers.push(3072);
+    }
+    if attacker_ability == "tinted-lens" && effectiveness < 1.0 {
+        final_modifiers.push(8192);
+    }
+    if attacker_item == "expert-belt" && effectiveness > 1.0 {
+        final_modifiers.push(4915);
+    }
+    if attacker_item == "life-orb" {
+        // Not quite ×1.3.
+        final_modifiers.push(5324);
+    }
+    let final_modifier = chain(&final_modifiers);
+    let burn_halves = request.burned && physical && attacker_ability != "guts";
+
+    let rolls: Vec<u32> = ROLLS
+        .map(|roll| {
+            if effectiveness == 0.0 {
+                return 0;
+            }
+            let mut damage = modify(base * roll / 100, stab);
+            // Effectiveness is a power of two, so this is exact.
+            damage = (damage as f64 * f64::from(effectiveness)) as u64;
+            if burn_halves {
+                damage /= 2;
+            }
+            damage = modify(damage, final_modifier).max(1);
+            u32::try_from(damage).unwrap_or(u32::MAX)
+        })
+        .collect();
+
+    let defender_hp = def.stat(defender, Stat::Hp);
+    let min = rolls.iter().copied().min().unwrap_or(0);
+    let max = rolls.iter().copied().max().unwrap_or(0);
+    let percent =
+        |damage: u32| (f64::from(damage) * 1000.0 / f64::from(defender_hp)).round() / 10.0;
+    let ko = ko_chance(&rolls, defender_hp);
+
+    let mut description = format!(
+        "{} {} vs. {}",
+        atk.describe(attacker, &[attack_stat]),
+        title_case(&pokemon_move.name),
+        def.describe(defender, &[Stat::Hp, defense_stat])
+    );
+    if request.critical {
+        description.push_str(" on a critical hit");
+    }
+    if request.burned {
+        description.push_str(" (attacker burned)");
+    }
+    let _ = write!(
+        description,
+        ": {}-{} ({} - {}%) -- {}",
+        min,
+        max,
+        percent(min),
+        percent(max),
+        ko
+    );
+
+    Ok(Damage {
+        attacker: attacker.name.clone(),
+        defender: defender.name.clone(),
+        move_name: pokemon_move.name.clone(),
+        move_type: pokemon_move.type_.clone(),
+        category: pokemon_move.category,
+        power: pokemon_move.power,
+        effectiveness,
+        rolls,
+        defender_hp,
+        min,
+        max,
+        min_percent: percent(min),
+        max_percent: percent(max),
+        ko,
+        description,
+    })
+}
+
+/// Looks up both Pokémon and the move in `source` and calculates the damage.
+pub async fn calc(source: &dyn PokemonDataSource, request: &CalcRequest) -> Result<Damage> {
+    Ok(calc_with_resources(source, request).await?.0)
+}
+
+/// [`calc`], also returning the resources the data was read from.
+pub(crate) async fn calc_with_resources(
+    source: &dyn PokemonDataSource,
+    request: &CalcRequest,
+) -> Result<(Damage, Vec<Resource>)> {
+    let attacker_name = PokeApiClient::normalize_pokemon_name(&request.attacker.name);
+    let defender_name = PokeApiClient::normalize_pokemon_name(&request.defender.name);
+    let move_name = PokeApiClient::normalize_pokemon_name(&request.move_name);
+    if attacker_name.is_empty() || defender_name.is_empty() || move_name.is_empty() {
+        return Err(PokidexError::InvalidRequest(
+            "a damage calculation needs an attacker, a defender and a move".to_string(),
+        ));
+    }
+    // Checked before the lookups too, so a bad spread fails fast.
+    request.validate()?;
+
+    let (attacker, defender, pokemon_move) = tokio::join!(
+        source.get_pokemon(&attacker_name),
+        source.get_pokemon(&defender_name),
+        source.get_move(&move_name)
+    );
+    let (attacker, defender, pokemon_move) = (attacker?, defender?, pokemon_move?);
+    let damage = calculate(
+        request,
+        &Battler::new(&attacker),
+        &Battler::new(&defender),
+        &MoveData::new(&pokemon_move)?,
+    )?;
+    let mut resources = vec![Resource::pokemon(&attacker), Resource::pokemon(&defender)];
+    // A mirror match reads one Pokémon.
+    resources.dedup();
+    resources.push(Resource::pokemon_move(&pokemon_move));
+    Ok((damage, resources))
+}
+
+/// Applies a modifier in 4096ths (6144 is ×1.5) the way the games do,
+/// rounding halves down.
+fn modify(value: u64, modifier: u64) -> u64 {
+    (value * modifier + 2047) / 4096
+}
+
+/// Combines modifiers in 4096ths, rounding after each one as the games do.
+fn chain(modifiers: &[u64]) -> u64 {
+    modifiers.iter().fold(4096, |chained, &modifier| {
+        (chained * modifier + 2048) / 4096
+    })
+}
+
+/// `stat` at a stat stage: +1 is ×1.5, −1 is ×2/3, up to ±6.
+fn boosted(stat: u32, stage: i32) -> u64 {
+    let stat = u64::from(stat);
+    let steps = u64::from(stage.unsigned_abs());
+    if stage >= 0 {
+        stat * (2 + steps) / 2
+    } else {
+        stat * 2 / (2 + steps)
+    }
+}
+
+/// The fewest hits that can KO and the chance they do, e.g. `guaranteed
+/// 2HKO` or `37.5% chance to OHKO`. Every roll is equally likely.
+fn ko_chance(rolls: &[u32], hp: u32) -> String {
+    let max = rolls.iter().copied().max().unwrap_or(0);
+    if max == 0 || rolls.is_empty() {
+        return "does no damage".to_string();
+    }
+    let hp = hp as usize;
+    let hits = hp.div_ceil(max as usize);
+    // Chance of each damage total so far; totals past the HP count as a KO.
+    let mut odds = vec![0.0; hp + 1];
+    odds[0] = 1.0;
+    for _ in 0..hits {
+        let mut next = vec![0.0; hp + 1];
+        for (total, chance) in odds.iter().enumerate().filter(|(_, c)| **c > 0.0) {
+            for &roll in rolls {
+                next[(total + roll as usize).min(hp)] += chance / rolls.len() as f64;
+            }
+        }
+        odds = next;
+    }
+    let name = match hits {
+        1 => "OHKO".to_string(),
+        n => format!("{}HKO", n),
+    };
+    let chance = odds[hp];
+    if chance > 1.0 - 1e-9 {
+        format!("guaranteed {}", name)
+    } else {
+        format!("{:.1}% chance to {}", chance * 100.0, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{calculate, Battler, CalcRequest, Category, Combatant, MoveData};
+    use crate::search::BaseStats;
+
+    fn battler(name: &str, types: &[&str], base: [u32; 6]) -> Battler {
+        let [hp, attack, defense, special_attack, special_defense, speed] = base;
+        Battler {
+            name: name.to_string(),
+            types: types.iter().map(|t| t.to_string()).collect(),
+            base: BaseStats {
+                hp,
+                attack,
+                defense,
+                special_attack,
+                special_defense,
+                speed,
+            },
+        }
+    }
+
+    /// Adamant 252 Atk Garchomp's Earthquake into an uninvested Heatran:
+    /// 394 Atk vs. 248 Def, 4× effective with STAB, against 323 HP.
+    fn earthquake_into_heatran() -> (CalcRequest, Battler, Battler, MoveData) {
+        let garchomp = battler(
+            "garchomp",
+            &["dragon", "ground"],
+            [108, 130, 95, 80, 85, 102],
+        );
+        let heatran = battler("heatran", &["fire", "steel"], [91, 90, 106, 130, 106, 77]);
+        let earthquake = MoveData {
+            name: "earthquake".to_string(),
+            type_: "ground".to_string(),
+            category: Category::Physical,
+            power: 100,
+        };
+        let request = CalcRequest {
+            attacker: Combatant {
+                name: "garchomp".to_string(),
+                nature: Some("adamant".parse().unwrap()),
+                evs: "252 Atk / 4 HP".parse().unwrap(),
+                ..Combatant::default()
+            },
+            defender: Combatant {
+                name: "heatran".to_string(),
+                ..Combatant::default()
+            },
+            move_name: "earthquake".to_string(),
+            ..CalcRequest::default()
+        };
+        (request, garchomp, heatran, earthquake)
+    }
+
+    #[test]
+    fn rolls_match_the_main_series_formula() {
+        let (request, garchomp, heatran, earthquake) = earthquake_into_heatran();
+        let damage = calculate(&request, &garchomp, &heatran, &earthquake).unwrap();
+        assert_eq!(damage.rolls.len(), 16);
+        assert_eq!(
+            (damage.min, damage.max, damage.defender_hp),
+            (684, 808, 323)
+        );
+        assert_eq!(damage.ko, "guaranteed OHKO");
+        assert!(damage
+            .description
+            .starts_with("252+ Atk Garchomp Earthquake vs. 0 HP / 0 Def Heatran: 684-808"));
+
+        let mut levitating = request.clone();
+        levitating.defender.ability = Some("Levitate".to_string());
+        let damage = calculate(&levitating, &garchomp, &heatran, &earthquake).unwrap();
+        assert_eq!(damage.max, 0);
+        assert_eq!(damage.ko, "does no damage");
+
+        let mut over_limit = request.clone();
+        over_limit.attacker.evs = "252 Atk / 252 HP / 252 Spe".parse().unwrap();
+        assert!(calculate(&over_limit, &garchomp, &heatran, &earthquake).is_err());
+    }
+
+    #[test]
+    fn applies_modifiers_in_4096ths() {
+        let (request, garchomp, heatran, earthquake) = earthquake_into_heatran();
+        let range = |request: &CalcRequest, defender: &Battler| {
+            let damage = calculate(request, &garchomp, defender, &earthquake).unwrap();
+            (damage.min, damage.max)
+        };
+
+        let mut crit = request.clone();
+        crit.critical = true;
+        assert_eq!(range(&crit, &heatran), (1024, 1212));
+
+        let mut burned = request.clone();
+        burned.burned = true;
+        assert_eq!(range(&burned, &heatran), (342, 404));
+        // Guts ignores the burn and raises Attack to 591.
+        burned.attacker.ability = Some("guts".to_string());
+        assert_eq!(range(&burned, &heatran), (1024, 1212));
+
+        let mut adaptability = request.clone();
+        adaptability.attacker.ability = Some("adaptability".to_string());
+        assert_eq!(range(&adaptability, &heatran), (912, 1080));
+
+        let skarmory = battler("skarmory", &["steel", "flying"], [65, 80, 140, 40, 70, 70]);
+        assert_eq!(range(&request, &skarmory), (0, 0));
+
+        // Life Orb is 5324/4096; rounding ×1.3 to 5325 would make the 91%
+        // roll 952.
+        let mut life_orb = request.clone();
+        life_orb.attacker.item = Some("Life Orb".to_string());
+        let damage = calculate(&life_orb, &garchomp, &heatran, &earthquake).unwrap();
+        assert_eq!(
+            damage.rolls,
+            [889, 905, 910, 920, 936, 941, 951, 967, 972, 983, 998, 1003, 1014, 1029, 1035, 1050]
+        );
+    }
+}
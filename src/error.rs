@@ -0,0 +1,180 @@
+use serde::Serialize;
+use std::time::Duration;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, PokidexError>;
+
+/// Every failure pokidex can report.
+///
+/// Variants are split by how a caller should react: transient ones are worth
+/// retrying, the rest are final. The CLI maps each variant to its own exit
+/// code via [`PokidexError::exit_code`].
+#[derive(Debug, Error)]
+pub enum PokidexError {
+    /// `kind` names what was looked up, e.g. `Pokémon` or `move`.
+    #[error("no such {kind}: {name}")]
+    NotFound { kind: &'static str, name: String },
+    #[error("network error: {0}")]
+    Network(String),
+    #[error("request timed out after {}s", .0.as_secs_f32())]
+    Timeout(Duration),
+    #[error("rate limited by {0}, please wait a moment and try again")]
+    RateLimited(String),
+    #[error("upstream returned HTTP {status}: {message}")]
+    Upstream { status: u16, message: String },
+    #[error("failed to parse response: {0}")]
+    ParseFailure(String),
+    #[error("invalid request: {0}")]
+    InvalidRequest(String),
+    #[error("the model declined to answer: {0}")]
+    LlmRefused(String),
+    #[error("invalid image: {0}")]
+    InvalidImage(String),
+    #[error("GEMINI_API_KEY is not set; export it or add it to a .env file")]
+    MissingApiKey,
+    #[error("file picker: {0}")]
+    FilePicker(String),
+    #[error("configuration error: {0}")]
+    Config(String),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl PokidexError {
+    /// Whether the failure is likely to go away on its own (connection
+    /// drops, timeouts, 429 and 5xx responses).
+    pub fn is_transient(&self) -> bool {
+        match self {
+            PokidexError::Network(_) | PokidexError::Timeout(_) | PokidexError::RateLimited(_) => {
+                true
+            }
+            PokidexError::Upstream { status, .. } => *status >= 500,
+            _ => false,
+        }
+    }
+
+    /// Process exit code used by the CLI. `1` and `2` are left to generic
+    /// failures and clap usage errors.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            PokidexError::NotFound { .. } => 3,
+            PokidexError::Network(_) => 4,
+            PokidexError::Timeout(_) => 5,
+            PokidexError::RateLimited(_) => 6,
+            PokidexError::Upstream { .. } => 7,
+            PokidexError::ParseFailure(_) => 8,
+            PokidexError::InvalidRequest(_) => 9,
+            PokidexError::LlmRefused(_) => 10,
+            PokidexError::InvalidImage(_) => 11,
+            PokidexError::MissingApiKey => 12,
+            PokidexError::FilePicker(_) => 13,
+            PokidexError::Io(_) => 14,
+            PokidexError::Config(_) => 15,
+        }
+    }
+
+    /// Stable snake_case name of the variant, for machine-readable output.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PokidexError::NotFound { .. } => "not_found",
+            PokidexError::Network(_) => "network",
+            PokidexError::Timeout(_) => "timeout",
+            PokidexError::RateLimited(_) => "rate_limited",
+            PokidexError::Upstream { .. } => "upstream",
+            PokidexError::ParseFailure(_) => "parse_failure",
+            PokidexError::InvalidRequest(_) => "invalid_request",
+            PokidexError::LlmRefused(_) => "llm_refused",
+            PokidexError::InvalidImage(_) => "invalid_image",
+            PokidexError::MissingApiKey => "missing_api_key",
+            PokidexError::FilePicker(_) => "file_picker",
+            PokidexError::Io(_) => "io",
+            PokidexError::Config(_) => "config",
+        }
+    }
+
+    /// Maps an HTTP status code from `service` to the matching variant. A
+    /// 404 is reported as a missing `resource`; callers that know what they
+    /// asked for name it themselves.
+    pub fn from_status(service: &str, status: u16, message: impl Into<String>) -> Self {
+        match status {
+            404 => PokidexError::NotFound {
+                kind: "resource",
+                name: message.into(),
+            },
+            429 => PokidexError::RateLimited(service.to_string()),
+            _ => PokidexError::Upstream {
+                status,
+                message: message.into(),
+            },
+        }
+    }
+
+    /// Classifies a transport-level reqwest failure. Decode errors are not
+    /// handled here because their meaning depends on the caller.
+    pub fn from_reqwest(service: &str, err: &reqwest::Error) -> Self {
+        if let Some(status) = err.status() {
+            return Self::from_status(service, status.as_u16(), err.to_string());
+        }
+        PokidexError::Network(format!("{}: {}", service, err))
+    }
+}
+
+/// An error as reported in JSON output: the [`PokidexError::kind`] and the
+/// display message.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ErrorSummary {
+    pub kind: &'static str,
+    pub message: String,
+}
+
+impl From<&PokidexError> for ErrorSummary {
+    fn from(error: &PokidexError) -> Self {
+        Self {
+            kind: error.kind(),
+            message: error.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PokidexError;
+
+    #[test]
+    fn classifies_statuses_by_retryability() {
+        assert!(!PokidexError::from_status("PokéAPI", 404, "pikachu").is_transient());
+        assert!(PokidexError::from_status("PokéAPI", 429, "slow down").is_transient());
+        assert!(PokidexError::from_status("Gemini", 503, "unavailable").is_transient());
+        assert!(!PokidexError::from_status("Gemini", 400, "bad request").is_transient());
+    }
+
+    #[test]
+    fn exit_codes_are_distinct() {
+        let errors = [
+            PokidexError::NotFound {
+                kind: "Pokémon",
+                name: "missingno".into(),
+            },
+            PokidexError::Network("down".into()),
+            PokidexError::Timeout(std::time::Duration::from_secs(5)),
+            PokidexError::RateLimited("Gemini".into()),
+            PokidexError::Upstream {
+                status: 500,
+                message: "oops".into(),
+            },
+            PokidexError::ParseFailure("json".into()),
+            PokidexError::InvalidRequest("bad".into()),
+            PokidexError::LlmRefused("safety".into()),
+            PokidexError::InvalidImage("empty".into()),
+            PokidexError::MissingApiKey,
+            PokidexError::FilePicker("cancelled".into()),
+            PokidexError::Io(std::io::Error::other("disk")),
+            PokidexError::Config("bad toml".into()),
+        ];
+        let mut codes: Vec<u8> = errors.iter().map(|e| e.exit_code()).collect();
+        codes.sort_unstable();
+        codes.dedup();
+        assert_eq!(codes.len(), errors.len());
+        assert!(codes.iter().all(|&c| c > 2));
+    }
+}
@@ -0,0 +1,292 @@
+use crate::config::Config;
+use rustemon::model::evolution::{ChainLink, EvolutionChain};
+use rustemon::model::pokemon::{Pokemon, PokemonMove, PokemonSpecies};
+use rustemon::model::resource::FlavorText;
+use std::fmt::Write;
+
+/// Language and game preferences applied when formatting PokéAPI data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// PokéAPI language code for flavor text.
+    pub language: String,
+    /// Game version whose flavor text and learnset are preferred.
+    pub game_version: Option<String>,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            language: "en".to_string(),
+            game_version: None,
+        }
+    }
+}
+
+impl From<&Config> for FormatOptions {
+    fn from(config: &Config) -> Self {
+        Self {
+            language: config.language.clone(),
+            game_version: config.game_version.clone(),
+        }
+    }
+}
+
+impl FormatOptions {
+    /// Flavor text in the configured language, preferring the configured
+    /// game and falling back to English when the language has no entries.
+    fn pick_flavor_text<'a>(&self, entries: &'a [FlavorText]) -> Option<&'a FlavorText> {
+        let from_game = self.game_version.as_deref().and_then(|game| {
+            entries.iter().find(|e| {
+                e.language.name == self.language
+                    && e.version.as_ref().is_some_and(|v| v.name == game)
+            })
+        });
+        from_game
+            .or_else(|| entries.iter().find(|e| e.language.name == self.language))
+            .or_else(|| entries.iter().find(|e| e.language.name == "en"))
+    }
+
+    /// The species' Pokédex description on one line, picked as in
+    /// `pick_flavor_text`.
+    pub fn flavor_text(&self, species: &PokemonSpecies) -> Option<String> {
+        let entry = self.pick_flavor_text(&species.flavor_text_entries)?;
+        // Entries keep the games' hard line and page breaks.
+        Some(
+            entry
+                .flavor_text
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" "),
+        )
+    }
+
+    /// Whether a version group (e.g. `scarlet-violet`) includes the
+    /// configured game (e.g. `scarlet`). Always true when no game is set.
+    fn matches_version_group(&self, version_group: &str) -> bool {
+        match self.game_version.as_deref() {
+            None => true,
+            Some(game) => {
+                version_group == game
+                    || version_group.starts_with(&format!("{}-", game))
+                    || version_group.ends_with(&format!("-{}", game))
+            }
+        }
+    }
+}
+
+pub(crate) fn format_pokemon_data(pokemon: &Pokemon) -> String {
+    let mut output = String::new();
+
+    writeln!(output, "Name: {}", pokemon.name).ok();
+    writeln!(output, "ID: {}", pokemon.id).ok();
+
+    // Types
+    write!(output, "Types: ").ok();
+    let types: Vec<String> = pokemon.types.iter().map(|t| t.type_.name.clone()).collect();
+    writeln!(output, "{}", types.join(", ")).ok();
+
+    // Stats
+    writeln!(output, "Stats:").ok();
+    for stat in &pokemon.stats {
+        let stat_name = &stat.stat.name;
+        let base_stat = stat.base_stat;
+        writeln!(output, "  - {}: {}", stat_name, base_stat).ok();
+    }
+
+    // Abilities
+    write!(output, "Abilities: ").ok();
+    let abilities: Vec<String> = pokemon
+        .abilities
+        .iter()
+        .map(|a| {
+            let mut name = a.ability.name.clone();
+            if a.is_hidden {
+                name.push_str(" (hidden)");
+            }
+            name
+        })
+        .collect();
+    writeln!(output, "{}", abilities.join(", ")).ok();
+
+    // Height and Weight
+    writeln!(output, "Height: {} dm", pokemon.height).ok();
+    writeln!(output, "Weight: {} hg", pokemon.weight).ok();
+
+    // Base Experience
+    if let Some(base_exp) = pokemon.base_experience {
+        writeln!(output, "Base Experience: {}", base_exp).ok();
+    }
+
+    output
+}
+
+pub(crate) fn format_pokemon_with_species(
+    pokemon: &Pokemon,
+    species: &PokemonSpecies,
+    options: &FormatOptions,
+) -> String {
+    let mut output = format_pokemon_data(pokemon);
+
+    // Add species information
+    output.push('\n');
+    output.push_str(&format_species_summary(species, options));
+
+    output
+}
+
+/// Full entry when the species is known, plain Pokémon data otherwise.
+pub fn format_pokemon_entry(
+    pokemon: &Pokemon,
+    species: Option<&PokemonSpecies>,
+    options: &FormatOptions,
+) -> String {
+    match species {
+        Some(species) => format_pokemon_with_species(pokemon, species, options),
+        None => format_pokemon_data(pokemon),
+    }
+}
+
+pub(crate) fn format_pokemon_stats(pokemon: &Pokemon) -> String {
+    let mut output = String::new();
+
+    writeln!(output, "Name: {}", pokemon.name).ok();
+    writeln!(output, "Base Stats:").ok();
+    let mut total = 0;
+    for stat in &pokemon.stats {
+        writeln!(output, "  - {}: {}", stat.stat.name, stat.base_stat).ok();
+        total += stat.base_stat;
+    }
+    writeln!(output, "  - total: {}", total).ok();
+
+    output
+}
+
+pub(crate) fn format_pokemon_moves(
+    pokemon: &Pokemon,
+    limit: usize,
+    options: &FormatOptions,
+) -> String {
+    let mut output = String::new();
+
+    // With a game configured, only list moves learnable in that game
+    let moves: Vec<&PokemonMove> = pokemon
+        .moves
+        .iter()
+        .filter(|m| {
+            m.version_group_details
+                .iter()
+                .any(|d| options.matches_version_group(&d.version_group.name))
+        })
+        .collect();
+
+    writeln!(output, "Name: {}", pokemon.name).ok();
+    match &options.game_version {
+        Some(game) => writeln!(output, "Moves in {} ({} total):", game, moves.len()).ok(),
+        None => writeln!(output, "Moves ({} total):", moves.len()).ok(),
+    };
+    for pokemon_move in moves.iter().take(limit) {
+        // Report how the move is learned in the most recent matching version group
+        let learned = pokemon_move
+            .version_group_details
+            .iter()
+            .rfind(|d| options.matches_version_group(&d.version_group.name))
+            .map(|d| {
+                if d.move_learn_method.name == "level-up" {
+                    format!("level-up (lv {})", d.level_learned_at)
+                } else {
+                    d.move_learn_method.name.clone()
+                }
+            })
+            .unwrap_or_else(|| "unknown".to_string());
+        writeln!(output, "  - {}: {}", pokemon_move.move_.name, learned).ok();
+    }
+    if moves.len() > limit {
+        writeln!(output, "  ... and {} more", moves.len() - limit).ok();
+    }
+
+    output
+}
+
+/// Species-only summary, used when the matching Pokémon entry is unavailable.
+pub(crate) fn format_species_summary(species: &PokemonSpecies, options: &FormatOptions) -> String {
+    let mut output = String::new();
+
+    writeln!(output, "Species Information:").ok();
+    writeln!(output, "  Capture Rate: {}", species.capture_rate).ok();
+    if let Some(base_happiness) = species.base_hapiness {
+        writeln!(output, "  Base Happiness: {}", base_happiness).ok();
+    }
+    writeln!(output, "  Is Legendary: {}", species.is_legendary).ok();
+    writeln!(output, "  Is Mythical: {}", species.is_mythical).ok();
+    if let Some(flavor_text) = options.flavor_text(species) {
+        writeln!(output, "  Description: {}", flavor_text).ok();
+    }
+
+    output
+}
+
+/// One line per branch of the chain, e.g. `eevee → vaporeon`. A species
+/// that doesn't evolve is a line on its own.
+pub fn format_evolution_chain(chain: &EvolutionChain) -> String {
+    let mut lines = Vec::new();
+    evolution_paths(&chain.chain, &mut Vec::new(), &mut lines);
+    lines.join("\n")
+}
+
+fn evolution_paths<'a>(link: &'a ChainLink, path: &mut Vec<&'a str>, lines: &mut Vec<String>) {
+    path.push(&link.species.name);
+    if link.evolves_to.is_empty() {
+        lines.push(path.join(" → "));
+    }
+    for next in &link.evolves_to {
+        evolution_paths(next, path, lines);
+    }
+    path.pop();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_evolution_chain, FormatOptions};
+
+    #[test]
+    fn matches_version_groups_containing_the_game() {
+        let options = FormatOptions {
+            game_version: Some("scarlet".to_string()),
+            ..FormatOptions::default()
+        };
+        assert!(options.matches_version_group("scarlet-violet"));
+        assert!(!options.matches_version_group("sword-shield"));
+
+        let violet = FormatOptions {
+            game_version: Some("violet".to_string()),
+            ..FormatOptions::default()
+        };
+        assert!(violet.matches_version_group("scarlet-violet"));
+        assert!(FormatOptions::default().matches_version_group("red-blue"));
+    }
+
+    #[test]
+    fn lists_each_evolution_branch() {
+        let link = |name: &str, evolves_to: serde_json::Value| {
+            serde_json::json!({
+                "is_baby": false,
+                "species": { "name": name, "url": "" },
+                "evolution_details": [],
+                "evolves_to": evolves_to,
+            })
+        };
+        let chain = serde_json::json!({
+            "id": 135,
+            "baby_trigger_item": null,
+            "chain": link("wurmple", serde_json::json!([
+                link("silcoon", serde_json::json!([link("beautifly", serde_json::json!([]))])),
+                link("cascoon", serde_json::json!([link("dustox", serde_json::json!([]))])),
+            ])),
+        });
+        let chain = serde_json::from_value(chain).unwrap();
+        assert_eq!(
+            format_evolution_chain(&chain),
+            "wurmple → silcoon → beautifly\nwurmple → cascoon → dustox"
+        );
+    }
+}
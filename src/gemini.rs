@@ -1,80 +1,294 @@
-use anyhow::{Context, Result};
+use crate::config::Config;
+use crate::error::{PokidexError, Result};
+use crate::image_input::ImageInput;
+use crate::llm::{EmbeddingPurpose, LanguageModel};
+use crate::retry::RetryPolicy;
+use async_trait::async_trait;
 use base64::{engine::general_purpose, Engine as _};
+use futures::StreamExt;
 use gemini_rust::prelude::*;
+use gemini_rust::FinishReason;
 use std::env;
-use std::fs;
+use std::time::Duration;
+
+/// Generation is slower than a PokéAPI lookup, so each attempt gets longer
+/// than the default five seconds.
+const GEMINI_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Most texts the API embeds in one batch request.
+const EMBED_BATCH_SIZE: usize = 100;
 
 pub struct GeminiClient {
     client: Gemini,
+    /// The model is fixed per client, so embeddings need their own.
+    embedder: Gemini,
+    embedding_model: String,
+    retry: RetryPolicy,
+    temperature: Option<f32>,
+    max_output_tokens: Option<i32>,
+    system_prompt: Option<String>,
 }
 
 impl GeminiClient {
     pub fn new() -> Result<Self> {
-        let api_key =
-            env::var("GEMINI_API_KEY").context("GEMINI_API_KEY environment variable not set")?;
+        Self::from_config(&Config::default())
+    }
+
+    /// Client using the model, sampling settings and system prompt from `config`.
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let api_key = env::var("GEMINI_API_KEY").map_err(|_| PokidexError::MissingApiKey)?;
+
+        let client = model_client(&api_key, &config.model)?;
+        let embedder = model_client(&api_key, &config.embedding_model)?;
 
-        let client = Gemini::new(&api_key).context("Failed to create Gemini client")?;
+        Ok(Self {
+            client,
+            embedder,
+            embedding_model: config.embedding_model.clone(),
+            retry: RetryPolicy::default().with_timeout(GEMINI_TIMEOUT),
+            temperature: config.temperature,
+            max_output_tokens: config.max_output_tokens,
+            system_prompt: config.system_prompt.clone(),
+        })
+    }
 
-        Ok(Self { client })
+    /// Request with the configured sampling settings and system prompt. Image
+    /// prompts start from it too, so every call honors the config.
+    fn text_request(&self) -> ContentBuilder {
+        let mut request = self.client.generate_content();
+        if let Some(system_prompt) = &self.system_prompt {
+            request = request.with_system_instruction(system_prompt.as_str());
+        }
+        if let Some(temperature) = self.temperature {
+            request = request.with_temperature(temperature);
+        }
+        if let Some(max_output_tokens) = self.max_output_tokens {
+            request = request.with_max_output_tokens(max_output_tokens);
+        }
+        request
     }
+}
 
-    pub async fn generate_content(&self, prompt: &str) -> Result<String> {
+#[async_trait]
+impl LanguageModel for GeminiClient {
+    async fn generate_content(&self, prompt: &str) -> Result<String> {
         let response = self
-            .client
-            .generate_content()
-            .with_user_message(prompt)
-            .execute()
-            .await
-            .context("Failed to send request to Gemini API")?;
-
-        Ok(response.text())
+            .retry
+            .run(|| async {
+                self.text_request()
+                    .with_user_message(prompt)
+                    .execute()
+                    .await
+                    .map_err(classify_error)
+            })
+            .await?;
+
+        response_text(&response)
     }
 
-    pub async fn identify_pokemon_from_image(&self, image_path: &str) -> Result<String> {
-        let image_bytes =
-            fs::read(image_path).context(format!("Failed to read image file: {}", image_path))?;
+    async fn generate_content_streaming(
+        &self,
+        prompt: &str,
+        on_text: &mut (dyn for<'t> FnMut(&'t str) + Send),
+    ) -> Result<String> {
+        // Only opening the stream is retried; once text has been handed out,
+        // starting over would repeat it.
+        let mut stream = self
+            .retry
+            .run(|| async {
+                self.text_request()
+                    .with_user_message(prompt)
+                    .execute_stream()
+                    .await
+                    .map_err(classify_error)
+            })
+            .await?;
+
+        let mut text = String::new();
+        loop {
+            let chunk = match tokio::time::timeout(GEMINI_TIMEOUT, stream.next()).await {
+                Ok(Some(chunk)) => chunk.map_err(classify_error)?,
+                Ok(None) => break,
+                Err(_) => return Err(PokidexError::Timeout(GEMINI_TIMEOUT)),
+            };
+            check_blocked(&chunk)?;
+            let delta = chunk.text();
+            if !delta.is_empty() {
+                on_text(&delta);
+                text.push_str(&delta);
+            }
+        }
 
-        let mime_type = Self::mime_type_for_path(image_path);
-        let image_b64 = general_purpose::STANDARD.encode(image_bytes);
+        if text.trim().is_empty() {
+            return Err(PokidexError::LlmRefused("empty response".to_string()));
+        }
+        Ok(text)
+    }
 
-        let prompt = "You are validating whether an image contains a Pokémon. Return STRICT JSON only with one of these shapes: {\"type\":\"pokemon\",\"name\":\"<pokemon name>\"} or {\"type\":\"not_pokemon\",\"reason\":\"<short reason>\"}. If unsure, return not_pokemon.";
+    async fn generate_with_image(&self, prompt: &str, image: &ImageInput) -> Result<String> {
+        let image_b64 = general_purpose::STANDARD.encode(&image.bytes);
 
         let response = self
-            .client
-            .generate_content()
-            .with_user_message(prompt)
-            .with_inline_data(image_b64, &mime_type)
-            .execute()
-            .await
-            .context("Failed to send image request to Gemini API")?;
-
-        Ok(response.text())
+            .retry
+            .run(|| async {
+                self.text_request()
+                    .with_user_message(prompt)
+                    .with_inline_data(image_b64.clone(), &image.mime_type)
+                    .execute()
+                    .await
+                    .map_err(classify_error)
+            })
+            .await?;
+
+        response_text(&response)
     }
 
-    fn mime_type_for_path(path: &str) -> String {
-        let lower = path.to_lowercase();
-        if lower.ends_with(".png") {
-            "image/png".to_string()
-        } else if lower.ends_with(".jpg") || lower.ends_with(".jpeg") {
-            "image/jpeg".to_string()
-        } else if lower.ends_with(".webp") {
-            "image/webp".to_string()
-        } else if lower.ends_with(".gif") {
-            "image/gif".to_string()
-        } else {
-            "application/octet-stream".to_string()
+    async fn embed(&self, texts: &[String], purpose: EmbeddingPurpose) -> Result<Vec<Vec<f32>>> {
+        let task_type = match purpose {
+            EmbeddingPurpose::Document => TaskType::RetrievalDocument,
+            EmbeddingPurpose::Query => TaskType::RetrievalQuery,
+        };
+        let mut vectors = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(EMBED_BATCH_SIZE) {
+            let response = self
+                .retry
+                .run(|| async {
+                    self.embedder
+                        .embed_content()
+                        .with_chunks(batch.to_vec())
+                        .with_task_type(task_type.clone())
+                        .execute_batch()
+                        .await
+                        .map_err(classify_error)
+                })
+                .await?;
+            if response.embeddings.len() != batch.len() {
+                return Err(PokidexError::ParseFailure(format!(
+                    "Gemini returned {} embeddings for {} texts",
+                    response.embeddings.len(),
+                    batch.len()
+                )));
+            }
+            vectors.extend(response.embeddings.into_iter().map(|e| e.values));
         }
+        Ok(vectors)
     }
 
-    pub async fn generate_with_context(&self, context: &str, user_query: &str) -> Result<String> {
-        let prompt = format!(
-            "You are a helpful Pokemon assistant. Use the following Pokemon data to answer the user's question accurately and concisely.\n\n\
-            Pokemon Data:\n{}\n\n\
-            User Question: {}\n\n\
-            Provide a clear, accurate answer based on the Pokemon data above. If the data doesn't contain the answer, say so.",
-            context, user_query
-        );
+    fn embedding_model(&self) -> Option<String> {
+        Some(self.embedding_model.clone())
+    }
+}
 
-        self.generate_content(&prompt).await
+fn model_client(api_key: &str, model: &str) -> Result<Gemini> {
+    // The API expects "models/<name>"; accept the bare name in config too.
+    let model = if model.starts_with("models/") {
+        model.to_string()
+    } else {
+        format!("models/{}", model)
+    };
+    Gemini::with_model(api_key, model)
+        .map_err(|e| PokidexError::InvalidRequest(format!("failed to create Gemini client: {}", e)))
+}
+
+/// Returns the answer text, or [`PokidexError::LlmRefused`] when the prompt
+/// or the answer was blocked and no usable text came back.
+fn response_text(response: &GenerationResponse) -> Result<String> {
+    check_blocked(response)?;
+    let text = response.text();
+    if text.trim().is_empty() {
+        return Err(PokidexError::LlmRefused("empty response".to_string()));
+    }
+    Ok(text)
+}
+
+/// Fails with [`PokidexError::LlmRefused`] if safety filters stopped the
+/// prompt or the answer.
+fn check_blocked(response: &GenerationResponse) -> Result<()> {
+    if let Some(reason) = response
+        .prompt_feedback
+        .as_ref()
+        .and_then(|f| f.block_reason.as_ref())
+    {
+        return Err(PokidexError::LlmRefused(format!(
+            "prompt blocked ({:?})",
+            reason
+        )));
+    }
+
+    let finish_reason = response
+        .candidates
+        .first()
+        .and_then(|c| c.finish_reason.as_ref());
+    if let Some(
+        reason @ (FinishReason::Safety
+        | FinishReason::Recitation
+        | FinishReason::Blocklist
+        | FinishReason::ProhibitedContent
+        | FinishReason::Spii
+        | FinishReason::ImageSafety),
+    ) = finish_reason
+    {
+        return Err(PokidexError::LlmRefused(format!(
+            "answer blocked ({:?})",
+            reason
+        )));
+    }
+    Ok(())
+}
+
+fn classify_error(err: ClientError) -> PokidexError {
+    match err {
+        ClientError::BadResponse {
+            code: 404,
+            description,
+        } => PokidexError::InvalidRequest(format!(
+            "Gemini model not found: {}",
+            description.unwrap_or_default()
+        )),
+        ClientError::BadResponse { code, description } => PokidexError::from_status(
+            "Gemini",
+            code,
+            description.unwrap_or_else(|| "no description".to_string()),
+        ),
+        ClientError::PerformRequest { source, .. } | ClientError::PerformRequestNew { source } => {
+            PokidexError::from_reqwest("Gemini", &source)
+        }
+        // A body cut off in transit is worth retrying; one that arrived but
+        // isn't the JSON we expect will read the same next time.
+        ClientError::DecodeResponse { source }
+            if source.is_connect() || source.is_timeout() || source.is_body() =>
+        {
+            PokidexError::from_reqwest("Gemini", &source)
+        }
+        ClientError::DecodeResponse { source } => {
+            PokidexError::ParseFailure(format!("Gemini: {}", source))
+        }
+        ClientError::Deserialize { source } => {
+            PokidexError::ParseFailure(format!("Gemini: {}", source))
+        }
+        e @ (ClientError::BadPart { .. } | ClientError::MissingResponseHeader { .. }) => {
+            PokidexError::Network(format!("Gemini: {}", e))
+        }
+        other => PokidexError::InvalidRequest(format!("Gemini: {}", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::classify_error;
+    use crate::error::PokidexError;
+    use gemini_rust::ClientError;
+
+    #[tokio::test]
+    async fn does_not_retry_undecodable_responses() {
+        let response = reqwest::Response::from(axum::http::Response::new("<html>oops</html>"));
+        let source = response.json::<serde_json::Value>().await.unwrap_err();
+        let error = classify_error(ClientError::DecodeResponse { source });
+        assert!(
+            matches!(error, PokidexError::ParseFailure(_)),
+            "{:?}",
+            error
+        );
+        assert!(!error.is_transient());
     }
 }
@@ -0,0 +1,349 @@
+use crate::error::{PokidexError, Result};
+use crate::retry::RetryPolicy;
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat, ImageReader};
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Longest side sent to the model; larger images are downscaled first.
+const MAX_DIMENSION: u32 = 2048;
+
+/// Images above this size are re-encoded even when their dimensions are fine.
+const MAX_UPLOAD_BYTES: usize = 4 * 1024 * 1024;
+
+/// Gemini's limit for inline image data, which is sent base64-encoded. HEIC
+/// can't be decoded locally, so it has to fit as is.
+const MAX_INLINE_BYTES: usize = 20 * 1024 * 1024;
+
+const JPEG_QUALITY: u8 = 85;
+
+/// Largest download accepted by [`ImageInput::from_url`].
+const MAX_DOWNLOAD_BYTES: usize = 25 * 1024 * 1024;
+
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Extensions `find_images` picks up. The format itself is always taken
+/// from the file's contents.
+const IMAGE_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "webp", "gif", "bmp", "tif", "tiff", "heic", "heif",
+];
+
+/// Image bytes plus the MIME type sent along with them to the model.
+#[derive(Debug, Clone)]
+pub struct ImageInput {
+    pub bytes: Vec<u8>,
+    pub mime_type: String,
+}
+
+impl ImageInput {
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = fs::read(path)
+            .map_err(|e| PokidexError::InvalidImage(format!("{}: {}", path.display(), e)))?;
+        Self::from_bytes(bytes).map_err(|e| {
+            PokidexError::InvalidImage(format!("{}: {}", path.display(), image_reason(e)))
+        })
+    }
+
+    /// Downloads an image over HTTPS. The response's content type is ignored
+    /// like any other hint; the bytes go through [`ImageInput::from_bytes`].
+    pub async fn from_url(url: &str) -> Result<Self> {
+        let parsed = reqwest::Url::parse(url)
+            .map_err(|e| PokidexError::InvalidRequest(format!("{}: {}", url, e)))?;
+        if parsed.scheme() != "https" {
+            return Err(PokidexError::InvalidRequest(format!(
+                "only https image URLs are supported: {}",
+                url
+            )));
+        }
+
+        let http = reqwest::Client::new();
+        let bytes = RetryPolicy::default()
+            .with_timeout(DOWNLOAD_TIMEOUT)
+            .run(|| download(&http, parsed.clone()))
+            .await?;
+        Self::from_bytes(bytes)
+            .map_err(|e| PokidexError::InvalidImage(format!("{}: {}", url, image_reason(e))))
+    }
+
+    /// Checks what `bytes` really are and gets them ready for the model.
+    ///
+    /// The format comes from the file signature, not from a name or a
+    /// client-supplied content type. PNG, JPEG and WebP up to 2048 px on a
+    /// side and 4 MB are sent unchanged; larger ones are downscaled and
+    /// re-encoded. GIF, BMP and TIFF are converted locally. HEIC/HEIF is
+    /// passed through because Gemini reads it natively, but can't be
+    /// downscaled, so one too large to send inline is rejected. Anything else
+    /// is rejected with [`PokidexError::InvalidImage`].
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self> {
+        if bytes.is_empty() {
+            return Err(PokidexError::InvalidImage("image is empty".to_string()));
+        }
+
+        if let Some(mime_type) = heif_mime_type(&bytes) {
+            let encoded_len = bytes.len().div_ceil(3) * 4;
+            if encoded_len > MAX_INLINE_BYTES {
+                return Err(PokidexError::InvalidImage(format!(
+                    "{} image is {} MB once encoded, over the {} MB upload limit, and \
+                     resizing HEIC/HEIF is not supported; convert it to JPEG or PNG first",
+                    mime_type,
+                    encoded_len / (1024 * 1024),
+                    MAX_INLINE_BYTES / (1024 * 1024)
+                )));
+            }
+            return Ok(Self {
+                bytes,
+                mime_type: mime_type.to_string(),
+            });
+        }
+
+        let format = match image::guess_format(&bytes) {
+            Ok(
+                format @ (ImageFormat::Png
+                | ImageFormat::Jpeg
+                | ImageFormat::WebP
+                | ImageFormat::Gif
+                | ImageFormat::Bmp
+                | ImageFormat::Tiff),
+            ) => format,
+            Ok(format) => {
+                return Err(PokidexError::InvalidImage(format!(
+                    "{:?} images are not supported",
+                    format
+                )))
+            }
+            Err(_) => {
+                return Err(PokidexError::InvalidImage(format!(
+                    "not an image ({})",
+                    describe_non_image(&bytes)
+                )))
+            }
+        };
+
+        if matches!(
+            format,
+            ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::WebP
+        ) && bytes.len() <= MAX_UPLOAD_BYTES
+        {
+            let (width, height) = ImageReader::with_format(Cursor::new(&bytes), format)
+                .into_dimensions()
+                .map_err(|e| PokidexError::InvalidImage(e.to_string()))?;
+            if width.max(height) <= MAX_DIMENSION {
+                return Ok(Self {
+                    bytes,
+                    mime_type: format.to_mime_type().to_string(),
+                });
+            }
+        }
+
+        let decoded = image::load_from_memory_with_format(&bytes, format)
+            .map_err(|e| PokidexError::InvalidImage(e.to_string()))?;
+        Self::encode(decoded)
+    }
+
+    /// Downscales to [`MAX_DIMENSION`] and re-encodes: PNG while it keeps
+    /// transparency within [`MAX_UPLOAD_BYTES`], JPEG otherwise.
+    fn encode(image: DynamicImage) -> Result<Self> {
+        let image = if image.width().max(image.height()) > MAX_DIMENSION {
+            image.resize(MAX_DIMENSION, MAX_DIMENSION, FilterType::Lanczos3)
+        } else {
+            image
+        };
+        let failed = |e: image::ImageError| PokidexError::InvalidImage(e.to_string());
+
+        if image.color().has_alpha() {
+            let mut png = Vec::new();
+            image
+                .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+                .map_err(failed)?;
+            if png.len() <= MAX_UPLOAD_BYTES {
+                return Ok(Self {
+                    bytes: png,
+                    mime_type: "image/png".to_string(),
+                });
+            }
+        }
+
+        let mut jpeg = Vec::new();
+        JpegEncoder::new_with_quality(&mut jpeg, JPEG_QUALITY)
+            .encode_image(&image.to_rgb8())
+            .map_err(failed)?;
+        Ok(Self {
+            bytes: jpeg,
+            mime_type: "image/jpeg".to_string(),
+        })
+    }
+}
+
+/// Fetches `url`, giving up once the body passes [`MAX_DOWNLOAD_BYTES`].
+async fn download(http: &reqwest::Client, url: reqwest::Url) -> Result<Vec<u8>> {
+    let too_large = || {
+        PokidexError::InvalidImage(format!(
+            "{}: larger than {} MB",
+            url,
+            MAX_DOWNLOAD_BYTES / (1024 * 1024)
+        ))
+    };
+
+    let mut response = http
+        .get(url.clone())
+        .send()
+        .await
+        .map_err(|e| PokidexError::from_reqwest("image host", &e))?;
+    let status = response.status();
+    if !status.is_success() {
+        // A missing image is the caller's mistake, not a missing Pokémon.
+        return Err(match status.as_u16() {
+            429 | 500.. => PokidexError::from_status("image host", status.as_u16(), url.as_str()),
+            code => PokidexError::InvalidRequest(format!("{}: HTTP {}", url, code)),
+        });
+    }
+    if response
+        .content_length()
+        .is_some_and(|len| len > MAX_DOWNLOAD_BYTES as u64)
+    {
+        return Err(too_large());
+    }
+
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| PokidexError::from_reqwest("image host", &e))?
+    {
+        if bytes.len() + chunk.len() > MAX_DOWNLOAD_BYTES {
+            return Err(too_large());
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(bytes)
+}
+
+/// The message inside an `InvalidImage`, so the path can be prefixed
+/// without repeating the variant's own wording.
+fn image_reason(error: PokidexError) -> String {
+    match error {
+        PokidexError::InvalidImage(reason) => reason,
+        other => other.to_string(),
+    }
+}
+
+/// `image/heic` or `image/heif` for ISO-BMFF files with a HEIF brand.
+fn heif_mime_type(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.len() < 12 || &bytes[4..8] != b"ftyp" {
+        return None;
+    }
+    match &bytes[8..12] {
+        b"heic" | b"heix" | b"heim" | b"heis" | b"hevc" | b"hevx" => Some("image/heic"),
+        b"mif1" | b"msf1" => Some("image/heif"),
+        _ => None,
+    }
+}
+
+/// A short guess at what a non-image file is, for the error message.
+fn describe_non_image(bytes: &[u8]) -> &'static str {
+    let head = &bytes[..bytes.len().min(512)];
+    if head.starts_with(b"%PDF") {
+        "looks like a PDF document"
+    } else if head.starts_with(b"PK\x03\x04") {
+        "looks like a ZIP archive"
+    } else if head.len() >= 12 && &head[4..8] == b"ftyp" {
+        "looks like a video"
+    } else if looks_like_text(head) {
+        "looks like a text file"
+    } else {
+        "unrecognised file signature"
+    }
+}
+
+fn looks_like_text(head: &[u8]) -> bool {
+    match std::str::from_utf8(head) {
+        Ok(text) => !text.contains('\0'),
+        // Only a multi-byte character cut off at the end of `head`.
+        Err(e) => e.error_len().is_none(),
+    }
+}
+
+/// Whether `path` has an extension we treat as an image when scanning a
+/// directory.
+pub fn is_image_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| IMAGE_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+}
+
+/// Image files in `dir` (and its subdirectories when `recursive`), sorted
+/// so reports come out in a stable order.
+pub fn find_images(dir: &Path, recursive: bool) -> Result<Vec<PathBuf>> {
+    let mut images = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                if recursive {
+                    pending.push(path);
+                }
+            } else if is_image_path(&path) {
+                images.push(path);
+            }
+        }
+    }
+    images.sort();
+    Ok(images)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ImageInput, MAX_DIMENSION};
+    use image::{DynamicImage, ImageFormat, RgbImage, RgbaImage};
+    use std::io::Cursor;
+
+    fn encoded(image: DynamicImage, format: ImageFormat) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut bytes), format)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn sniffs_and_converts_formats() {
+        let png = encoded(RgbaImage::new(8, 8).into(), ImageFormat::Png);
+        let input = ImageInput::from_bytes(png.clone()).unwrap();
+        assert_eq!(input.mime_type, "image/png");
+        assert_eq!(input.bytes, png);
+
+        let bmp = encoded(RgbImage::new(8, 8).into(), ImageFormat::Bmp);
+        assert_eq!(ImageInput::from_bytes(bmp).unwrap().mime_type, "image/jpeg");
+
+        let mut heic = b"\0\0\0\x18ftypheic".to_vec();
+        heic.extend([0; 16]);
+        assert_eq!(
+            ImageInput::from_bytes(heic.clone()).unwrap().mime_type,
+            "image/heic"
+        );
+        // 16 MB is over the inline limit once base64-encoded.
+        heic.resize(16 * 1024 * 1024, 0);
+        let too_large = ImageInput::from_bytes(heic).unwrap_err().to_string();
+        assert!(too_large.contains("not supported"), "{}", too_large);
+
+        let text = ImageInput::from_bytes(b"name,type\npikachu,electric\n".to_vec());
+        assert!(text.unwrap_err().to_string().contains("text file"));
+    }
+
+    #[test]
+    fn downscales_large_images() {
+        let large = encoded(
+            RgbImage::new(MAX_DIMENSION * 2, 16).into(),
+            ImageFormat::Png,
+        );
+        let input = ImageInput::from_bytes(large).unwrap();
+        let resized = image::load_from_memory(&input.bytes).unwrap();
+        assert_eq!(resized.width(), MAX_DIMENSION);
+        assert_eq!(input.mime_type, "image/jpeg");
+    }
+}
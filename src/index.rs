@@ -0,0 +1,560 @@
+//! The semantic search index: one entry per species, written from its
+//! genus, types, abilities, evolution methods and Pokédex text and embedded
+//! ahead of time by `pokidex index`. It lets questions that describe a
+//! Pokémon without naming it ("which Pokémon is shaped like a bell?") be
+//! answered from the data.
+
+use crate::error::{PokidexError, Result};
+use crate::format::FormatOptions;
+use crate::llm::{EmbeddingPurpose, LanguageModel};
+use crate::source::{PokemonDataSource, MAX_CONCURRENT_REQUESTS};
+use crate::text::{capitalized, title_case};
+use futures::stream::{self, StreamExt};
+use rustemon::model::evolution::{ChainLink, EvolutionChain, EvolutionDetail};
+use rustemon::model::pokemon::{Pokemon, PokemonSpecies};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const INDEX_FILE: &str = "semantic-index.json";
+
+/// Model recorded for indexes built with local embeddings.
+pub const LOCAL_MODEL: &str = "local-tfidf";
+
+/// Buckets words are hashed into by the local embeddings.
+const LOCAL_DIMENSIONS: usize = 512;
+
+/// Words that say nothing about which Pokémon is meant.
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "can", "do", "does", "for", "from", "has",
+    "have", "in", "into", "is", "it", "its", "like", "of", "on", "or", "that", "the", "their",
+    "them", "they", "this", "to", "was", "what", "when", "which", "who", "with", "pokemon",
+    "pokémon",
+];
+
+/// One species as stored in the index.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexEntry {
+    /// Species name, e.g. `chingling`.
+    pub name: String,
+    /// National Dex number.
+    pub id: i64,
+    /// The text that was embedded, given to the model when retrieved.
+    pub text: String,
+    /// Unit-length embedding of `text`.
+    pub vector: Vec<f32>,
+}
+
+/// Embedded species entries, saved as JSON in the cache directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SemanticIndex {
+    /// The embedding model behind the vectors, or [`LOCAL_MODEL`].
+    pub model: String,
+    /// Inverse document frequency of each local embedding bucket; empty for
+    /// model embeddings.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub idf: Vec<f32>,
+    pub entries: Vec<IndexEntry>,
+}
+
+impl SemanticIndex {
+    /// Where the index lives under `cache_dir`.
+    pub fn path(cache_dir: &Path) -> PathBuf {
+        cache_dir.join(INDEX_FILE)
+    }
+
+    /// Reads the index at `path`, or `None` if none has been built.
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        serde_json::from_slice(&bytes).map(Some).map_err(|e| {
+            PokidexError::ParseFailure(format!(
+                "{}: {} (rebuild it with `pokidex index`)",
+                path.display(),
+                e
+            ))
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json =
+            serde_json::to_vec(self).map_err(|e| PokidexError::ParseFailure(e.to_string()))?;
+        // Written aside and renamed so an interrupted save keeps the old index.
+        let partial = path.with_extension("part");
+        fs::write(&partial, json)?;
+        fs::rename(&partial, path)?;
+        Ok(())
+    }
+
+    /// Indexes `documents` (species name, Dex number, text) with local
+    /// embeddings.
+    pub fn local(documents: Vec<(String, i64, String)>) -> Self {
+        let counts: Vec<HashMap<usize, f32>> = documents
+            .iter()
+            .map(|(_, _, text)| bucket_counts(text))
+            .collect();
+        let mut frequency = vec![0usize; LOCAL_DIMENSIONS];
+        for buckets in &counts {
+            for bucket in buckets.keys() {
+                frequency[*bucket] += 1;
+            }
+        }
+        let total = documents.len() as f32;
+        let idf: Vec<f32> = frequency
+            .iter()
+            .map(|&n| ((total + 1.0) / (n as f32 + 1.0)).ln() + 1.0)
+            .collect();
+
+        let entries = documents
+            .into_iter()
+            .zip(&counts)
+            .map(|((name, id, text), buckets)| IndexEntry {
+                name,
+                id,
+                text,
+                vector: weigh(buckets, &idf),
+            })
+            .collect();
+        Self {
+            model: LOCAL_MODEL.to_string(),
+            idf,
+            entries,
+        }
+    }
+
+    /// Whether `query` names an indexed species, in which case the tools
+    /// can look it up directly.
+    pub(crate) fn mentions_species(&self, query: &str) -> bool {
+        let names: HashSet<&str> = self.entries.iter().map(|e| e.name.as_str()).collect();
+        query
+            .to_lowercase()
+            .split(|c: char| !c.is_alphanumeric() && c != '-')
+            .any(|word| names.contains(word))
+    }
+
+    /// The `k` entries closest to `query`, best first. Queries are embedded
+    /// the way the index was: locally, or by `llm` if it still uses the
+    /// index's model.
+    pub async fn search(
+        &self,
+        llm: &dyn LanguageModel,
+        query: &str,
+        k: usize,
+    ) -> Result<Vec<&IndexEntry>> {
+        let vector = if self.model == LOCAL_MODEL {
+            weigh(&bucket_counts(query), &self.idf)
+        } else {
+            match llm.embedding_model() {
+                Some(model) if model == self.model => {}
+                current => {
+                    return Err(PokidexError::InvalidRequest(format!(
+                        "the search index was embedded with {} but the model is set up for {}; rebuild it with `pokidex index`",
+                        self.model,
+                        current.as_deref().unwrap_or("no embeddings")
+                    )))
+                }
+            }
+            let vectors = llm
+                .embed(&[query.to_string()], EmbeddingPurpose::Query)
+                .await?;
+            normalized(vectors.into_iter().next().unwrap_or_default())
+        };
+        Ok(self.nearest(&vector, k))
+    }
+
+    fn nearest(&self, vector: &[f32], k: usize) -> Vec<&IndexEntry> {
+        let mut scored: Vec<(f32, &IndexEntry)> = self
+            .entries
+            .iter()
+            .map(|entry| (dot(&entry.vector, vector), entry))
+            .filter(|(score, _)| *score > 0.0)
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.into_iter().take(k).map(|(_, entry)| entry).collect()
+    }
+}
+
+/// Builds an index of every species `source` lists, or the first `limit`.
+/// Entries are embedded by `embedder`, or locally when it's `None`.
+/// `on_progress` hears how many species have been read out of how many.
+pub async fn build(
+    source: &dyn PokemonDataSource,
+    embedder: Option<&dyn LanguageModel>,
+    options: &FormatOptions,
+    limit: Option<usize>,
+    on_progress: &mut (dyn FnMut(usize, usize) + Send),
+) -> Result<SemanticIndex> {
+    let mut names = source.pokemon_names().await?;
+    if let Some(limit) = limit {
+        names.truncate(limit);
+    }
+    if names.is_empty() {
+        return Err(PokidexError::InvalidRequest(
+            "the data source has no species to index".to_string(),
+        ));
+    }
+
+    // Collected up front for the same reason as in `get_many`.
+    let reads: Vec<_> = names
+        .iter()
+        .map(|name| read_species(source, name, options))
+        .collect();
+    let mut reads = stream::iter(reads).buffered(MAX_CONCURRENT_REQUESTS);
+    let mut documents = Vec::with_capacity(names.len());
+    let mut done = 0;
+    while let Some(document) = reads.next().await {
+        documents.extend(document?);
+        done += 1;
+        on_progress(done, names.len());
+    }
+
+    let Some(llm) = embedder else {
+        return Ok(SemanticIndex::local(documents));
+    };
+    let model = llm.embedding_model().ok_or_else(|| {
+        PokidexError::InvalidRequest(
+            "this model backend has no embeddings; use embeddings = \"local\"".to_string(),
+        )
+    })?;
+    let texts: Vec<String> = documents.iter().map(|(_, _, text)| text.clone()).collect();
+    let vectors = llm.embed(&texts, EmbeddingPurpose::Document).await?;
+    let entries = documents
+        .into_iter()
+        .zip(vectors)
+        .map(|((name, id, text), vector)| IndexEntry {
+            name,
+            id,
+            text,
+            vector: normalized(vector),
+        })
+        .collect();
+    Ok(SemanticIndex {
+        model,
+        idf: Vec::new(),
+        entries,
+    })
+}
+
+/// The document for species `name`, or `None` if PokéAPI has no such
+/// species. Its evolution chain is an optional extra.
+async fn read_species(
+    source: &dyn PokemonDataSource,
+    name: &str,
+    options: &FormatOptions,
+) -> Result<Option<(String, i64, String)>> {
+    let (species, pokemon) = match source.get_species_with_default(name).await {
+        Ok(found) => found,
+        Err(PokidexError::NotFound { .. }) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let chain = source.get_evolution_chain(&species).await.ok();
+    let text = document(&species, Some(&pokemon), chain.as_ref(), options);
+    Ok(Some((species.name.clone(), species.id, text)))
+}
+
+/// The indexed text for `species`: what it is, what it looks like, how it
+/// evolves and its Pokédex description.
+pub(crate) fn document(
+    species: &PokemonSpecies,
+    pokemon: Option<&Pokemon>,
+    chain: Option<&EvolutionChain>,
+    options: &FormatOptions,
+) -> String {
+    let mut sentences = Vec::new();
+    let genus = species
+        .genera
+        .iter()
+        .find(|g| g.language.name == options.language)
+        .or_else(|| species.genera.iter().find(|g| g.language.name == "en"));
+    sentences.push(match genus {
+        Some(genus) => format!(
+            "{} (#{}), the {}.",
+            title_case(&species.name),
+            species.id,
+            genus.genus
+        ),
+        None => format!("{} (#{}).", title_case(&species.name), species.id),
+    });
+
+    if let Some(pokemon) = pokemon {
+        let types: Vec<String> = pokemon
+            .types
+            .iter()
+            .map(|t| title_case(&t.type_.name))
+            .collect();
+        sentences.push(format!("{} type.", types.join("/")));
+        let abilities: Vec<String> = pokemon
+            .abilities
+            .iter()
+            .map(|a| words(&a.ability.name))
+            .collect();
+        sentences.push(format!("Abilities: {}.", abilities.join(", ")));
+    }
+
+    let mut looks = vec![format!("{} color", words(&species.color.name))];
+    if let Some(shape) = &species.shape {
+        looks.push(format!("{} shape", words(&shape.name)));
+    }
+    if let Some(habitat) = &species.habitat {
+        looks.push(format!("lives in {} habitats", words(&habitat.name)));
+    }
+    sentences.push(format!("{}.", capitalized(&looks.join(", "))));
+    sentences.push(format!(
+        "Introduced in {}.",
+        words(&species.generation.name)
+    ));
+    if species.is_legendary {
+        sentences.push("Legendary Pokémon.".to_string());
+    }
+    if species.is_mythical {
+        sentences.push("Mythical Pokémon.".to_string());
+    }
+
+    if let Some(chain) = chain {
+        sentences.extend(evolutions(&chain.chain, &species.name));
+    }
+    sentences.extend(options.flavor_text(species));
+    sentences.join(" ")
+}
+
+/// Sentences on what `species` evolves from and into, and how.
+fn evolutions(chain: &ChainLink, species: &str) -> Vec<String> {
+    let Some((link, parent)) = find_link(chain, species, None) else {
+        return Vec::new();
+    };
+    let mut sentences = Vec::new();
+    if let Some(parent) = parent {
+        sentences.push(evolution_sentence(
+            "Evolves from",
+            &parent.species.name,
+            &link.evolution_details,
+        ));
+    }
+    for next in &link.evolves_to {
+        sentences.push(evolution_sentence(
+            "Evolves into",
+            &next.species.name,
+            &next.evolution_details,
+        ));
+    }
+    if sentences.is_empty() {
+        sentences.push("Does not evolve.".to_string());
+    }
+    sentences
+}
+
+/// The link for `species` and the one it evolves from.
+fn find_link<'a>(
+    link: &'a ChainLink,
+    species: &str,
+    parent: Option<&'a ChainLink>,
+) -> Option<(&'a ChainLink, Option<&'a ChainLink>)> {
+    if link.species.name == species {
+        return Some((link, parent));
+    }
+    link.evolves_to
+        .iter()
+        .find_map(|next| find_link(next, species, Some(link)))
+}
+
+/// e.g. "Evolves into Gengar by trade." Methods from different games are
+/// joined with "or".
+fn evolution_sentence(lead: &str, species: &str, details: &[EvolutionDetail]) -> String {
+    let mut methods: Vec<String> = Vec::new();
+    for method in details.iter().map(evolution_method) {
+        if !method.is_empty() && !methods.contains(&method) {
+            methods.push(method);
+        }
+    }
+    if methods.is_empty() {
+        format!("{} {}.", lead, title_case(species))
+    } else {
+        format!("{} {} {}.", lead, title_case(species), methods.join(" or "))
+    }
+}
+
+/// How one evolution happens, e.g. "at level 36" or "by trade holding a
+/// metal coat".
+fn evolution_method(detail: &EvolutionDetail) -> String {
+    let mut parts = Vec::new();
+    match (detail.trigger.name.as_str(), detail.min_level) {
+        ("level-up", Some(level)) => parts.push(format!("at level {}", level)),
+        ("level-up", None) => parts.push("by leveling up".to_string()),
+        ("trade", _) => parts.push("by trade".to_string()),
+        // The item says it all.
+        ("use-item", _) => {}
+        (trigger, _) => parts.push(format!("by {}", words(trigger))),
+    }
+    if let Some(item) = &detail.item {
+        parts.push(format!("with a {}", words(&item.name)));
+    }
+    if let Some(item) = &detail.held_item {
+        parts.push(format!("holding a {}", words(&item.name)));
+    }
+    if let Some(known_move) = &detail.known_move {
+        parts.push(format!("knowing {}", words(&known_move.name)));
+    }
+    if let Some(move_type) = &detail.known_move_type {
+        parts.push(format!("knowing a {} move", title_case(&move_type.name)));
+    }
+    if detail.min_happiness.is_some() {
+        parts.push("with high friendship".to_string());
+    }
+    if detail.min_affection.is_some() {
+        parts.push("with high affection".to_string());
+    }
+    if detail.min_beauty.is_some() {
+        parts.push("with high beauty".to_string());
+    }
+    if !detail.time_of_day.is_empty() {
+        parts.push(format!("during the {}", detail.time_of_day));
+    }
+    if let Some(location) = &detail.location {
+        parts.push(format!("at {}", words(&location.name)));
+    }
+    if detail.needs_overworld_rain {
+        parts.push("in the rain".to_string());
+    }
+    if let Some(species) = &detail.party_species {
+        parts.push(format!("with {} in the party", title_case(&species.name)));
+    }
+    if let Some(party_type) = &detail.party_type {
+        parts.push(format!(
+            "with a {} type in the party",
+            title_case(&party_type.name)
+        ));
+    }
+    match detail.relative_physical_stats {
+        Some(1) => parts.push("with Attack above Defense".to_string()),
+        Some(0) => parts.push("with Attack equal to Defense".to_string()),
+        Some(-1) => parts.push("with Attack below Defense".to_string()),
+        _ => {}
+    }
+    if let Some(species) = &detail.trade_species {
+        parts.push(format!("for a {}", title_case(&species.name)));
+    }
+    if detail.turn_upside_down {
+        parts.push("with the console upside down".to_string());
+    }
+    match detail.gender {
+        Some(1) => parts.push("if female".to_string()),
+        Some(2) => parts.push("if male".to_string()),
+        _ => {}
+    }
+    parts.join(" ")
+}
+
+/// `metal-coat` → `metal coat`.
+fn words(name: &str) -> String {
+    name.replace('-', " ")
+}
+
+/// Term counts of `text`, hashed into [`LOCAL_DIMENSIONS`] buckets.
+fn bucket_counts(text: &str) -> HashMap<usize, f32> {
+    let mut counts = HashMap::new();
+    for word in text
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.len() > 1 && !STOPWORDS.contains(w))
+    {
+        *counts.entry(bucket(stem(word))).or_insert(0.0) += 1.0;
+    }
+    counts
+}
+
+/// Unit-length TF-IDF vector, with sublinear term frequency.
+fn weigh(counts: &HashMap<usize, f32>, idf: &[f32]) -> Vec<f32> {
+    let mut vector = vec![0.0; LOCAL_DIMENSIONS];
+    for (&bucket, &count) in counts {
+        vector[bucket] = (1.0 + count.ln()) * idf.get(bucket).copied().unwrap_or(1.0);
+    }
+    normalized(vector)
+}
+
+/// Strips the common English endings, so "shaped" finds "shape" and
+/// "evolves" finds "evolve".
+fn stem(word: &str) -> &str {
+    let mut stem = word;
+    if stem.len() > 3 && !stem.ends_with("ss") {
+        stem = stem.strip_suffix('s').unwrap_or(stem);
+    }
+    for suffix in ["ing", "ed"] {
+        if stem.len() > suffix.len() + 2 {
+            stem = stem.strip_suffix(suffix).unwrap_or(stem);
+        }
+    }
+    if stem.len() > 3 {
+        stem = stem.strip_suffix('e').unwrap_or(stem);
+    }
+    stem
+}
+
+/// FNV-1a hash of `term`, folded into a bucket.
+fn bucket(term: &str) -> usize {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in term.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    (hash % LOCAL_DIMENSIONS as u64) as usize
+}
+
+fn normalized(mut vector: Vec<f32>) -> Vec<f32> {
+    let norm = dot(&vector, &vector).sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+    vector
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SemanticIndex;
+
+    #[test]
+    fn finds_species_from_a_description() {
+        let document = |name: &str, id, text: &str| (name.to_string(), id, text.to_string());
+        let index = SemanticIndex::local(vec![
+            document(
+                "chingling",
+                433,
+                "Chingling (#433), the Bell Pokémon. Psychic type. Yellow color, ball shape. Evolves into Chimecho by leveling up with high friendship during the night.",
+            ),
+            document(
+                "haunter",
+                93,
+                "Haunter (#93), the Gas Pokémon. Ghost/Poison type. Purple color, arms shape. Evolves from Gastly at level 25. Evolves into Gengar by trade.",
+            ),
+            document(
+                "charmeleon",
+                5,
+                "Charmeleon (#5), the Flame Pokémon. Fire type. Red color, upright shape. Evolves from Charmander at level 16. Evolves into Charizard at level 36.",
+            ),
+        ]);
+
+        let nearest = |query: &str| {
+            let vector = super::weigh(&super::bucket_counts(query), &index.idf);
+            let names: Vec<&str> = index
+                .nearest(&vector, 2)
+                .iter()
+                .map(|e| e.name.as_str())
+                .collect();
+            names.join(",")
+        };
+        assert!(nearest("which pokemon looks like a bell?").starts_with("chingling"));
+        assert!(nearest("ghosts that evolve when traded").starts_with("haunter"));
+        assert!(index.mentions_species("How fast is Haunter's Gengar?"));
+        assert!(!index.mentions_species("which pokémon evolve by trade?"));
+    }
+}
@@ -0,0 +1,56 @@
+//! Pokidex: a Pokémon RAG agent combining PokéAPI data with a language model.
+//!
+//! The `pokidex` binary is a thin layer over this crate's public API. Other
+//! tools can embed the same engine:
+//!
+//! ```no_run
+//! # async fn demo() -> pokidex::Result<()> {
+//! let engine = pokidex::RAGEngine::builder().build()?;
+//! let answer = engine.process_query("What type is Charizard?").await?;
+//! println!("{}", answer.text);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Besides the engine, the public modules hold the pieces the CLI is built
+//! from: the damage calculator, dataset search, the semantic index, batch
+//! runs, formatting, sprites, and the HTTP and MCP servers.
+
+pub mod batch;
+pub mod citation;
+mod config;
+pub mod damage;
+mod error;
+pub mod format;
+mod gemini;
+pub mod image_input;
+pub mod index;
+pub mod llm;
+pub mod mcp;
+mod pokeapi;
+mod rag;
+mod retry;
+pub mod search;
+pub mod server;
+mod session;
+mod source;
+pub mod sprites;
+mod team;
+#[cfg(test)]
+pub(crate) mod test_support;
+mod text;
+mod tools;
+mod types;
+mod verify;
+mod vision;
+
+pub use config::{Backend, Config, Embeddings, Picker, SpriteProtocol};
+pub use error::{PokidexError, Result};
+pub use gemini::GeminiClient;
+pub use image_input::ImageInput;
+pub use llm::{EmbeddingPurpose, LanguageModel};
+pub use pokeapi::PokeApiClient;
+pub use rag::{Answer, AnswerEvent, RAGEngine, RAGEngineBuilder, ToolCall};
+pub use session::{ChatSession, Turn};
+pub use source::PokemonDataSource;
+pub use verify::Mismatch;
@@ -0,0 +1,107 @@
+use crate::config::{Backend, Config};
+use crate::error::{PokidexError, Result};
+use crate::gemini::GeminiClient;
+use crate::image_input::ImageInput;
+use crate::vision::MAX_DETECTIONS;
+use async_trait::async_trait;
+
+/// What a text is embedded for; some models embed search documents and
+/// the questions that look for them differently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmbeddingPurpose {
+    Document,
+    Query,
+}
+
+/// The language model behind the engine.
+///
+/// [`crate::gemini::GeminiClient`] is the default implementation; embedders
+/// can plug in another backend through [`crate::RAGEngineBuilder::llm`].
+#[async_trait]
+pub trait LanguageModel: Send + Sync {
+    /// Sends a single user prompt and returns the model's text answer.
+    async fn generate_content(&self, prompt: &str) -> Result<String>;
+
+    /// Like [`LanguageModel::generate_content`], but hands each piece of the
+    /// answer to `on_text` as it arrives. Backends without streaming report
+    /// the whole answer as a single piece. (The explicit `for<'t>` stops
+    /// `async_trait` from tying each piece to the callback's own lifetime.)
+    async fn generate_content_streaming(
+        &self,
+        prompt: &str,
+        on_text: &mut (dyn for<'t> FnMut(&'t str) + Send),
+    ) -> Result<String> {
+        let text = self.generate_content(prompt).await?;
+        on_text(&text);
+        Ok(text)
+    }
+
+    /// Sends `prompt` together with `image` and returns the text answer.
+    async fn generate_with_image(&self, prompt: &str, image: &ImageInput) -> Result<String>;
+
+    /// Asks the model whether `image` shows a Pokémon, naming up to
+    /// `max_candidates` possibilities. The answer is the raw JSON decision
+    /// described in `vision_prompt`.
+    async fn identify_pokemon_from_image(
+        &self,
+        image: &ImageInput,
+        max_candidates: usize,
+    ) -> Result<String> {
+        self.generate_with_image(&vision_prompt(max_candidates), image)
+            .await
+    }
+
+    /// Embeds each of `texts` for semantic search, in order. Backends
+    /// without an embedding model fail with [`PokidexError::InvalidRequest`].
+    async fn embed(&self, texts: &[String], purpose: EmbeddingPurpose) -> Result<Vec<Vec<f32>>> {
+        let _ = (texts, purpose);
+        Err(PokidexError::InvalidRequest(
+            "this model backend has no embeddings; use embeddings = \"local\"".to_string(),
+        ))
+    }
+
+    /// Name of the model [`LanguageModel::embed`] uses. An index keeps it so
+    /// questions are only embedded with the model that embedded its entries.
+    fn embedding_model(&self) -> Option<String> {
+        None
+    }
+
+    async fn generate_with_context(&self, context: &str, user_query: &str) -> Result<String> {
+        self.generate_content(&context_prompt(context, user_query))
+            .await
+    }
+}
+
+/// The model `config` selects.
+pub fn from_config(config: &Config) -> Result<Box<dyn LanguageModel>> {
+    match config.backend {
+        Backend::Gemini => Ok(Box::new(GeminiClient::from_config(config)?)),
+    }
+}
+
+/// Prompt asking the model to answer `user_query` from `context` only.
+pub(crate) fn context_prompt(context: &str, user_query: &str) -> String {
+    format!(
+        "You are a helpful Pokemon assistant. Use the following Pokemon data to answer the user's question accurately and concisely.\n\n\
+        Pokemon Data:\n{}\n\n\
+        User Question: {}\n\n\
+        Provide a clear, accurate answer based on the Pokemon data above. If the data doesn't contain the answer, say so.",
+        context, user_query
+    )
+}
+
+/// Prompt asking for a strict-JSON verdict on an image: one detection per
+/// Pokémon visible, each with a confidence, a region hint and up to
+/// `max_candidates` candidate names.
+pub(crate) fn vision_prompt(max_candidates: usize) -> String {
+    format!(
+        "You are validating whether an image contains Pokémon. Return STRICT JSON only with one of these shapes: \
+        {{\"type\":\"pokemon\",\"detections\":[{{\"name\":\"<most likely pokemon name>\",\"confidence\":<0.0-1.0>,\"region\":\"<where it is, e.g. left, top-right, opponent side>\",\"candidates\":[{{\"name\":\"<pokemon name>\",\"confidence\":<0.0-1.0>}}]}}]}} \
+        or {{\"type\":\"not_pokemon\",\"reason\":\"<short reason>\",\"confidence\":<0.0-1.0>}}. \
+        Add one detection per Pokémon visible (battle screenshots and team previews show several, up to {}). \
+        For each, list up to {} candidates, most likely first, when you are not sure which Pokémon it is. \
+        If unsure whether the image shows a Pokémon at all, return not_pokemon.",
+        MAX_DETECTIONS,
+        max_candidates.max(1)
+    )
+}
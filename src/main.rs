@@ -1,156 +1,839 @@
-mod file_picker;
-mod gemini;
-mod pokeapi;
-mod rag;
-
-use anyhow::Result;
-use clap::{Parser, Subcommand};
-use rag::RAGEngine;
+mod cli;
+
+use clap::{Args, Parser, Subcommand};
+use cli::terminal_image::SpriteView;
+use cli::{clipboard, file_picker, markdown, repl, tui};
+use futures::StreamExt;
+use pokidex::batch::{self, ReportFormat, ReportWriter};
+use pokidex::citation;
+use pokidex::damage::{self, CalcRequest, Combatant, Nature, Spread, DEFAULT_LEVEL};
+use pokidex::format::{self, FormatOptions};
+use pokidex::image_input;
+use pokidex::index::{self, SemanticIndex};
+use pokidex::llm;
+use pokidex::mcp::McpServer;
+use pokidex::search::{Dataset, LazyDataset, SearchQuery, Stat, StatFilter};
+use pokidex::server;
+use pokidex::sprites::SpriteKind;
+use pokidex::{
+    Backend, Config, Embeddings, ImageInput, Picker, PokeApiClient, PokemonDataSource,
+    PokidexError, RAGEngine, Result, SpriteProtocol,
+};
+use std::collections::BTreeMap;
+use std::fs;
 use std::io::{self, Write};
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::pin::pin;
+use std::process::ExitCode;
+use std::time::Instant;
+use tokio::io::AsyncReadExt;
 
 #[derive(Parser)]
 #[command(name = "pokidex")]
 #[command(about = "A Pokemon RAG agent powered by Gemini AI and PokéAPI")]
 struct Cli {
+    /// Print model decisions and tool output to stderr
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
+    #[command(flatten)]
+    config: ConfigArgs,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
 
+/// Overrides for `pokidex.toml`; these win over the file and `POKIDEX_*` variables.
+#[derive(Args)]
+struct ConfigArgs {
+    /// Config file to use instead of $XDG_CONFIG_HOME/pokidex/pokidex.toml
+    #[arg(long = "config", global = true, value_name = "PATH")]
+    path: Option<PathBuf>,
+    /// Language model backend
+    #[arg(long, global = true)]
+    backend: Option<Backend>,
+    /// Model name, e.g. gemini-2.5-flash
+    #[arg(long, global = true)]
+    model: Option<String>,
+    /// Sampling temperature
+    #[arg(long, global = true)]
+    temperature: Option<f32>,
+    /// Maximum tokens in a model answer
+    #[arg(long, global = true)]
+    max_output_tokens: Option<i32>,
+    /// PokéAPI language code for flavor text and answers, e.g. en, ja, fr
+    #[arg(long, global = true)]
+    language: Option<String>,
+    /// Game version to prefer for flavor text and learnsets, e.g. scarlet
+    #[arg(long = "game", global = true)]
+    game_version: Option<String>,
+    /// Directory for the PokéAPI response cache
+    #[arg(long, global = true)]
+    cache_dir: Option<PathBuf>,
+    /// Maximum tool calls the model may make per question
+    #[arg(long, global = true)]
+    max_tool_steps: Option<usize>,
+    /// Replace the model's system prompt
+    #[arg(long, global = true)]
+    system_prompt: Option<String>,
+    /// Candidate Pokémon the model may suggest for an image
+    #[arg(long, global = true)]
+    max_image_candidates: Option<usize>,
+    /// How to draw sprites: auto, kitty, iterm, sixel or blocks
+    #[arg(long, global = true)]
+    sprite_protocol: Option<SpriteProtocol>,
+    /// What embeds the search index: llm or local
+    #[arg(long, global = true)]
+    embeddings: Option<Embeddings>,
+    /// Search index entries given to the model for questions naming no Pokémon (0 = off)
+    #[arg(long, global = true)]
+    retrieval_top_k: Option<usize>,
+}
+
+impl ConfigArgs {
+    /// Loads the config file and environment, then applies these flags.
+    /// Returns the effective config and the file it came from, if any.
+    fn load(self) -> Result<(Config, Option<PathBuf>)> {
+        let (mut config, source) = Config::load(self.path.as_deref())?;
+        if let Some(backend) = self.backend {
+            config.backend = backend;
+        }
+        if let Some(model) = self.model {
+            config.model = model;
+        }
+        if self.temperature.is_some() {
+            config.temperature = self.temperature;
+        }
+        if self.max_output_tokens.is_some() {
+            config.max_output_tokens = self.max_output_tokens;
+        }
+        if let Some(language) = self.language {
+            config.language = language;
+        }
+        if self.game_version.is_some() {
+            config.game_version = self.game_version;
+        }
+        if let Some(cache_dir) = self.cache_dir {
+            config.cache_dir = cache_dir;
+        }
+        if let Some(max_tool_steps) = self.max_tool_steps {
+            config.max_tool_steps = max_tool_steps;
+        }
+        if self.system_prompt.is_some() {
+            config.system_prompt = self.system_prompt;
+        }
+        if let Some(max_image_candidates) = self.max_image_candidates {
+            config.max_image_candidates = max_image_candidates;
+        }
+        if let Some(sprite_protocol) = self.sprite_protocol {
+            config.sprite_protocol = sprite_protocol;
+        }
+        if let Some(embeddings) = self.embeddings {
+            config.embeddings = embeddings;
+        }
+        if let Some(retrieval_top_k) = self.retrieval_top_k {
+            config.retrieval_top_k = retrieval_top_k;
+        }
+        Ok((config, source))
+    }
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Start interactive chat mode
-    Chat,
+    Chat {
+        /// Draw the Pokémon an answer looked up: artwork (default) or front
+        #[arg(long, num_args = 0..=1, default_missing_value = "artwork", value_name = "KIND")]
+        sprites: Option<SpriteKind>,
+    },
+    /// Browse the Pokédex full-screen and ask about the selected Pokémon
+    Tui,
     /// Ask a single question
     Ask {
         /// Your question about Pokemon
         question: String,
     },
+    /// Answer every question in a .txt (one per line) or .jsonl file, writing JSONL results
+    Batch {
+        /// Questions file; .jsonl/.ndjson lines are {"question": ..., "id": ...}
+        file: PathBuf,
+        /// Questions answered at the same time
+        #[arg(long, default_value_t = 4)]
+        concurrency: usize,
+        /// Write results here instead of stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
     /// Identify a Pokemon from an image and return its specs
     IdentifyImage {
-        /// Path to the image file
-        image_path: String,
+        /// Image file path, https URL, or - to read the image from stdin
+        #[arg(required_unless_present = "clipboard", conflicts_with = "clipboard")]
+        image: Option<String>,
+        /// Read the image from the clipboard (wl-paste on Wayland, xclip on X11)
+        #[arg(long)]
+        clipboard: bool,
+        /// Also summarise the Pokémon found as a team (shared weaknesses, type coverage)
+        #[arg(long)]
+        team: bool,
+    },
+    /// Identify every image in a directory and write a CSV or JSON report
+    IdentifyDir {
+        /// Directory to scan for .png/.jpg/.webp/.gif/.bmp/.tiff/.heic files
+        dir: PathBuf,
+        /// Also scan subdirectories
+        #[arg(long, short)]
+        recursive: bool,
+        /// Report format: csv or json
+        #[arg(long, default_value = "csv")]
+        format: ReportFormat,
+        /// Maximum model calls per minute
+        #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u32).range(1..))]
+        rate: u32,
+        /// Write the report here instead of stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
     },
     /// Open system file picker (Finder/File Explorer/dialog) and identify a Pokemon image
-    SelectImage,
+    SelectImage {
+        /// File pickers to try in order: system, zenity, kdialog, terminal
+        #[arg(long = "picker", value_delimiter = ',')]
+        pickers: Vec<Picker>,
+        /// Also summarise the Pokémon found as a team (shared weaknesses, type coverage)
+        #[arg(long)]
+        team: bool,
+    },
+    /// Show a Pokémon's Pokédex entry without asking the model
+    Info {
+        /// Pokemon name or National Dex number
+        name: String,
+        /// Draw its picture above the entry: artwork (default) or front
+        #[arg(long, num_args = 0..=1, default_missing_value = "artwork", value_name = "KIND")]
+        sprite: Option<SpriteKind>,
+    },
+    /// List every Pokémon matching filters, from the local dataset (no Gemini key needed)
+    Search(SearchArgs),
+    /// Calculate the damage one move does, e.g. `calc garchomp earthquake heatran` (no Gemini key needed)
+    Calc(Box<CalcArgs>),
+    /// Pre-fetch Pokemon and species data into the local PokéAPI cache
+    CacheWarm {
+        /// Pokemon names or IDs to fetch (defaults to IDs 1..=limit)
+        names: Vec<String>,
+        /// Number of National Dex entries to fetch when no names are given
+        #[arg(long, default_value_t = 151)]
+        limit: u32,
+    },
+    /// Build the semantic search index used for questions that describe a Pokémon without naming it
+    Index {
+        /// Index only the first N species in National Dex order
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+    /// Serve the engine over HTTP (POST /ask, POST /identify, GET /pokemon/{name})
+    Serve {
+        /// Port to listen on
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+        /// Address to bind
+        #[arg(long, default_value = "127.0.0.1")]
+        host: IpAddr,
+    },
+    /// Run a Model Context Protocol server on stdio exposing the PokéAPI tools (no Gemini key needed)
+    Mcp,
+    /// Inspect the effective configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigCommand,
+    },
+}
+
+#[derive(Args)]
+struct SearchArgs {
+    /// Types a match must all have, e.g. --type fire --type flying (or fire,flying)
+    #[arg(long = "type", value_delimiter = ',')]
+    types: Vec<String>,
+    /// Generation the species was introduced in
+    #[arg(long, visible_alias = "gen")]
+    generation: Option<u8>,
+    /// An ability a match can have, e.g. levitate
+    #[arg(long)]
+    ability: Option<String>,
+    /// Base stat bounds, e.g. speed>100, hp<=60, attack=80..120 or total>=600
+    #[arg(long = "stat", value_name = "FILTER")]
+    stats: Vec<StatFilter>,
+    /// Only legendaries (--legendary false for none)
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    legendary: Option<bool>,
+    /// Only mythicals (--mythical false for none)
+    #[arg(long, num_args = 0..=1, default_missing_value = "true")]
+    mythical: Option<bool>,
+    /// Egg group, e.g. dragon
+    #[arg(long)]
+    egg_group: Option<String>,
+    /// Pokédex color, e.g. red
+    #[arg(long)]
+    color: Option<String>,
+    /// Habitat, e.g. mountain
+    #[arg(long)]
+    habitat: Option<String>,
+    /// Sort by a stat, highest first
+    #[arg(long)]
+    sort: Option<Stat>,
+    /// Sort lowest first
+    #[arg(long, requires = "sort")]
+    ascending: bool,
+    /// Show at most this many matches
+    #[arg(long)]
+    limit: Option<usize>,
+    /// Print the matches as JSON
+    #[arg(long)]
+    json: bool,
+    /// Rebuild the dataset from the PokéAPI cache first
+    #[arg(long)]
+    refresh: bool,
+}
+
+impl SearchArgs {
+    fn query(&self) -> SearchQuery {
+        let mut stats = BTreeMap::new();
+        for filter in &self.stats {
+            stats.insert(filter.stat, filter.range);
+        }
+        SearchQuery {
+            types: self.types.clone(),
+            generation: self.generation,
+            ability: self.ability.clone(),
+            stats,
+            legendary: self.legendary,
+            mythical: self.mythical,
+            egg_group: self.egg_group.clone(),
+            color: self.color.clone(),
+            habitat: self.habitat.clone(),
+            sort: self.sort,
+            ascending: self.ascending,
+            limit: self.limit,
+        }
+    }
+}
+
+#[derive(Args)]
+struct CalcArgs {
+    /// Attacking Pokémon
+    attacker: String,
+    /// Move it uses, e.g. earthquake
+    #[arg(value_name = "MOVE")]
+    move_name: String,
+    /// Defending Pokémon
+    defender: String,
+    /// Level of both Pokémon, unless set per side
+    #[arg(long, default_value_t = DEFAULT_LEVEL)]
+    level: u32,
+    /// Attacker's level [default: --level]
+    #[arg(long)]
+    attacker_level: Option<u32>,
+    /// Attacker's nature, e.g. adamant
+    #[arg(long)]
+    attacker_nature: Option<Nature>,
+    /// Attacker's EVs, e.g. "252 Atk / 4 HP"
+    #[arg(long)]
+    attacker_evs: Option<Spread>,
+    /// Attacker's IVs where not 31, e.g. "0 Atk"
+    #[arg(long)]
+    attacker_ivs: Option<Spread>,
+    /// Attacker's item, e.g. choice-band
+    #[arg(long)]
+    attacker_item: Option<String>,
+    /// Attacker's ability, e.g. adaptability
+    #[arg(long)]
+    attacker_ability: Option<String>,
+    /// Attacker's stat stage for the move's attacking stat, -6 to 6
+    #[arg(long, default_value_t = 0, allow_negative_numbers = true)]
+    attacker_boost: i32,
+    /// Defender's level [default: --level]
+    #[arg(long)]
+    defender_level: Option<u32>,
+    /// Defender's nature, e.g. bold
+    #[arg(long)]
+    defender_nature: Option<Nature>,
+    /// Defender's EVs, e.g. "252 HP / 4 Def"
+    #[arg(long)]
+    defender_evs: Option<Spread>,
+    /// Defender's IVs where not 31
+    #[arg(long)]
+    defender_ivs: Option<Spread>,
+    /// Defender's item, e.g. assault-vest
+    #[arg(long)]
+    defender_item: Option<String>,
+    /// Defender's ability, e.g. levitate
+    #[arg(long)]
+    defender_ability: Option<String>,
+    /// Defender's stat stage for the move's defending stat, -6 to 6
+    #[arg(long, default_value_t = 0, allow_negative_numbers = true)]
+    defender_boost: i32,
+    /// Calculate a critical hit
+    #[arg(long)]
+    crit: bool,
+    /// The attacker is burned
+    #[arg(long)]
+    burned: bool,
+    /// Print the result as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+impl CalcArgs {
+    fn request(&self) -> CalcRequest {
+        CalcRequest {
+            attacker: Combatant {
+                name: self.attacker.clone(),
+                level: self.attacker_level.unwrap_or(self.level),
+                nature: self.attacker_nature,
+                evs: self.attacker_evs.clone().unwrap_or_default(),
+                ivs: self.attacker_ivs.clone().unwrap_or_default(),
+                item: self.attacker_item.clone(),
+                ability: self.attacker_ability.clone(),
+                boost: self.attacker_boost,
+            },
+            defender: Combatant {
+                name: self.defender.clone(),
+                level: self.defender_level.unwrap_or(self.level),
+                nature: self.defender_nature,
+                evs: self.defender_evs.clone().unwrap_or_default(),
+                ivs: self.defender_ivs.clone().unwrap_or_default(),
+                item: self.defender_item.clone(),
+                ability: self.defender_ability.clone(),
+                boost: self.defender_boost,
+            },
+            move_name: self.move_name.clone(),
+            critical: self.crit,
+            burned: self.burned,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Print the merged config (file, environment and flags) as TOML
+    Show,
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
     dotenv::dotenv().ok(); // Load .env file if present
 
     let cli = Cli::parse();
 
-    let rag_engine = RAGEngine::new()?;
+    // The server drains in-flight requests on Ctrl-C itself; everything else
+    // is simply abandoned.
+    let result = if matches!(cli.command, Some(Commands::Serve { .. })) {
+        run(cli).await
+    } else {
+        tokio::select! {
+            result = run(cli) => result,
+            _ = ctrl_c() => {
+                println!("\nReceived Ctrl-C, exiting...");
+                Ok(())
+            }
+        }
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let (config, config_source) = cli.config.load()?;
+
+    // These commands don't need a model, so they must work without a Gemini
+    // key (`index` only wants one for model embeddings).
+    match cli.command {
+        Some(Commands::Index { limit }) => {
+            return build_index(&config, limit).await;
+        }
+        Some(Commands::Search(args)) => {
+            return search(&config, &args).await;
+        }
+        Some(Commands::Calc(args)) => {
+            return calc(&config, &args).await;
+        }
+        Some(Commands::CacheWarm { names, limit }) => {
+            return warm_cache(&config, names, limit).await;
+        }
+        Some(Commands::Config {
+            action: ConfigCommand::Show,
+        }) => {
+            return show_config(&config, config_source);
+        }
+        Some(Commands::Info { name, sprite }) => {
+            return show_info(&config, &name, sprite).await;
+        }
+        Some(Commands::Mcp) => {
+            let source = PokeApiClient::with_cache_dir(&config.cache_dir)?;
+            let server = McpServer::new(
+                source,
+                FormatOptions::from(&config),
+                LazyDataset::new(&config.cache_dir),
+            );
+            let stdin = tokio::io::BufReader::new(tokio::io::stdin());
+            return server.serve(stdin, tokio::io::stdout()).await;
+        }
+        _ => {}
+    }
 
-    let _ctrlc = tokio::spawn(async {
-        tokio::signal::ctrl_c()
-            .await
-            .expect("Failed to listen for ctrl-c");
-        println!("\nReceived Ctrl-C, exiting...");
-        std::process::exit(0);
-    });
+    let file_pickers = config.file_pickers.clone();
+    let (cache_dir, sprite_protocol) = (config.cache_dir.clone(), config.sprite_protocol);
+    let rag_engine = RAGEngine::builder()
+        .config(config)
+        .verbose(cli.verbose)
+        .build()?;
 
     match cli.command {
+        Some(Commands::Serve { port, host }) => {
+            let addr = SocketAddr::new(host, port);
+            println!("Listening on http://{}", addr);
+            server::serve(rag_engine, addr, async {
+                ctrl_c().await;
+                println!("\nReceived Ctrl-C, finishing in-flight requests...");
+            })
+            .await?;
+        }
         Some(Commands::Ask { question }) => {
             println!("Processing your question...\n");
-            match rag_engine.process_query(&question).await {
-                Ok(response) => {
-                    println!("Assistant: {}", response);
-                }
-                Err(e) => {
-                    eprintln!("Error: {}", e);
-                    std::process::exit(1);
-                }
+            let answer = rag_engine.process_query(&question).await?;
+            println!("Assistant: {}", markdown::render_for_stdout(&answer.text));
+            if let Some(footer) = citation::format_sources(&answer.sources) {
+                println!("\n{}", footer);
             }
         }
-        Some(Commands::IdentifyImage { image_path }) => {
+        Some(Commands::Batch {
+            file,
+            concurrency,
+            output,
+        }) => {
+            run_batch(&rag_engine, &file, concurrency, output.as_deref()).await?;
+        }
+        Some(Commands::IdentifyImage {
+            image,
+            clipboard,
+            team,
+        }) => {
+            let image = load_image(image.as_deref(), clipboard).await?;
             println!("Analyzing image...\n");
-            match rag_engine.process_image_query(&image_path).await {
-                Ok(response) => println!("Assistant: {}", response),
-                Err(e) => {
-                    eprintln!("Error: {}", e);
-                    std::process::exit(1);
-                }
-            }
+            let response = rag_engine.process_image_with(&image, team).await?;
+            println!("Assistant: {}", markdown::render_for_stdout(&response));
         }
-        Some(Commands::SelectImage) => {
+        Some(Commands::IdentifyDir {
+            dir,
+            recursive,
+            format,
+            rate,
+            output,
+        }) => {
+            identify_dir(
+                &rag_engine,
+                &dir,
+                recursive,
+                format,
+                rate,
+                output.as_deref(),
+            )
+            .await?;
+        }
+        Some(Commands::SelectImage { pickers, team }) => {
             println!("Opening file picker...\n");
-            match file_picker::pick_image_file() {
-                Ok(selected_path) => {
-                    println!("Selected: {}", selected_path.display());
-                    println!("Analyzing image...\n");
-                    match rag_engine
-                        .process_image_query(selected_path.to_string_lossy().as_ref())
-                        .await
-                    {
-                        Ok(response) => println!("Assistant: {}", response),
-                        Err(e) => {
-                            eprintln!("Error: {}", e);
-                            std::process::exit(1);
-                        }
-                    }
-                }
-                Err(e) => {
-                    eprintln!("Error: {}", e);
-                    std::process::exit(1);
-                }
-            }
+            let pickers = if pickers.is_empty() {
+                &file_pickers
+            } else {
+                &pickers
+            };
+            let selected_path = file_picker::pick_image_file(pickers)?;
+            println!("Selected: {}", selected_path.display());
+            println!("Analyzing image...\n");
+            let image = ImageInput::from_path(&selected_path)?;
+            let response = rag_engine.process_image_with(&image, team).await?;
+            println!("Assistant: {}", markdown::render_for_stdout(&response));
+        }
+        Some(Commands::Chat { sprites }) => {
+            let sprites =
+                sprites.and_then(|kind| SpriteView::new(&cache_dir, sprite_protocol, kind));
+            repl::run(rag_engine, &cache_dir, sprites).await?;
         }
-        Some(Commands::Chat) | None => {
-            run_chat_mode(rag_engine).await?;
+        None => {
+            repl::run(rag_engine, &cache_dir, None).await?;
+        }
+        Some(Commands::Tui) => {
+            tui::run(rag_engine, &cache_dir).await?;
+        }
+        Some(
+            Commands::Info { .. }
+            | Commands::CacheWarm { .. }
+            | Commands::Index { .. }
+            | Commands::Search(_)
+            | Commands::Calc(_)
+            | Commands::Config { .. }
+            | Commands::Mcp,
+        ) => {
+            unreachable!("handled before engine setup")
         }
     }
 
     Ok(())
 }
 
-async fn run_chat_mode(rag_engine: RAGEngine) -> Result<()> {
-    println!("Welcome to Pokidex RAG Agent!");
-    println!("Ask me anything about Pokemon. Type 'quit' or 'exit' to leave.\n");
+/// Reads the image given to `identify-image`: the clipboard, stdin for `-`,
+/// a download for URLs, otherwise a file. All of them end up in
+/// [`ImageInput::from_bytes`].
+async fn load_image(image: Option<&str>, clipboard: bool) -> Result<ImageInput> {
+    if clipboard {
+        return ImageInput::from_bytes(clipboard::read_image()?);
+    }
+    match image.unwrap_or_default() {
+        "-" => {
+            let mut bytes = Vec::new();
+            tokio::io::stdin().read_to_end(&mut bytes).await?;
+            ImageInput::from_bytes(bytes)
+        }
+        url if url.contains("://") => ImageInput::from_url(url).await,
+        path => ImageInput::from_path(path),
+    }
+}
 
-    loop {
-        print!("You: ");
-        io::stdout().flush()?;
+async fn ctrl_c() {
+    tokio::signal::ctrl_c()
+        .await
+        .expect("Failed to listen for ctrl-c");
+}
 
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
+fn show_config(config: &Config, source: Option<PathBuf>) -> Result<()> {
+    let toml = config.to_toml()?;
+    match (source, Config::default_path()) {
+        (Some(path), _) => println!("# Loaded from {}", path.display()),
+        (None, Some(path)) => println!("# No config file found (looked for {})", path.display()),
+        (None, None) => println!("# No config file found"),
+    }
+    print!("{}", toml);
+    Ok(())
+}
 
-        let query = input.trim();
+async fn show_info(config: &Config, name: &str, sprite: Option<SpriteKind>) -> Result<()> {
+    let client = PokeApiClient::with_cache_dir(&config.cache_dir)?;
+    let name = PokeApiClient::normalize_pokemon_name(name);
+    let (pokemon, species) = client.get_pokemon_with_species(&name).await?;
 
-        if query.is_empty() {
-            continue;
+    let view =
+        sprite.and_then(|kind| SpriteView::new(&config.cache_dir, config.sprite_protocol, kind));
+    if let Some(view) = view {
+        // The entry is still worth printing when the picture can't be had.
+        if let Err(e) = view.show(&pokemon).await {
+            eprintln!("(no sprite: {})", e);
         }
+    }
+    let options = FormatOptions::from(config);
+    println!(
+        "{}",
+        format::format_pokemon_entry(&pokemon, species.as_ref(), &options)
+    );
+    Ok(())
+}
 
-        if query == "quit" || query == "exit" {
-            println!("Goodbye!");
-            break;
-        }
+async fn warm_cache(config: &Config, names: Vec<String>, limit: u32) -> Result<()> {
+    let names = if names.is_empty() {
+        (1..=limit).map(|id| id.to_string()).collect()
+    } else {
+        names
+            .iter()
+            .map(|n| PokeApiClient::normalize_pokemon_name(n))
+            .collect::<Vec<_>>()
+    };
 
-        if query == "help" {
-            println!("Ask me questions about Pokemon! Examples:");
-            println!("  - What are Pikachu's stats?");
-            println!("  - What type is Charizard?");
-            println!("  - What moves can Pikachu learn?");
-            println!("  - You can also run: pokidex identify-image ./pokemon.png");
-            println!("  - Or open file picker: pokidex select-image");
-            println!("\nType 'quit' or 'exit' to leave.\n");
-            continue;
+    println!("Warming cache for {} Pokemon...", names.len());
+    let client = PokeApiClient::with_cache_dir(&config.cache_dir)?;
+    let refs: Vec<&str> = names.iter().map(String::as_str).collect();
+    let results = client.get_many_with_species(&refs).await;
+
+    let mut failed = 0;
+    let mut first_error = None;
+    // Species can legitimately be missing for alternate forms, so they are
+    // reported as a count rather than as failures.
+    let mut species_missing = 0;
+    for (name, (pokemon, species)) in names.iter().zip(results) {
+        if let Err(e) = pokemon {
+            eprintln!("  {}: {}", name, e);
+            failed += 1;
+            first_error.get_or_insert(e);
+        }
+        if species.is_none() {
+            species_missing += 1;
         }
+    }
 
-        print!("Assistant: ");
-        io::stdout().flush()?;
+    println!(
+        "Cached {}/{} Pokemon ({} without species data).",
+        names.len() - failed,
+        names.len(),
+        species_missing
+    );
+    first_error.map_or(Ok(()), Err)
+}
 
-        match rag_engine.process_query(query).await {
-            Ok(response) => {
-                println!("{}", response);
-            }
-            Err(e) => {
-                eprintln!("Error: {}", e);
-                println!("Please try again or type 'help' for examples.");
-            }
+async fn build_index(config: &Config, limit: Option<usize>) -> Result<()> {
+    let source = PokeApiClient::with_cache_dir(&config.cache_dir)?;
+    let embedder = match config.embeddings {
+        Embeddings::Llm => Some(llm::from_config(config)?),
+        Embeddings::Local => None,
+    };
+
+    let options = FormatOptions::from(config);
+    let index = index::build(
+        &source,
+        embedder.as_deref(),
+        &options,
+        limit,
+        &mut report_progress,
+    )
+    .await?;
+
+    let path = SemanticIndex::path(&config.cache_dir);
+    index.save(&path)?;
+    println!(
+        "Indexed {} species with {} into {}",
+        index.entries.len(),
+        index.model,
+        path.display()
+    );
+    Ok(())
+}
+
+async fn search(config: &Config, args: &SearchArgs) -> Result<()> {
+    let path = Dataset::path(&config.cache_dir);
+    let saved = if args.refresh {
+        None
+    } else {
+        Dataset::load(&path)?
+    };
+    let dataset = match saved {
+        Some(dataset) => dataset,
+        None => {
+            eprintln!("Building the Pokédex dataset from the PokéAPI cache...");
+            let source = PokeApiClient::with_cache_dir(&config.cache_dir)?;
+            let dataset = Dataset::build(&source, &mut report_progress).await?;
+            dataset.save(&path)?;
+            dataset
         }
-        println!();
+    };
+
+    let results = dataset.search(&args.query())?;
+    if args.json {
+        let json = serde_json::to_string_pretty(&results.entries)
+            .map_err(|e| PokidexError::ParseFailure(e.to_string()))?;
+        println!("{}", json);
+    } else {
+        print!("{}", results.format());
     }
+    Ok(())
+}
 
+async fn calc(config: &Config, args: &CalcArgs) -> Result<()> {
+    let client = PokeApiClient::with_cache_dir(&config.cache_dir)?;
+    let damage = damage::calc(&client, &args.request()).await?;
+    if args.json {
+        let json = serde_json::to_string_pretty(&damage)
+            .map_err(|e| PokidexError::ParseFailure(e.to_string()))?;
+        println!("{}", json);
+    } else {
+        print!("{}", damage.format());
+    }
     Ok(())
 }
+
+/// Progress line for commands that read every species.
+fn report_progress(done: usize, total: usize) {
+    eprint!("\rRead {}/{} species", done, total);
+    if done == total {
+        eprintln!();
+    }
+}
+
+async fn run_batch(
+    engine: &RAGEngine,
+    file: &Path,
+    concurrency: usize,
+    output: Option<&Path>,
+) -> Result<()> {
+    let questions = batch::read_questions(file)?;
+    let mut out: Box<dyn Write> = match output {
+        Some(path) => Box::new(io::BufWriter::new(fs::File::create(path)?)),
+        None => Box::new(io::stdout()),
+    };
+
+    eprintln!("Answering {} questions...", questions.len());
+    let started = Instant::now();
+    let mut results = batch::run_batch(engine, &questions, concurrency);
+    let mut failed = 0;
+    let mut first_error = None;
+    while let Some(result) = results.next().await {
+        let line = serde_json::to_string(&result)
+            .map_err(|e| PokidexError::ParseFailure(e.to_string()))?;
+        writeln!(out, "{}", line)?;
+        // Flush per line so an interrupted run keeps what it finished.
+        out.flush()?;
+
+        if let Some(error) = result.error {
+            eprintln!("  {}: {}", result.question, error);
+            failed += 1;
+            first_error.get_or_insert(error);
+        }
+    }
+
+    eprintln!(
+        "Answered {}/{} questions in {:.1}s.",
+        questions.len() - failed,
+        questions.len(),
+        started.elapsed().as_secs_f32()
+    );
+    first_error.map_or(Ok(()), Err)
+}
+
+async fn identify_dir(
+    engine: &RAGEngine,
+    dir: &Path,
+    recursive: bool,
+    format: ReportFormat,
+    rate: u32,
+    output: Option<&Path>,
+) -> Result<()> {
+    let images = image_input::find_images(dir, recursive)?;
+    let out: Box<dyn Write> = match output {
+        Some(path) => Box::new(io::BufWriter::new(fs::File::create(path)?)),
+        None => Box::new(io::stdout()),
+    };
+    let mut report = ReportWriter::new(out, format);
+
+    eprintln!(
+        "Identifying {} images (at most {} per minute)...",
+        images.len(),
+        rate
+    );
+    let mut rows = pin!(batch::identify_images(engine, &images, rate));
+    let mut found = 0;
+    let mut first_error = None;
+    while let Some(row) = rows.next().await {
+        report.write(&row)?;
+        if row.pokemon.is_some() {
+            found += 1;
+        }
+        if let Some(error) = row.error {
+            eprintln!("  {}: {}", row.file.display(), error);
+            first_error.get_or_insert(error);
+        }
+    }
+    report.finish()?;
+
+    eprintln!("Found Pokémon in {}/{} images.", found, images.len());
+    first_error.map_or(Ok(()), Err)
+}
@@ -0,0 +1,352 @@
+//! Model Context Protocol server, used by `pokidex mcp`.
+//!
+//! Speaks JSON-RPC 2.0 over newline-delimited stdio and exposes the
+//! agent's data tools plus `pokemon://{name}` resources. It only needs a
+//! [`PokemonDataSource`]; no language model is involved, the client brings
+//! its own.
+
+use crate::error::{PokidexError, Result};
+use crate::format::{self, FormatOptions};
+use crate::pokeapi::PokeApiClient;
+use crate::rag::ToolCall;
+use crate::search::LazyDataset;
+use crate::source::PokemonDataSource;
+use crate::tools::{self, Tool, ToolArgs, TOOLS};
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+
+const PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+const RESOURCE_SCHEME: &str = "pokemon://";
+
+/// Pokémon listed by `resources/list` (the original 151, by Dex ID); any
+/// other entry can still be read through the URI template.
+const LISTED_POKEMON: u32 = 151;
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+const RESOURCE_NOT_FOUND: i64 = -32002;
+
+/// A JSON-RPC error reply.
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+pub struct McpServer {
+    data_source: Box<dyn PokemonDataSource>,
+    format_options: FormatOptions,
+    dataset: LazyDataset,
+}
+
+impl McpServer {
+    pub fn new(
+        data_source: impl PokemonDataSource + 'static,
+        format_options: FormatOptions,
+        dataset: LazyDataset,
+    ) -> Self {
+        Self {
+            data_source: Box::new(data_source),
+            format_options,
+            dataset,
+        }
+    }
+
+    /// Answers requests read from `input` until it closes.
+    pub async fn serve<R, W>(&self, input: R, mut output: W) -> Result<()>
+    where
+        R: AsyncBufRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        let mut lines = input.lines();
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(reply) = self.handle_line(&line).await {
+                let mut bytes = serde_json::to_vec(&reply)
+                    .map_err(|e| PokidexError::ParseFailure(e.to_string()))?;
+                bytes.push(b'\n');
+                output.write_all(&bytes).await?;
+                output.flush().await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles one message; notifications get no reply.
+    async fn handle_line(&self, line: &str) -> Option<Value> {
+        let message: Value = match serde_json::from_str(line) {
+            Ok(message) => message,
+            Err(e) => {
+                return Some(error_reply(
+                    Value::Null,
+                    RpcError::new(PARSE_ERROR, e.to_string()),
+                ))
+            }
+        };
+        let id = message.get("id").cloned()?;
+        let method = message.get("method").and_then(Value::as_str).unwrap_or("");
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+        let reply = match self.dispatch(method, &params).await {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(e) => error_reply(id, e),
+        };
+        Some(reply)
+    }
+
+    async fn dispatch(&self, method: &str, params: &Value) -> std::result::Result<Value, RpcError> {
+        match method {
+            "initialize" => Ok(initialize(params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(list_tools()),
+            "tools/call" => self.call_tool(params).await,
+            "resources/list" => Ok(list_resources()),
+            "resources/templates/list" => Ok(json!({
+                "resourceTemplates": [{
+                    "uriTemplate": format!("{}{{name}}", RESOURCE_SCHEME),
+                    "name": "Pokémon entry",
+                    "description": "Formatted PokéAPI data for a Pokémon name or National Dex ID",
+                    "mimeType": "text/plain"
+                }]
+            })),
+            "resources/read" => self.read_resource(params).await,
+            other => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("unknown method '{}'", other),
+            )),
+        }
+    }
+
+    /// Runs a tool. Lookup failures are tool results with `isError` set, so
+    /// the client's model can see them; malformed calls are protocol errors.
+    async fn call_tool(&self, params: &Value) -> std::result::Result<Value, RpcError> {
+        let name = params.get("name").and_then(Value::as_str).unwrap_or("");
+        let tool = Tool::find(name)
+            .ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("unknown tool '{}'", name)))?;
+        let arguments = params.get("arguments").cloned().unwrap_or(Value::Null);
+        let call = match tool.args {
+            ToolArgs::Name | ToolArgs::Names => ToolCall {
+                tool: tool.name.to_string(),
+                names: ToolCall::from_json(&arguments)
+                    .names
+                    .iter()
+                    .map(|n| PokeApiClient::normalize_pokemon_name(n))
+                    .collect(),
+                args: Value::Null,
+            },
+            ToolArgs::Query | ToolArgs::Calc => ToolCall {
+                tool: tool.name.to_string(),
+                names: Vec::new(),
+                args: arguments,
+            },
+        };
+        if call.names.is_empty() && matches!(tool.args, ToolArgs::Name | ToolArgs::Names) {
+            return Err(RpcError::new(
+                INVALID_PARAMS,
+                format!("{} needs a Pokémon name", tool.name),
+            ));
+        }
+
+        let (text, is_error) = match tools::run_tool(
+            self.data_source.as_ref(),
+            &self.format_options,
+            &self.dataset,
+            &call,
+        )
+        .await
+        {
+            Ok(output) => (output.text, false),
+            Err(e) => (e.to_string(), true),
+        };
+        Ok(json!({
+            "content": [{ "type": "text", "text": text }],
+            "isError": is_error
+        }))
+    }
+
+    async fn read_resource(&self, params: &Value) -> std::result::Result<Value, RpcError> {
+        let uri = params.get("uri").and_then(Value::as_str).unwrap_or("");
+        let name = uri
+            .strip_prefix(RESOURCE_SCHEME)
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("unsupported URI '{}'", uri)))?;
+        let name = PokeApiClient::normalize_pokemon_name(name);
+
+        match self.data_source.get_pokemon_with_species(&name).await {
+            Ok((pokemon, species)) => Ok(json!({
+                "contents": [{
+                    "uri": uri,
+                    "mimeType": "text/plain",
+                    "text": format::format_pokemon_entry(&pokemon, species.as_ref(), &self.format_options)
+                }]
+            })),
+            Err(e @ PokidexError::NotFound { .. }) => {
+                Err(RpcError::new(RESOURCE_NOT_FOUND, e.to_string()))
+            }
+            Err(e) => Err(RpcError::new(INTERNAL_ERROR, e.to_string())),
+        }
+    }
+}
+
+/// Agrees on the client's protocol version when we support it, otherwise
+/// offers our newest.
+fn initialize(params: &Value) -> Value {
+    let requested = params.get("protocolVersion").and_then(Value::as_str);
+    let version = requested
+        .filter(|v| PROTOCOL_VERSIONS.contains(v))
+        .unwrap_or(PROTOCOL_VERSIONS[0]);
+    json!({
+        "protocolVersion": version,
+        "capabilities": {
+            "tools": {},
+            "resources": {}
+        },
+        "serverInfo": {
+            "name": "pokidex",
+            "version": env!("CARGO_PKG_VERSION")
+        }
+    })
+}
+
+fn list_tools() -> Value {
+    let tools: Vec<Value> = TOOLS
+        .iter()
+        .map(|tool| {
+            json!({
+                "name": tool.name,
+                "description": tool.description,
+                "inputSchema": tool.input_schema()
+            })
+        })
+        .collect();
+    json!({ "tools": tools })
+}
+
+fn list_resources() -> Value {
+    let resources: Vec<Value> = (1..=LISTED_POKEMON)
+        .map(|id| {
+            json!({
+                "uri": format!("{}{}", RESOURCE_SCHEME, id),
+                "name": format!("Pokédex #{}", id),
+                "mimeType": "text/plain"
+            })
+        })
+        .collect();
+    json!({ "resources": resources })
+}
+
+fn error_reply(id: Value, error: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": error.code, "message": error.message }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{initialize, McpServer};
+    use crate::format::FormatOptions;
+    use crate::search::Dataset;
+    use crate::test_support::{entry, EmptySource};
+    use serde_json::{json, Value};
+
+    /// Sends `lines` to a server with a two-entry dataset and no Pokémon
+    /// data, and returns its replies.
+    async fn exchange(lines: &[String]) -> Vec<Value> {
+        let dataset = Dataset {
+            entries: vec![
+                entry("aerodactyl", 142, &["rock", "flying"], 130, false),
+                entry("onix", 95, &["rock", "ground"], 70, false),
+            ],
+        };
+        let server = McpServer::new(EmptySource, FormatOptions::default(), dataset.into());
+        let input = lines.join("\n");
+        let mut output = Vec::new();
+        server.serve(input.as_bytes(), &mut output).await.unwrap();
+        output
+            .split(|&b| b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect()
+    }
+
+    fn request(id: u64, method: &str, params: Value) -> String {
+        json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }).to_string()
+    }
+
+    #[test]
+    fn negotiates_protocol_version() {
+        let known = initialize(&json!({ "protocolVersion": "2024-11-05" }));
+        assert_eq!(known["protocolVersion"], "2024-11-05");
+
+        let unknown = initialize(&json!({ "protocolVersion": "1999-01-01" }));
+        assert_eq!(unknown["protocolVersion"], "2025-06-18");
+    }
+
+    #[tokio::test]
+    async fn lists_and_calls_tools() {
+        let replies = exchange(&[
+            request(1, "tools/list", json!({})),
+            request(
+                2,
+                "tools/call",
+                json!({ "name": "search_pokemon", "arguments": { "types": ["flying"] } }),
+            ),
+            request(
+                3,
+                "tools/call",
+                json!({ "name": "get_pokemon", "arguments": { "name": "MissingNo" } }),
+            ),
+        ])
+        .await;
+
+        let tools = replies[0]["result"]["tools"].as_array().unwrap();
+        assert!(tools.iter().any(|t| t["name"] == "search_pokemon"));
+        assert!(tools.iter().all(|t| t["inputSchema"].is_object()));
+
+        let found = &replies[1]["result"];
+        assert_eq!(found["isError"], false);
+        assert!(found["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("aerodactyl"));
+
+        let missing = &replies[2]["result"];
+        assert_eq!(missing["isError"], true);
+        assert_eq!(missing["content"][0]["text"], "no such Pokémon: missingno");
+    }
+
+    #[tokio::test]
+    async fn reports_protocol_errors() {
+        let replies = exchange(&[
+            request(1, "resources/read", json!({ "uri": "https://pokeapi.co/" })),
+            request(2, "resources/read", json!({ "uri": "pokemon://missingno" })),
+            request(3, "prompts/list", json!({})),
+            json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }).to_string(),
+            r#"{"jsonrpc": "2.0", "id": 4"#.to_string(),
+        ])
+        .await;
+
+        // The notification gets no reply.
+        assert_eq!(replies.len(), 4);
+        let codes: Vec<&Value> = replies.iter().map(|r| &r["error"]["code"]).collect();
+        assert_eq!(codes, [-32602, -32002, -32601, -32700]);
+        assert_eq!(replies[1]["id"], 2);
+        assert!(replies[3]["id"].is_null());
+    }
+}
@@ -1,160 +1,98 @@
-use anyhow::{Context, Result};
-use rustemon::client::RustemonClient;
+use crate::error::{PokidexError, Result};
+use crate::retry::RetryPolicy;
+use crate::source::PokemonDataSource;
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use rustemon::client::{CACacheManager, RustemonClient, RustemonClientBuilder};
+use rustemon::model::evolution::EvolutionChain;
+use rustemon::model::moves::Move;
 use rustemon::model::pokemon::Pokemon;
 use rustemon::model::pokemon::PokemonSpecies;
-use std::fmt::Write;
+use std::future::Future;
+use std::path::PathBuf;
+
+const POKEAPI_BASE_URL: &str = "https://pokeapi.co/api/v2";
 
 pub struct PokeApiClient {
     client: RustemonClient,
+    http: reqwest::Client,
+    retry: RetryPolicy,
 }
 
 impl PokeApiClient {
     pub fn new() -> Self {
         Self {
             client: RustemonClient::default(),
+            http: reqwest::Client::new(),
+            retry: RetryPolicy::default(),
         }
     }
 
-    pub async fn get_pokemon(&self, name_or_id: &str) -> Result<Pokemon> {
-        // Try by name first
-        match rustemon::pokemon::pokemon::get_by_name(name_or_id, &self.client).await {
-            Ok(pokemon) => Ok(pokemon),
-            Err(_) => {
-                // Try parsing as ID
-                if let Ok(id) = name_or_id.parse::<i64>() {
-                    rustemon::pokemon::pokemon::get_by_id(id, &self.client)
-                        .await
-                        .context(format!("Failed to find Pokemon with ID: {}", id))
-                } else {
-                    Err(anyhow::anyhow!("Failed to find Pokemon: {}", name_or_id))
-                }
-            }
-        }
+    /// Client whose HTTP cache lives in `cache_dir` instead of `./rustemon-cache`.
+    pub fn with_cache_dir(cache_dir: impl Into<PathBuf>) -> Result<Self> {
+        let client = RustemonClientBuilder::default()
+            .with_manager(CACacheManager::new(cache_dir.into(), false))
+            .try_build()
+            .map_err(|e| PokidexError::Config(format!("PokéAPI client: {}", e)))?;
+        Ok(Self {
+            client,
+            ..Self::new()
+        })
     }
 
-    pub async fn get_pokemon_species(&self, name_or_id: &str) -> Result<PokemonSpecies> {
-        // Try by name first
-        match rustemon::pokemon::pokemon_species::get_by_name(name_or_id, &self.client).await {
-            Ok(species) => Ok(species),
-            Err(_) => {
-                // Try parsing as ID
-                if let Ok(id) = name_or_id.parse::<i64>() {
-                    rustemon::pokemon::pokemon_species::get_by_id(id, &self.client)
-                        .await
-                        .context(format!("Failed to find Pokemon species with ID: {}", id))
-                } else {
-                    Err(anyhow::anyhow!(
-                        "Failed to find Pokemon species: {}",
-                        name_or_id
-                    ))
+    /// Runs a rustemon call under the retry policy, translating its errors
+    /// into [`PokidexError`] so callers can tell a missing entry from an outage.
+    async fn fetch<T, F, Fut>(&self, endpoint: &str, key: &str, call: F) -> Result<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = std::result::Result<T, rustemon::error::Error>>,
+    {
+        let call = &call;
+        self.retry
+            .run(|| async move {
+                match call().await {
+                    Ok(value) => Ok(value),
+                    Err(e) => Err(self.classify_error(endpoint, key, e).await),
                 }
-            }
-        }
-    }
-
-    pub fn format_pokemon_data(&self, pokemon: &Pokemon) -> String {
-        let mut output = String::new();
-
-        writeln!(output, "Name: {}", pokemon.name).ok();
-        writeln!(output, "ID: {}", pokemon.id).ok();
-
-        // Types
-        write!(output, "Types: ").ok();
-        let types: Vec<String> = pokemon.types.iter().map(|t| t.type_.name.clone()).collect();
-        writeln!(output, "{}", types.join(", ")).ok();
-
-        // Stats
-        writeln!(output, "Stats:").ok();
-        for stat in &pokemon.stats {
-            let stat_name = &stat.stat.name;
-            let base_stat = stat.base_stat;
-            writeln!(output, "  - {}: {}", stat_name, base_stat).ok();
-        }
-
-        // Abilities
-        write!(output, "Abilities: ").ok();
-        let abilities: Vec<String> = pokemon
-            .abilities
-            .iter()
-            .map(|a| {
-                let mut name = a.ability.name.clone();
-                if a.is_hidden {
-                    name.push_str(" (hidden)");
-                }
-                name
             })
-            .collect();
-        writeln!(output, "{}", abilities.join(", ")).ok();
-
-        // Height and Weight
-        writeln!(output, "Height: {} dm", pokemon.height).ok();
-        writeln!(output, "Weight: {} hg", pokemon.weight).ok();
-
-        // Base Experience
-        if let Some(base_exp) = pokemon.base_experience {
-            writeln!(output, "Base Experience: {}", base_exp).ok();
-        }
-
-        output
+            .await
     }
 
-    pub fn format_pokemon_with_species(
+    async fn classify_error(
         &self,
-        pokemon: &Pokemon,
-        species: &PokemonSpecies,
-    ) -> String {
-        let mut output = self.format_pokemon_data(pokemon);
-
-        // Add species information
-        writeln!(output, "\nSpecies Information:").ok();
-        writeln!(output, "  Capture Rate: {}", species.capture_rate).ok();
-        if let Some(base_happiness) = species.base_hapiness {
-            writeln!(output, "  Base Happiness: {}", base_happiness).ok();
+        endpoint: &str,
+        key: &str,
+        err: rustemon::error::Error,
+    ) -> PokidexError {
+        let reqwest_err = match err {
+            rustemon::error::Error::Reqwest(e) => e,
+            rustemon::error::Error::ReqwestMiddleware(reqwest_middleware::Error::Reqwest(e)) => e,
+            rustemon::error::Error::ReqwestMiddleware(e) => {
+                return PokidexError::Network(format!("PokéAPI: {}", e))
+            }
+            rustemon::error::Error::UrlParse(_) => return not_found(endpoint, key),
+            e => return PokidexError::Network(format!("PokéAPI: {}", e)),
+        };
+
+        if reqwest_err.status() == Some(StatusCode::NOT_FOUND) {
+            return not_found(endpoint, key);
         }
-        writeln!(output, "  Is Legendary: {}", species.is_legendary).ok();
-        writeln!(output, "  Is Mythical: {}", species.is_mythical).ok();
-
-        // Flavor text (first English one)
-        if let Some(flavor_text) = species
-            .flavor_text_entries
-            .iter()
-            .find(|e| e.language.name == "en")
-        {
-            writeln!(
-                output,
-                "  Description: {}",
-                flavor_text.flavor_text.replace('\n', " ")
-            )
-            .ok();
+        if !reqwest_err.is_decode() {
+            return PokidexError::from_reqwest("PokéAPI", &reqwest_err);
         }
 
-        output
-    }
-
-    pub async fn extract_pokemon_name(&self, query: &str) -> Option<String> {
-        // Simple extraction - look for capitalized words that might be Pokemon names
-        // This is a basic implementation; could be improved with NLP
-        let words: Vec<&str> = query.split_whitespace().collect();
-
-        for word in words {
-            // Check if word starts with capital letter and is reasonable length
-            if word
-                .chars()
-                .next()
-                .map(|c| c.is_uppercase())
-                .unwrap_or(false)
-                && word.len() > 2
-                && word.len() < 20
-            {
-                // Try to get the Pokemon to verify
-                let normalized = Self::normalize_pokemon_name(word);
-                if let Ok(_) = self.get_pokemon(&normalized).await {
-                    return Some(normalized);
-                }
+        // rustemon decodes the body without checking the status, so a 404
+        // ("Not Found" as plain text) and a 503 error page look the same.
+        // Ask again directly to find out which one it was.
+        let url = format!("{}/{}/{}", POKEAPI_BASE_URL, endpoint, key);
+        match self.http.get(&url).send().await {
+            Ok(resp) if resp.status() == StatusCode::NOT_FOUND => not_found(endpoint, key),
+            Ok(resp) if !resp.status().is_success() => {
+                PokidexError::from_status("PokéAPI", resp.status().as_u16(), key)
             }
+            Ok(_) => PokidexError::ParseFailure(format!("{} '{}': {}", endpoint, key, reqwest_err)),
+            Err(e) => PokidexError::from_reqwest("PokéAPI", &e),
         }
-
-        None
     }
 
     pub fn normalize_pokemon_name(name: &str) -> String {
@@ -193,9 +131,114 @@ impl PokeApiClient {
     }
 }
 
+#[async_trait]
+impl PokemonDataSource for PokeApiClient {
+    async fn get_pokemon(&self, name_or_id: &str) -> Result<Pokemon> {
+        let client = &self.client;
+        match name_or_id.parse::<i64>() {
+            Ok(id) => {
+                self.fetch("pokemon", name_or_id, || {
+                    rustemon::pokemon::pokemon::get_by_id(id, client)
+                })
+                .await
+            }
+            Err(_) => {
+                self.fetch("pokemon", name_or_id, || {
+                    rustemon::pokemon::pokemon::get_by_name(name_or_id, client)
+                })
+                .await
+            }
+        }
+    }
+
+    async fn get_pokemon_species(&self, name_or_id: &str) -> Result<PokemonSpecies> {
+        let client = &self.client;
+        match name_or_id.parse::<i64>() {
+            Ok(id) => {
+                self.fetch("pokemon-species", name_or_id, || {
+                    rustemon::pokemon::pokemon_species::get_by_id(id, client)
+                })
+                .await
+            }
+            Err(_) => {
+                self.fetch("pokemon-species", name_or_id, || {
+                    rustemon::pokemon::pokemon_species::get_by_name(name_or_id, client)
+                })
+                .await
+            }
+        }
+    }
+
+    async fn pokemon_names(&self) -> Result<Vec<String>> {
+        let client = &self.client;
+        let entries = self
+            .fetch("pokemon-species", "", || {
+                rustemon::pokemon::pokemon_species::get_all_entries(client)
+            })
+            .await?;
+        Ok(entries.into_iter().map(|entry| entry.name).collect())
+    }
+
+    async fn get_move(&self, name_or_id: &str) -> Result<Move> {
+        let client = &self.client;
+        match name_or_id.parse::<i64>() {
+            Ok(id) => {
+                self.fetch("move", name_or_id, || {
+                    rustemon::moves::move_::get_by_id(id, client)
+                })
+                .await
+            }
+            Err(_) => {
+                self.fetch("move", name_or_id, || {
+                    rustemon::moves::move_::get_by_name(name_or_id, client)
+                })
+                .await
+            }
+        }
+    }
+
+    async fn get_evolution_chain(&self, species: &PokemonSpecies) -> Result<EvolutionChain> {
+        // The chain is only linked by URL, e.g. .../evolution-chain/67/.
+        let id = species
+            .evolution_chain
+            .as_ref()
+            .and_then(|chain| chain.url.trim_end_matches('/').rsplit('/').next())
+            .and_then(|id| id.parse::<i64>().ok())
+            .ok_or_else(|| {
+                PokidexError::InvalidRequest(format!("no evolution data for {}", species.name))
+            })?;
+        let client = &self.client;
+        self.fetch("evolution-chain", &id.to_string(), || {
+            rustemon::evolution::evolution_chain::get_by_id(id, client)
+        })
+        .await
+    }
+}
+
+/// The error for a PokéAPI 404, naming what `endpoint` holds so a
+/// misspelled move isn't reported as a missing Pokémon.
+fn not_found(endpoint: &str, key: &str) -> PokidexError {
+    let kind = match endpoint {
+        "pokemon" | "pokemon-species" => "Pokémon",
+        "move" => "move",
+        "evolution-chain" => "evolution chain",
+        _ => "PokéAPI resource",
+    };
+    PokidexError::NotFound {
+        kind,
+        name: key.to_string(),
+    }
+}
+
+impl Default for PokeApiClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::PokeApiClient;
+    use super::{not_found, PokeApiClient};
 
     #[test]
     fn normalizes_display_names_to_pokeapi_slugs() {
@@ -225,4 +268,20 @@ mod tests {
         );
         assert_eq!(PokeApiClient::normalize_pokemon_name("Ho-Oh"), "ho-oh");
     }
+
+    #[test]
+    fn names_what_was_not_found() {
+        assert_eq!(
+            not_found("move", "eartquake").to_string(),
+            "no such move: eartquake"
+        );
+        assert_eq!(
+            not_found("pokemon", "pikachoo").to_string(),
+            "no such Pokémon: pikachoo"
+        );
+        assert_eq!(
+            not_found("evolution-chain", "999").to_string(),
+            "no such evolution chain: 999"
+        );
+    }
 }
@@ -1,240 +1,996 @@
-use crate::gemini::GeminiClient;
+use crate::citation::{self, Resource, Source, SourceList};
+use crate::config::Config;
+use crate::error::{PokidexError, Result};
+use crate::format::{self, FormatOptions};
+use crate::image_input::ImageInput;
+use crate::index::SemanticIndex;
+use crate::llm::{self, LanguageModel};
 use crate::pokeapi::PokeApiClient;
-use anyhow::Result;
-use serde_json::Value;
+use crate::search::{self, Dataset, LazyDataset};
+use crate::session::Turn;
+use crate::source::PokemonDataSource;
+use crate::team::TeamSummary;
+use crate::tools::{self, Tool, ToolOutput};
+use crate::verify::{self, Facts, Mismatch};
+use crate::vision::{Detection, Identification, Verdict, MAX_DETECTIONS};
+use futures::future::BoxFuture;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::fmt;
+
+/// Name the index search is reported under in tool events and calls.
+const SEARCH_TOOL: &str = "semantic_search";
+
+/// A tool the model asked the engine to run while answering.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ToolCall {
+    pub tool: String,
+    pub names: Vec<String>,
+    /// Structured arguments of tools that take them, e.g. the filters of
+    /// `search_pokemon`.
+    #[serde(skip_serializing_if = "Value::is_null")]
+    pub args: Value,
+}
+
+impl ToolCall {
+    /// Reads `{"tool": ..., "name": ...}`, `{"tool": ..., "names": [...]}`
+    /// or `{"tool": ..., "args": {...}}`.
+    pub(crate) fn from_json(json: &Value) -> Self {
+        let tool = json
+            .get("tool")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string();
+        let mut names: Vec<String> = json
+            .get("names")
+            .and_then(|v| v.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_str())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        if let Some(name) = json.get("name").and_then(|v| v.as_str()) {
+            names.insert(0, name.to_string());
+        }
+        let args = json.get("args").cloned().unwrap_or(Value::Null);
+        Self { tool, names, args }
+    }
+
+    /// The arguments as shown to the model: the names, or the structured
+    /// arguments as JSON.
+    pub(crate) fn arguments(&self) -> String {
+        if self.args.is_null() {
+            self.names.join(", ")
+        } else {
+            self.args.to_string()
+        }
+    }
+}
+
+/// The engine's reply to a text question.
+#[derive(Debug, Clone, Serialize)]
+pub struct Answer {
+    pub text: String,
+    /// Tools run to produce the answer, in order.
+    pub tool_calls: Vec<ToolCall>,
+    /// The PokéAPI resources the answer cites, or all it retrieved if it
+    /// cites none.
+    pub sources: Vec<Source>,
+    /// Claims the retrieved data contradicts, already noted at the end of
+    /// `text`.
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// Progress reported by [`RAGEngine::process_query_streaming`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AnswerEvent {
+    /// A data lookup is starting.
+    ToolStart { tool: String, names: Vec<String> },
+    /// The lookup finished. A failed data lookup is followed by the error;
+    /// a failed index search is skipped.
+    ToolEnd {
+        tool: String,
+        names: Vec<String>,
+        ok: bool,
+    },
+    /// The next piece of the answer text.
+    TextDelta { text: String },
+}
+
+/// Builds a [`RAGEngine`]. Anything left unset falls back to the defaults:
+/// the configured backend (Gemini, reading `GEMINI_API_KEY`) and the live
+/// PokéAPI cached under the configured cache directory.
+#[derive(Default)]
+pub struct RAGEngineBuilder {
+    config: Config,
+    llm: Option<Box<dyn LanguageModel>>,
+    data_source: Option<Box<dyn PokemonDataSource>>,
+    index: Option<SemanticIndex>,
+    dataset: Option<Dataset>,
+    verbose: bool,
+}
+
+impl RAGEngineBuilder {
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn max_tool_steps(mut self, max_tool_steps: usize) -> Self {
+        self.config.max_tool_steps = max_tool_steps;
+        self
+    }
+
+    pub fn llm(mut self, llm: impl LanguageModel + 'static) -> Self {
+        self.llm = Some(Box::new(llm));
+        self
+    }
+
+    pub fn data_source(mut self, data_source: impl PokemonDataSource + 'static) -> Self {
+        self.data_source = Some(Box::new(data_source));
+        self
+    }
+
+    /// Search `index` instead of the one saved in the cache directory.
+    pub fn index(mut self, index: SemanticIndex) -> Self {
+        self.index = Some(index);
+        self
+    }
+
+    /// Run set queries over `dataset` instead of the one saved in the cache
+    /// directory.
+    pub fn dataset(mut self, dataset: Dataset) -> Self {
+        self.dataset = Some(dataset);
+        self
+    }
+
+    /// Print each model decision and tool output to stderr.
+    pub fn verbose(mut self, verbose: bool) -> Self {
+        self.verbose = verbose;
+        self
+    }
+
+    pub fn build(self) -> Result<RAGEngine> {
+        let llm = match self.llm {
+            Some(llm) => llm,
+            None => llm::from_config(&self.config)?,
+        };
+        let data_source = match self.data_source {
+            Some(data_source) => data_source,
+            None => Box::new(PokeApiClient::with_cache_dir(&self.config.cache_dir)?),
+        };
+        let index = match self.index {
+            Some(index) => Some(index),
+            None => SemanticIndex::load(&SemanticIndex::path(&self.config.cache_dir))?,
+        };
+        let dataset = match self.dataset {
+            Some(dataset) => LazyDataset::from(dataset),
+            None => LazyDataset::new(&self.config.cache_dir),
+        };
+
+        Ok(RAGEngine {
+            llm,
+            data_source,
+            format_options: FormatOptions::from(&self.config),
+            max_tool_steps: self.config.max_tool_steps,
+            max_image_candidates: self.config.max_image_candidates,
+            index,
+            dataset,
+            retrieval_top_k: self.config.retrieval_top_k,
+            verbose: self.verbose,
+        })
+    }
+}
 
 pub struct RAGEngine {
-    gemini: GeminiClient,
-    pokeapi: PokeApiClient,
+    llm: Box<dyn LanguageModel>,
+    data_source: Box<dyn PokemonDataSource>,
+    format_options: FormatOptions,
+    max_tool_steps: usize,
+    max_image_candidates: usize,
+    index: Option<SemanticIndex>,
+    dataset: LazyDataset,
+    retrieval_top_k: usize,
+    verbose: bool,
 }
+
 impl RAGEngine {
+    /// Engine with the default Gemini model and PokéAPI data source.
     pub fn new() -> Result<Self> {
-        Ok(Self {
-            gemini: GeminiClient::new()?,
-            pokeapi: PokeApiClient::new(),
-        })
+        Self::builder().build()
+    }
+
+    pub fn builder() -> RAGEngineBuilder {
+        RAGEngineBuilder::default()
+    }
+
+    pub fn data_source(&self) -> &dyn PokemonDataSource {
+        self.data_source.as_ref()
+    }
+
+    pub fn format_options(&self) -> &FormatOptions {
+        &self.format_options
+    }
+
+    /// Switches the language of flavor text and answers, e.g. `ja`.
+    pub fn set_language(&mut self, language: impl Into<String>) {
+        self.format_options.language = language.into();
+    }
+
+    /// Switches the preferred game version, or drops the preference.
+    pub fn set_game_version(&mut self, game_version: Option<String>) {
+        self.format_options.game_version = game_version;
+    }
+
+    /// Process a user query by asking the model which tool to call (if any),
+    /// executing that tool via the data source, and returning the model's final answer.
+    pub async fn process_query(&self, query: &str) -> Result<Answer> {
+        self.process_query_streaming(query, &[], &mut |_| {}).await
     }
 
-    /// Process a user query by asking Gemini which tool to call (if any),
-    /// executing that tool via the PokéAPI client, and returning Gemini's final answer.
-    pub async fn process_query(&self, query: &str) -> Result<String> {
+    /// [`RAGEngine::process_query`] with earlier turns of the conversation
+    /// as context, reporting tool calls and answer text through `on_event`
+    /// while it works. [`crate::ChatSession`] keeps the history for you.
+    pub async fn process_query_streaming(
+        &self,
+        query: &str,
+        history: &[Turn],
+        on_event: &mut (dyn FnMut(AnswerEvent) + Send),
+    ) -> Result<Answer> {
+        let mut facts = Vec::new();
+        let mut answer = self
+            .answer_query(query, history, &mut facts, on_event)
+            .await?;
+        answer.mismatches = self.check_answer(&answer, &facts);
+        if !answer.mismatches.is_empty() {
+            let note = verify::note(&answer.mismatches);
+            on_event(AnswerEvent::TextDelta { text: note.clone() });
+            answer.text.push_str(&note);
+        }
+        Ok(answer)
+    }
+
+    /// Answers `query`, adding the Pokémon data shown to the model to
+    /// `facts` for [`RAGEngine::check_answer`].
+    async fn answer_query(
+        &self,
+        query: &str,
+        history: &[Turn],
+        facts: &mut Vec<Facts>,
+        on_event: &mut (dyn FnMut(AnswerEvent) + Send),
+    ) -> Result<Answer> {
+        let history = history_section(history);
         // Describe available tools to the model and request a JSON action or a final answer.
-        let tools_description = r#"
-Available tools you can call:
-1) get_pokemon(name) -> Returns detailed Pokemon data for a given name or id.
-2) get_pokemon_species(name) -> Returns species information (flavor text, capture rate, etc.).
-3) get_pokemon_stats(name) -> Returns only base stats for a given pokemon.
-4) get_pokemon_moves(name) -> Returns a compact move list for a given pokemon.
-
-If you need to call a tool, respond with a JSON object exactly in this shape:
-  {"type":"action","tool":"get_pokemon","name":"pikachu"}
-or
-  {"type":"action","tool":"get_pokemon_species","name":"pikachu"}
-or
-  {"type":"action","tool":"get_pokemon_stats","name":"pikachu"}
-or
-  {"type":"action","tool":"get_pokemon_moves","name":"pikachu"}
-
-If you can answer the user directly without calling a tool, respond with:
-  {"type":"final","answer":"<your answer>"}
-"#;
-
-        let initial_prompt = format!(
-            "You are a Pokemon assistant agent. Decide whether to call one of the available tools to fetch Pokemon data, or answer directly. {}\nUser Question: {}",
-            tools_description, query
+        let tools_description = tools::prompt_description();
+
+        let mut tool_calls: Vec<ToolCall> = Vec::new();
+        let mut observations = String::new();
+        let mut sources = SourceList::default();
+
+        if let Some(answer) = self.answer_set_question(query, &history, on_event).await? {
+            return Ok(answer);
+        }
+
+        // A question that describes a Pokémon instead of naming it starts
+        // from the closest index entries, without using up a tool step.
+        tool_calls.extend(
+            self.search_index(query, &mut observations, &mut sources, on_event)
+                .await,
         );
+        let max_calls = tool_calls.len() + self.max_tool_steps;
 
-        let decision = self.gemini.generate_content(&initial_prompt).await?;
-        println!("[RAG] Model decision (raw): {}", decision);
-
-        // Try to parse model decision as JSON
-        if let Ok(json) = serde_json::from_str::<Value>(&decision) {
-            if let Some(t) = json.get("type").and_then(|v| v.as_str()) {
-                match t {
-                    "action" => {
-                        let tool = json.get("tool").and_then(|v| v.as_str()).unwrap_or("");
-                        let name = json.get("name").and_then(|v| v.as_str()).unwrap_or("");
-                        println!(
-                            "[RAG] Model requested action: tool='{}' name='{}'",
-                            tool, name
-                        );
-
-                        // Execute the requested tool
-                        let tool_output = match tool {
-                            "get_pokemon" => {
-                                match self.pokeapi.get_pokemon(name).await {
-                                    Ok(pokemon) => {
-                                        // Try to also fetch species for richer context
-                                        if let Ok(species) =
-                                            self.pokeapi.get_pokemon_species(name).await
-                                        {
-                                            self.pokeapi
-                                                .format_pokemon_with_species(&pokemon, &species)
-                                        } else {
-                                            self.pokeapi.format_pokemon_data(&pokemon)
-                                        }
-                                    }
-                                    Err(e) => format!("Error fetching pokemon: {}", e),
-                                }
-                            }
-                            "get_pokemon_species" => {
-                                match self.pokeapi.get_pokemon_species(name).await {
-                                    Ok(species) => {
-                                        // Try to also fetch the Pokemon to reuse existing formatter
-                                        if let Ok(pokemon) = self.pokeapi.get_pokemon(name).await {
-                                            self.pokeapi
-                                                .format_pokemon_with_species(&pokemon, &species)
-                                        } else {
-                                            // Build a minimal, human-readable species summary without serde
-                                            let mut out = String::new();
-                                            out.push_str("Species Information:\n");
-                                            out.push_str(&format!(
-                                                "  Capture Rate: {}\n",
-                                                species.capture_rate
-                                            ));
-                                            if let Some(base_hap) = species.base_hapiness {
-                                                out.push_str(&format!(
-                                                    "  Base Happiness: {}\n",
-                                                    base_hap
-                                                ));
-                                            }
-                                            out.push_str(&format!(
-                                                "  Is Legendary: {}\n",
-                                                species.is_legendary
-                                            ));
-                                            out.push_str(&format!(
-                                                "  Is Mythical: {}\n",
-                                                species.is_mythical
-                                            ));
-                                            if let Some(flavor_text) = species
-                                                .flavor_text_entries
-                                                .iter()
-                                                .find(|e| e.language.name == "en")
-                                            {
-                                                out.push_str(&format!(
-                                                    "  Description: {}\n",
-                                                    flavor_text.flavor_text.replace('\n', " ")
-                                                ));
-                                            }
-                                            out
-                                        }
-                                    }
-                                    Err(e) => format!("Error fetching species: {}", e),
-                                }
-                            }
-                            "get_pokemon_stats" => match self.pokeapi.get_pokemon(name).await {
-                                Ok(pokemon) => self.pokeapi.format_pokemon_stats(&pokemon),
-                                Err(e) => format!("Error fetching pokemon stats: {}", e),
-                            },
-                            "get_pokemon_moves" => match self.pokeapi.get_pokemon(name).await {
-                                Ok(pokemon) => self.pokeapi.format_pokemon_moves(&pokemon, 30),
-                                Err(e) => format!("Error fetching pokemon moves: {}", e),
-                            },
-                            _ => format!("Unknown tool requested: {}", tool),
-                        };
-
-                        // Ask Gemini to produce a final answer using the tool output
-                        println!("[RAG] Tool output:\n{}", tool_output);
-                        let followup = format!(
-                            "Tool output:\n{}\n\nBased on the tool output above, provide a concise, user-facing answer to the original question: {}",
-                            tool_output, query
-                        );
-                        println!("[RAG] Sending followup to model...");
-
-                        let final_resp = self.gemini.generate_content(&followup).await?;
-                        println!("[RAG] Model final response: {}", final_resp);
-                        return Ok(final_resp);
-                    }
-                    "final" => {
-                        if let Some(ans) = json.get("answer").and_then(|v| v.as_str()) {
-                            println!("[RAG] Model provided final answer without tools.");
-                            return Ok(ans.to_string());
+        // Let the model call tools until it answers or the step budget runs out.
+        while tool_calls.len() < max_calls {
+            let mut prompt = format!(
+                "You are a Pokemon assistant agent. Decide whether to call one of the available tools to fetch Pokemon data, or answer directly. {}\n{}User Question: {}{}",
+                tools_description,
+                history,
+                query,
+                self.language_instruction()
+            );
+            if !observations.is_empty() {
+                prompt.push_str(&format!(
+                    "\n\nTool results so far:\n{}\nYou may call {} more tool(s). If the results above are enough, respond with the final JSON.{}",
+                    observations,
+                    max_calls - tool_calls.len(),
+                    citation::CITATION_INSTRUCTION
+                ));
+            }
+
+            let decision = self.llm.generate_content(&prompt).await?;
+            self.trace(format_args!("Model decision (raw): {}", decision));
+
+            let json = parse_json_reply(&decision);
+            match json
+                .as_ref()
+                .and_then(|j| j.get("type"))
+                .and_then(|v| v.as_str())
+            {
+                Some("action") => {
+                    let call = ToolCall::from_json(json.as_ref().unwrap_or(&Value::Null));
+                    self.trace(format_args!(
+                        "Model requested action: tool='{}' names={:?}",
+                        call.tool, call.names
+                    ));
+
+                    on_event(AnswerEvent::ToolStart {
+                        tool: call.tool.clone(),
+                        names: call.names.clone(),
+                    });
+                    let tool_output = self.run_tool(&call).await;
+                    on_event(AnswerEvent::ToolEnd {
+                        tool: call.tool.clone(),
+                        names: call.names.clone(),
+                        ok: tool_output.is_ok(),
+                    });
+                    let output = match tool_output {
+                        Ok(output) => output,
+                        // Shown to the model like any other result, so it can
+                        // fix the call; the attempt still uses up a step.
+                        Err(e) if tools::is_model_error(&e) => {
+                            self.trace(format_args!("Tool error: {}", e));
+                            observations.push_str(&format!(
+                                "[{}({})]\n{}\n",
+                                call.tool,
+                                call.arguments(),
+                                json!({ "error": e.to_string() })
+                            ));
+                            tool_calls.push(call);
+                            continue;
                         }
+                        Err(e) => return Err(e),
+                    };
+                    let ToolOutput {
+                        text, resources, ..
+                    } = &output;
+                    self.trace(format_args!("Tool output:\n{}", text));
+                    observations.push_str(&format!(
+                        "[{}({})] {}\n{}\n",
+                        call.tool,
+                        call.arguments(),
+                        sources.header(resources),
+                        text
+                    ));
+                    facts.extend(output.facts);
+                    tool_calls.push(call);
+                }
+                Some("final") => {
+                    if let Some(ans) = json
+                        .as_ref()
+                        .and_then(|j| j.get("answer"))
+                        .and_then(|v| v.as_str())
+                    {
+                        self.trace(format_args!(
+                            "Model provided final answer after {} tool call(s).",
+                            tool_calls.len()
+                        ));
+                        on_event(AnswerEvent::TextDelta {
+                            text: ans.to_string(),
+                        });
+                        return Ok(Answer {
+                            text: ans.to_string(),
+                            tool_calls,
+                            sources: sources.cited_in(ans),
+                            mismatches: Vec::new(),
+                        });
+                    }
+                    break;
+                }
+                _ => {
+                    // Once tools have run, a free-text reply is the answer itself.
+                    if !tool_calls.is_empty() {
+                        on_event(AnswerEvent::TextDelta {
+                            text: decision.clone(),
+                        });
+                        return Ok(Answer {
+                            sources: sources.cited_in(&decision),
+                            mismatches: Vec::new(),
+                            text: decision,
+                            tool_calls,
+                        });
                     }
-                    _ => {}
+                    break;
                 }
             }
         }
 
+        if !tool_calls.is_empty() {
+            // Step budget spent: ask for a final answer from what was gathered.
+            let followup = format!(
+                "{}Tool output:\n{}\n\nBased on the tool output above, provide a concise, user-facing answer to the original question: {}{}{}",
+                history,
+                observations,
+                query,
+                citation::CITATION_INSTRUCTION,
+                self.language_instruction()
+            );
+            self.trace(format_args!("Sending followup to model..."));
+
+            let final_resp = self
+                .llm
+                .generate_content_streaming(&followup, &mut text_deltas(on_event))
+                .await?;
+            self.trace(format_args!("Model final response: {}", final_resp));
+            return Ok(Answer {
+                sources: sources.cited_in(&final_resp),
+                mismatches: Vec::new(),
+                text: final_resp,
+                tool_calls,
+            });
+        }
+
         // Fallback: try to extract a Pokemon name and use the RAG pattern as before.
-        let pokemon_name = self.pokeapi.extract_pokemon_name(query).await;
+        let query_with_language = format!("{}{}{}", history, query, self.language_instruction());
+        let pokemon_name = self.data_source.extract_pokemon_name(query).await;
 
         if let Some(name) = pokemon_name {
-            let pokemon = self.pokeapi.get_pokemon(&name).await?;
-            let species = self.pokeapi.get_pokemon_species(&name).await.ok();
-
-            let context = if let Some(species) = species {
-                self.pokeapi.format_pokemon_with_species(&pokemon, &species)
-            } else {
-                self.pokeapi.format_pokemon_data(&pokemon)
-            };
+            on_event(AnswerEvent::ToolStart {
+                tool: "get_pokemon".to_string(),
+                names: vec![name.clone()],
+            });
+            let found = self.data_source.get_pokemon_with_species(&name).await;
+            on_event(AnswerEvent::ToolEnd {
+                tool: "get_pokemon".to_string(),
+                names: vec![name.clone()],
+                ok: found.is_ok(),
+            });
+            let (pokemon, species) = found?;
+            facts.push(Facts::new(&pokemon, species.as_ref()));
+            let mut resources = vec![Resource::pokemon(&pokemon)];
+            resources.extend(species.as_ref().map(Resource::species));
+            let context = format!(
+                "{}\n{}",
+                sources.header(&resources),
+                format::format_pokemon_entry(&pokemon, species.as_ref(), &self.format_options)
+            );
+            let query = format!("{}{}", query_with_language, citation::CITATION_INSTRUCTION);
 
-            self.gemini.generate_with_context(&context, query).await
+            let text = self
+                .llm
+                .generate_content_streaming(
+                    &llm::context_prompt(&context, &query),
+                    &mut text_deltas(on_event),
+                )
+                .await?;
+            Ok(Answer {
+                sources: sources.cited_in(&text),
+                mismatches: Vec::new(),
+                text,
+                tool_calls: vec![ToolCall {
+                    tool: "get_pokemon".to_string(),
+                    names: vec![name],
+                    args: Value::Null,
+                }],
+            })
         } else {
             let context = "You are a Pokemon assistant. Answer questions about Pokemon using general knowledge. If asked about a specific Pokemon, you may need the Pokemon name to provide detailed information.";
-            self.gemini.generate_with_context(context, query).await
+            let text = self
+                .llm
+                .generate_content_streaming(
+                    &llm::context_prompt(context, &query_with_language),
+                    &mut text_deltas(on_event),
+                )
+                .await?;
+            Ok(Answer {
+                text,
+                tool_calls: Vec::new(),
+                sources: Vec::new(),
+                mismatches: Vec::new(),
+            })
+        }
+    }
+
+    /// Answers a question about a set of Pokémon ("strongest non-legendary
+    /// Water types") from the local dataset: the model turns it into
+    /// `search_pokemon` filters, the search runs locally, and the model
+    /// summarises the ranked matches. `None` hands the question back to the
+    /// agent loop: there is no dataset, it isn't a set question, the model
+    /// found no filters for it, or the search failed.
+    async fn answer_set_question(
+        &self,
+        query: &str,
+        history: &str,
+        on_event: &mut (dyn FnMut(AnswerEvent) + Send),
+    ) -> Result<Option<Answer>> {
+        let Ok(dataset) = self.dataset.get().await else {
+            return Ok(None);
+        };
+        if !search::is_set_question(query, dataset) {
+            return Ok(None);
+        }
+        let Some(tool) = Tool::find("search_pokemon") else {
+            return Ok(None);
+        };
+
+        let prompt = format!(
+            "You translate questions about sets of Pokémon into filters for a local Pokédex search. The filter arguments, as JSON Schema:\n{}\n\n\
+            Reply with STRICT JSON only: {{\"type\":\"search\",\"args\":{{...}}}}, or {{\"type\":\"none\"}} if the question is about particular Pokémon or needs something these filters can't express. \
+            For rankings, sort by the stat asked about (\"strongest\" is total, \"fastest\" is speed, \"bulkiest\" is hp) and set limit to the number asked for, or 10.\n\n\
+            {}Question: {}",
+            tool.input_schema(),
+            history,
+            query
+        );
+        let reply = self.llm.generate_content(&prompt).await?;
+        self.trace(format_args!("Model search filters (raw): {}", reply));
+        let json = parse_json_reply(&reply);
+        if json
+            .as_ref()
+            .and_then(|j| j.get("type"))
+            .and_then(Value::as_str)
+            != Some("search")
+        {
+            return Ok(None);
+        }
+        let call = ToolCall {
+            tool: tool.name.to_string(),
+            names: Vec::new(),
+            args: json
+                .as_ref()
+                .and_then(|j| j.get("args"))
+                .cloned()
+                .unwrap_or(Value::Null),
+        };
+
+        on_event(AnswerEvent::ToolStart {
+            tool: call.tool.clone(),
+            names: Vec::new(),
+        });
+        let output = self.run_tool(&call).await;
+        on_event(AnswerEvent::ToolEnd {
+            tool: call.tool.clone(),
+            names: Vec::new(),
+            ok: output.is_ok(),
+        });
+        let ToolOutput {
+            text, resources, ..
+        } = match output {
+            Ok(output) => output,
+            Err(e) => {
+                self.trace(format_args!("Search failed: {}", e));
+                return Ok(None);
+            }
+        };
+        self.trace(format_args!("Tool output:\n{}", text));
+
+        let mut sources = SourceList::default();
+        let prompt = format!(
+            "{}Search results:\n[{}({})] {}\n{}\n\
+            Using the search results above, answer the question: {}\n\
+            Keep the results' order, and say how many matched when the list was cut off.{}{}",
+            history,
+            call.tool,
+            call.arguments(),
+            sources.header(&resources),
+            text,
+            query,
+            citation::CITATION_INSTRUCTION,
+            self.language_instruction()
+        );
+        let text = self
+            .llm
+            .generate_content_streaming(&prompt, &mut text_deltas(on_event))
+            .await?;
+        Ok(Some(Answer {
+            sources: sources.cited_in(&text),
+            mismatches: Vec::new(),
+            text,
+            tool_calls: vec![call],
+        }))
+    }
+
+    /// Adds the index entries closest to `query` to `observations`, unless
+    /// there is no index or the query names a species the tools can look up
+    /// directly. A failed search is only traced; the model still has its
+    /// tools.
+    async fn search_index(
+        &self,
+        query: &str,
+        observations: &mut String,
+        sources: &mut SourceList,
+        on_event: &mut (dyn FnMut(AnswerEvent) + Send),
+    ) -> Option<ToolCall> {
+        let index = self.index.as_ref()?;
+        if self.retrieval_top_k == 0 || index.mentions_species(query) {
+            return None;
         }
+
+        on_event(AnswerEvent::ToolStart {
+            tool: SEARCH_TOOL.to_string(),
+            names: Vec::new(),
+        });
+        let found = index
+            .search(self.llm.as_ref(), query, self.retrieval_top_k)
+            .await;
+        let names: Vec<String> = match &found {
+            Ok(entries) => entries.iter().map(|e| e.name.clone()).collect(),
+            Err(_) => Vec::new(),
+        };
+        on_event(AnswerEvent::ToolEnd {
+            tool: SEARCH_TOOL.to_string(),
+            names: names.clone(),
+            ok: found.is_ok(),
+        });
+        let entries = match found {
+            Ok(entries) if !entries.is_empty() => entries,
+            Ok(_) => return None,
+            Err(e) => {
+                self.trace(format_args!("Index search failed: {}", e));
+                return None;
+            }
+        };
+
+        let resources: Vec<Resource> = entries
+            .iter()
+            .map(|entry| Resource {
+                endpoint: "pokemon-species",
+                name: entry.name.clone(),
+                id: entry.id,
+            })
+            .collect();
+        let texts: Vec<&str> = entries.iter().map(|e| e.text.as_str()).collect();
+        self.trace(format_args!("Index search results:\n{}", texts.join("\n")));
+        observations.push_str(&format!(
+            "[{}({})] {}\n{}\n",
+            SEARCH_TOOL,
+            query,
+            sources.header(&resources),
+            texts.join("\n")
+        ));
+        Some(ToolCall {
+            tool: SEARCH_TOOL.to_string(),
+            names,
+            args: Value::Null,
+        })
+    }
+
+    /// Compares the answer with the Pokémon data its tools showed the
+    /// model. Search candidates, set queries and calculations only name
+    /// Pokémon, so they aren't checked. Only English answers are read.
+    fn check_answer(&self, answer: &Answer, facts: &[Facts]) -> Vec<Mismatch> {
+        if facts.is_empty() || self.format_options.language != "en" {
+            return Vec::new();
+        }
+        // The same Pokémon can come from several tools; a species lookup is
+        // the only one that knows its capture rate.
+        let mut unique: Vec<Facts> = Vec::with_capacity(facts.len());
+        for f in facts {
+            match unique.iter_mut().find(|u| u.name == f.name) {
+                Some(seen) => seen.capture_rate = seen.capture_rate.or(f.capture_rate),
+                None => unique.push(f.clone()),
+            }
+        }
+
+        let mismatches = verify::check(&answer.text, &unique);
+        for mismatch in &mismatches {
+            self.trace(format_args!("Answer contradicts the data: {}", mismatch));
+        }
+        mismatches
+    }
+
+    /// Executes the requested tool. Failures are returned as errors; the
+    /// agent loop decides which ones go back to the model.
+    ///
+    /// The tool future is boxed as a trait object: inlined into both the
+    /// agent loop and set-question answering, its rustemon-typed state
+    /// overflows the compiler's `Send` check for the server's handlers.
+    async fn run_tool(&self, call: &ToolCall) -> Result<ToolOutput> {
+        let output: BoxFuture<'_, Result<ToolOutput>> = Box::pin(tools::run_tool(
+            self.data_source.as_ref(),
+            &self.format_options,
+            &self.dataset,
+            call,
+        ));
+        output.await
+    }
+
+    /// Runs one of the agent's tools directly, without the model, and
+    /// returns its formatted output.
+    pub async fn call_tool(&self, call: &ToolCall) -> Result<String> {
+        Ok(self.run_tool(call).await?.text)
     }
 
     pub async fn process_image_query(&self, image_path: &str) -> Result<String> {
-        let decision = self.gemini.identify_pokemon_from_image(image_path).await?;
-        println!("[RAG] Image decision (raw): {}", decision);
-
-        if let Ok(json) = serde_json::from_str::<Value>(&decision) {
-            if let Some(t) = json.get("type").and_then(|v| v.as_str()) {
-                match t {
-                    "pokemon" => {
-                        let name = json
-                            .get("name")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("")
-                            .trim();
-                        let normalized_name = PokeApiClient::normalize_pokemon_name(name);
-
-                        if normalized_name.is_empty() {
-                            return Ok("I couldn't confidently identify a specific Pokémon from that image. Please try a clearer image.".to_string());
-                        }
+        let image = ImageInput::from_path(image_path)?;
+        self.process_image(&image).await
+    }
 
-                        let pokemon = match self.pokeapi.get_pokemon(&normalized_name).await {
-                            Ok(p) => p,
-                            Err(_) => {
-                                return Ok("I detected a character, but couldn't match it to a valid Pokémon entry. Please try another image.".to_string())
-                            }
-                        };
-
-                        let species = self
-                            .pokeapi
-                            .get_pokemon_species(&normalized_name)
-                            .await
-                            .ok();
-                        let specs = if let Some(species) = species {
-                            self.pokeapi.format_pokemon_with_species(&pokemon, &species)
-                        } else {
-                            self.pokeapi.format_pokemon_data(&pokemon)
-                        };
-
-                        return Ok(format!(
-                            "Identified Pokémon from image: {}
-
-{}",
-                            pokemon.name, specs
-                        ));
-                    }
-                    "not_pokemon" => {
-                        let reason = json
-                            .get("reason")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("the image does not appear to contain a Pokémon");
-                        return Ok(format!(
-                            "I can't provide Pokémon specs for this image because {}.",
-                            reason
-                        ));
+    /// Asks the model what `image` shows and checks each detection's
+    /// candidates against the data source. Only the existence of each name
+    /// is checked; no specs are formatted.
+    pub async fn identify(&self, image: &ImageInput) -> Result<Identification> {
+        let decision = self
+            .llm
+            .identify_pokemon_from_image(image, self.max_image_candidates)
+            .await?;
+        self.trace(format_args!("Image decision (raw): {}", decision));
+
+        let mut identification = Identification::from_reply(&decision);
+        identification.detections.truncate(MAX_DETECTIONS);
+        for detection in &mut identification.detections {
+            detection
+                .candidates
+                .truncate(self.max_image_candidates.max(1));
+            self.validate_candidates(detection).await?;
+        }
+        Ok(identification)
+    }
+
+    /// Drops candidates the data source doesn't know and re-picks the best
+    /// guess. Lookup failures other than "not found" are returned.
+    async fn validate_candidates(&self, detection: &mut Detection) -> Result<()> {
+        let names: Vec<&str> = detection
+            .candidates
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        let lookups = self.data_source.get_many(&names).await;
+
+        let mut valid = Vec::new();
+        for (mut candidate, lookup) in detection.candidates.drain(..).zip(lookups) {
+            match lookup {
+                Ok(pokemon) => {
+                    candidate.name = pokemon.name;
+                    valid.push(candidate);
+                }
+                Err(PokidexError::NotFound { .. }) => {
+                    self.trace(format_args!("No Pokémon named '{}'", candidate.name));
+                    detection.unmatched.push(candidate.name);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        detection.pokemon = valid.first().map(|c| c.name.clone());
+        detection.candidates = valid;
+        Ok(())
+    }
+
+    pub async fn process_image(&self, image: &ImageInput) -> Result<String> {
+        self.process_image_with(image, false).await
+    }
+
+    /// Identifies the Pokémon in `image` and returns specs for each one, or
+    /// explains why the image was rejected. Low-confidence guesses are shown
+    /// together with the other candidates. With `team_summary`, a
+    /// team summary of everything found is appended.
+    pub async fn process_image_with(
+        &self,
+        image: &ImageInput,
+        team_summary: bool,
+    ) -> Result<String> {
+        let identification = self.identify(image).await?;
+
+        match identification.verdict {
+            Verdict::Pokemon => {
+                let found: Vec<&Detection> = identification
+                    .detections
+                    .iter()
+                    .filter(|d| d.pokemon.is_some())
+                    .collect();
+                if found.is_empty() {
+                    let unmatched: Vec<&str> = identification
+                        .detections
+                        .iter()
+                        .flat_map(|d| &d.unmatched)
+                        .map(String::as_str)
+                        .collect();
+                    if unmatched.is_empty() {
+                        return Ok("I couldn't confidently identify a specific Pokémon from that image. Please try a clearer image.".to_string());
                     }
-                    _ => {}
+                    return Ok(format!(
+                        "I detected a character, but couldn't match it to a valid Pokémon entry (suggested: {}). Please try another image.",
+                        unmatched.join(", ")
+                    ));
+                }
+
+                let lookups = found.iter().map(|d| {
+                    let name = d.pokemon.as_deref().unwrap_or_default();
+                    self.data_source.get_pokemon_with_species(name)
+                });
+                let entries = futures::future::try_join_all(lookups).await?;
+
+                let mut sections = Vec::with_capacity(found.len());
+                for (i, (detection, (pokemon, species))) in found.iter().zip(&entries).enumerate() {
+                    let specs =
+                        format::format_pokemon_entry(pokemon, species.as_ref(), &self.format_options);
+                    let mut header = if found.len() == 1 {
+                        format!("Identified Pokémon from image: {}", pokemon.name)
+                    } else {
+                        match &detection.region {
+                            Some(region) => format!("[{}] {} ({})", i + 1, pokemon.name, region),
+                            None => format!("[{}] {}", i + 1, pokemon.name),
+                        }
+                    };
+                    header.push_str(&confidence_note(detection));
+                    sections.push(format!("{}\n\n{}", header, specs));
+                }
+
+                let mut output = sections.join("\n\n");
+                if found.len() > 1 {
+                    output = format!(
+                        "Identified {} Pokémon in the image.\n\n{}",
+                        found.len(),
+                        output
+                    );
+                }
+                if team_summary {
+                    let summary = TeamSummary::from_pokemon(entries.iter().map(|(p, _)| p));
+                    output.push_str(&format!("\n\n{}", summary));
                 }
+                Ok(output)
             }
+            Verdict::NotPokemon => Ok(format!(
+                "I can't provide Pokémon specs for this image because {}.",
+                identification
+                    .reason
+                    .as_deref()
+                    .unwrap_or("the image does not appear to contain a Pokémon")
+            )),
+            Verdict::Unknown => Ok("I couldn't determine whether this image contains a Pokémon. Please try a clearer Pokémon image.".to_string()),
+        }
+    }
+
+    /// Extra prompt line asking for answers in the configured language.
+    fn language_instruction(&self) -> String {
+        match self.format_options.language.as_str() {
+            "en" => String::new(),
+            lang => format!(
+                "\nAnswer in the language with PokéAPI language code '{}'.",
+                lang
+            ),
         }
+    }
+
+    fn trace(&self, message: fmt::Arguments<'_>) {
+        if self.verbose {
+            eprintln!("[RAG] {}", message);
+        }
+    }
+}
+
+/// Header suffix for a shaky guess: its confidence and the alternatives.
+fn confidence_note(detection: &Detection) -> String {
+    if !detection.is_low_confidence() {
+        return String::new();
+    }
+    let mut note = String::new();
+    if let Some(confidence) = detection.candidates[0].confidence {
+        note.push_str(&format!(" (low confidence: {})", percent(confidence)));
+    }
+    let others: Vec<String> = detection.candidates[1..]
+        .iter()
+        .map(|c| match c.confidence {
+            Some(confidence) => format!("{} ({})", c.name, percent(confidence)),
+            None => c.name.clone(),
+        })
+        .collect();
+    if !others.is_empty() {
+        note.push_str(&format!("\nOther possibilities: {}", others.join(", ")));
+    }
+    note
+}
+
+fn percent(confidence: f32) -> String {
+    format!("{:.0}%", confidence * 100.0)
+}
+
+/// Earlier turns formatted for a prompt, or nothing for a fresh conversation.
+fn history_section(history: &[Turn]) -> String {
+    if history.is_empty() {
+        return String::new();
+    }
+    let mut section = String::from("Conversation so far:\n");
+    for turn in history {
+        section.push_str(&format!(
+            "User: {}\nAssistant: {}\n",
+            turn.question, turn.answer
+        ));
+    }
+    section.push('\n');
+    section
+}
+
+/// Adapts an event callback to the text callback of
+/// [`LanguageModel::generate_content_streaming`].
+fn text_deltas<'a>(
+    on_event: &'a mut (dyn FnMut(AnswerEvent) + Send),
+) -> impl FnMut(&str) + Send + 'a {
+    move |text: &str| {
+        on_event(AnswerEvent::TextDelta {
+            text: text.to_string(),
+        })
+    }
+}
 
-        Ok("I couldn't determine whether this image contains a Pokémon. Please try a clearer Pokémon image.".to_string())
+/// Parses a JSON reply from the model, tolerating the ```json fences it
+/// sometimes wraps around strict-JSON answers.
+pub(crate) fn parse_json_reply(raw: &str) -> Option<Value> {
+    let trimmed = raw.trim();
+    let body = trimmed
+        .strip_prefix("```json")
+        .or_else(|| trimmed.strip_prefix("```"))
+        .and_then(|rest| rest.strip_suffix("```"))
+        .unwrap_or(trimmed);
+    serde_json::from_str(body.trim()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_json_reply, RAGEngine, ToolCall};
+    use crate::config::Config;
+    use crate::test_support::{EmptySource, ScriptedModel};
+    use serde_json::json;
+
+    fn engine(model: ScriptedModel) -> RAGEngine {
+        RAGEngine::builder()
+            .config(Config {
+                cache_dir: std::env::temp_dir().join("pokidex-rag-tests"),
+                max_tool_steps: 2,
+                ..Config::default()
+            })
+            .llm(model)
+            .data_source(EmptySource)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn parses_fenced_json_replies() {
+        let json =
+            parse_json_reply("```json\n{\"type\":\"final\",\"answer\":\"Fire\"}\n```").unwrap();
+        assert_eq!(json["answer"], "Fire");
+        assert!(parse_json_reply("Charizard is Fire/Flying.").is_none());
+    }
+
+    #[test]
+    fn reads_single_and_multiple_tool_names() {
+        let single =
+            ToolCall::from_json(&serde_json::json!({"tool": "get_pokemon", "name": "pikachu"}));
+        assert_eq!(single.names, vec!["pikachu"]);
+
+        let many = ToolCall::from_json(
+            &serde_json::json!({"tool": "compare_pokemon", "names": ["pikachu", "raichu"]}),
+        );
+        assert_eq!(many.names, vec!["pikachu", "raichu"]);
+    }
+
+    #[tokio::test]
+    async fn shows_tool_errors_to_the_model() {
+        // A misspelled name comes back as a result the model can answer from.
+        let model = ScriptedModel::new(|prompt| {
+            if prompt.contains(r#"{"error":"no such Pokémon: pikachoo"}"#) {
+                json!({"type": "final", "answer": "There is no Pokémon called Pikachoo."})
+            } else {
+                json!({"type": "action", "tool": "get_pokemon", "name": "pikachoo"})
+            }
+            .to_string()
+        });
+        let answer = engine(model)
+            .process_query("What type is Pikachoo?")
+            .await
+            .unwrap();
+        assert_eq!(answer.text, "There is no Pokémon called Pikachoo.");
+        assert_eq!(answer.tool_calls.len(), 1);
+
+        // Calls that keep failing use up the step budget like any other.
+        let model = ScriptedModel::new(|prompt| {
+            if prompt.contains("Based on the tool output above") {
+                "I couldn't look that up.".to_string()
+            } else {
+                json!({"type": "action", "tool": "teleport", "name": "abra"}).to_string()
+            }
+        });
+        let answer = engine(model).process_query("Where is Abra?").await.unwrap();
+        assert_eq!(answer.text, "I couldn't look that up.");
+        assert_eq!(answer.tool_calls.len(), 2);
     }
 }
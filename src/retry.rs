@@ -0,0 +1,147 @@
+use crate::error::PokidexError;
+use rand::Rng;
+use std::future::Future;
+use std::time::Duration;
+
+/// Retry settings shared by the PokéAPI and Gemini clients.
+///
+/// Each attempt is bounded by `timeout`; transient failures are retried up to
+/// `max_retries` times with exponential backoff and full jitter.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub timeout: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(250),
+            max_delay: Duration::from_secs(4),
+            timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Same policy with a different per-attempt timeout.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Runs `op` until it succeeds, fails with a non-transient error, or the
+    /// retry budget is spent. The last error is returned in the latter cases.
+    pub async fn run<T, F, Fut>(&self, mut op: F) -> Result<T, PokidexError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, PokidexError>>,
+    {
+        let mut attempt = 0;
+        loop {
+            let result = match tokio::time::timeout(self.timeout, op()).await {
+                Ok(result) => result,
+                Err(_) => Err(PokidexError::Timeout(self.timeout)),
+            };
+
+            match result {
+                Ok(value) => return Ok(value),
+                Err(e) if e.is_transient() && attempt < self.max_retries => {
+                    tokio::time::sleep(self.backoff(attempt)).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Delay before retry number `attempt` (zero-based): a random duration
+    /// between zero and `base_delay * 2^attempt`, capped at `max_delay`.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let ceiling = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        let millis = ceiling.as_millis() as u64;
+        if millis == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_millis(rand::rng().random_range(0..=millis))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RetryPolicy;
+    use crate::error::PokidexError;
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    fn fast_policy() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(2),
+            timeout: Duration::from_millis(50),
+        }
+    }
+
+    #[test]
+    fn backoff_stays_under_cap() {
+        let policy = RetryPolicy::default();
+        for attempt in 0..10 {
+            assert!(policy.backoff(attempt) <= policy.max_delay);
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_transient_errors_until_budget_is_spent() {
+        let calls = Cell::new(0);
+        let result: Result<(), _> = fast_policy()
+            .run(|| {
+                calls.set(calls.get() + 1);
+                async { Err(PokidexError::Network("connection reset".into())) }
+            })
+            .await;
+
+        assert!(matches!(result, Err(PokidexError::Network(_))));
+        assert_eq!(calls.get(), 4);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_not_found() {
+        let calls = Cell::new(0);
+        let result: Result<(), _> = fast_policy()
+            .run(|| {
+                calls.set(calls.get() + 1);
+                async {
+                    Err(PokidexError::NotFound {
+                        kind: "Pokémon",
+                        name: "missingno".into(),
+                    })
+                }
+            })
+            .await;
+
+        assert!(matches!(result, Err(PokidexError::NotFound { .. })));
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[tokio::test]
+    async fn times_out_slow_attempts() {
+        let result: Result<(), _> = RetryPolicy {
+            max_retries: 0,
+            ..fast_policy()
+        }
+        .run(|| async {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            Ok(())
+        })
+        .await;
+
+        assert!(matches!(result, Err(PokidexError::Timeout(_))));
+    }
+}
@@ -0,0 +1,782 @@
+//! Set queries over the whole Pokédex, e.g. "Fire/Flying types from
+//! Generation I with Speed over 100". They run over a compact copy of every
+//! species, built once from the data source and kept in the cache
+//! directory, instead of looking each Pokémon up again.
+
+use crate::citation::Resource;
+use crate::error::{PokidexError, Result};
+use crate::source::{PokemonDataSource, MAX_CONCURRENT_REQUESTS};
+use crate::types::TYPES;
+use futures::stream::{self, StreamExt};
+use rustemon::model::pokemon::{Pokemon, PokemonSpecies};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tokio::sync::OnceCell;
+
+const DATASET_FILE: &str = "pokedex.json";
+
+/// Words asking for several Pokémon at once.
+const SET_WORDS: &[&str] = &[
+    "all",
+    "every",
+    "list",
+    "which",
+    "many",
+    "top",
+    "strongest",
+    "weakest",
+    "fastest",
+    "slowest",
+    "bulkiest",
+    "best",
+    "highest",
+    "lowest",
+    "most",
+    "least",
+];
+
+/// Set words that make a set-style noun phrase with a group word after
+/// them, e.g. "all ... Pokémon", "list every legendary".
+const QUANTIFIERS: &[&str] = &["all", "every", "list", "many", "top"];
+
+/// Words naming a group of Pokémon.
+const GROUP_WORDS: &[&str] = &[
+    "pokemon",
+    "pokémon",
+    "mons",
+    "types",
+    "legendary",
+    "legendaries",
+    "mythical",
+    "mythicals",
+];
+
+/// A base stat, or their total.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Stat {
+    Hp,
+    Attack,
+    Defense,
+    #[serde(alias = "special_attack")]
+    SpecialAttack,
+    #[serde(alias = "special_defense")]
+    SpecialDefense,
+    Speed,
+    Total,
+}
+
+impl Stat {
+    pub const ALL: [Stat; 7] = [
+        Stat::Hp,
+        Stat::Attack,
+        Stat::Defense,
+        Stat::SpecialAttack,
+        Stat::SpecialDefense,
+        Stat::Speed,
+        Stat::Total,
+    ];
+
+    /// Column label, e.g. `SpA`.
+    pub fn label(self) -> &'static str {
+        match self {
+            Stat::Hp => "HP",
+            Stat::Attack => "Atk",
+            Stat::Defense => "Def",
+            Stat::SpecialAttack => "SpA",
+            Stat::SpecialDefense => "SpD",
+            Stat::Speed => "Spe",
+            Stat::Total => "Total",
+        }
+    }
+
+    /// PokéAPI's name, e.g. `special-attack`; `total` for the total.
+    pub fn name(self) -> &'static str {
+        match self {
+            Stat::Hp => "hp",
+            Stat::Attack => "attack",
+            Stat::Defense => "defense",
+            Stat::SpecialAttack => "special-attack",
+            Stat::SpecialDefense => "special-defense",
+            Stat::Speed => "speed",
+            Stat::Total => "total",
+        }
+    }
+}
+
+impl FromStr for Stat {
+    type Err = PokidexError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().replace(['_', ' '], "-").as_str() {
+            "hp" => Ok(Stat::Hp),
+            "attack" | "atk" => Ok(Stat::Attack),
+            "defense" | "defence" | "def" => Ok(Stat::Defense),
+            "special-attack" | "sp-atk" | "spa" => Ok(Stat::SpecialAttack),
+            "special-defense" | "special-defence" | "sp-def" | "spd" => Ok(Stat::SpecialDefense),
+            "speed" | "spe" => Ok(Stat::Speed),
+            "total" | "bst" => Ok(Stat::Total),
+            other => Err(PokidexError::InvalidRequest(format!(
+                "unknown stat '{}' (expected hp, attack, defense, special-attack, special-defense, speed or total)",
+                other
+            ))),
+        }
+    }
+}
+
+/// Inclusive bounds on a stat; a missing bound is open.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Range {
+    pub min: Option<u32>,
+    pub max: Option<u32>,
+}
+
+impl Range {
+    fn contains(self, value: u32) -> bool {
+        self.min.is_none_or(|min| value >= min) && self.max.is_none_or(|max| value <= max)
+    }
+
+    /// e.g. `speed ≥ 101` or `hp 50–80`.
+    fn describe(self, stat: Stat) -> String {
+        match (self.min, self.max) {
+            (Some(min), Some(max)) if min == max => format!("{} = {}", stat.name(), min),
+            (Some(min), Some(max)) => format!("{} {}–{}", stat.name(), min, max),
+            (Some(min), None) => format!("{} ≥ {}", stat.name(), min),
+            (None, Some(max)) => format!("{} ≤ {}", stat.name(), max),
+            (None, None) => format!("any {}", stat.name()),
+        }
+    }
+}
+
+/// A stat bound as written on the command line: `speed>100`, `hp<=60`,
+/// `attack=80..120` or `total=600`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatFilter {
+    pub stat: Stat,
+    pub range: Range,
+}
+
+impl FromStr for StatFilter {
+    type Err = PokidexError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || {
+            PokidexError::InvalidRequest(format!(
+                "invalid stat filter '{}' (expected e.g. speed>100, hp<=60 or attack=80..120)",
+                s
+            ))
+        };
+        let at = s.find(['<', '>', '=']).ok_or_else(invalid)?;
+        let stat: Stat = s[..at].parse()?;
+        let rest = &s[at..];
+        let (op, value) = ["<=", ">=", "<", ">", "="]
+            .iter()
+            .find_map(|op| rest.strip_prefix(op).map(|value| (*op, value.trim())))
+            .ok_or_else(invalid)?;
+        let number = |text: &str| text.trim().parse::<u32>().map_err(|_| invalid());
+
+        let range = match op {
+            "=" => match value.split_once("..") {
+                Some((min, max)) => Range {
+                    min: Some(number(min)?),
+                    max: Some(number(max)?),
+                },
+                None => Range {
+                    min: Some(number(value)?),
+                    max: Some(number(value)?),
+                },
+            },
+            ">" => Range {
+                min: Some(number(value)?.checked_add(1).ok_or_else(invalid)?),
+                max: None,
+            },
+            ">=" => Range {
+                min: Some(number(value)?),
+                max: None,
+            },
+            "<" => Range {
+                min: None,
+                max: Some(number(value)?.checked_sub(1).ok_or_else(invalid)?),
+            },
+            _ => Range {
+                min: None,
+                max: Some(number(value)?),
+            },
+        };
+        Ok(Self { stat, range })
+    }
+}
+
+/// Base stats of a species' default form.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BaseStats {
+    pub hp: u32,
+    pub attack: u32,
+    pub defense: u32,
+    pub special_attack: u32,
+    pub special_defense: u32,
+    pub speed: u32,
+}
+
+impl BaseStats {
+    /// The base stats PokéAPI lists for `pokemon`.
+    pub fn of(pokemon: &Pokemon) -> Self {
+        let mut stats = Self::default();
+        for stat in &pokemon.stats {
+            let value = u32::try_from(stat.base_stat).unwrap_or(0);
+            match stat.stat.name.parse() {
+                Ok(Stat::Hp) => stats.hp = value,
+                Ok(Stat::Attack) => stats.attack = value,
+                Ok(Stat::Defense) => stats.defense = value,
+                Ok(Stat::SpecialAttack) => stats.special_attack = value,
+                Ok(Stat::SpecialDefense) => stats.special_defense = value,
+                Ok(Stat::Speed) => stats.speed = value,
+                _ => {}
+            }
+        }
+        stats
+    }
+
+    pub fn get(&self, stat: Stat) -> u32 {
+        match stat {
+            Stat::Hp => self.hp,
+            Stat::Attack => self.attack,
+            Stat::Defense => self.defense,
+            Stat::SpecialAttack => self.special_attack,
+            Stat::SpecialDefense => self.special_defense,
+            Stat::Speed => self.speed,
+            Stat::Total => {
+                self.hp
+                    + self.attack
+                    + self.defense
+                    + self.special_attack
+                    + self.special_defense
+                    + self.speed
+            }
+        }
+    }
+}
+
+/// What set queries know about one species.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DexEntry {
+    /// Species name, e.g. `charizard`.
+    pub name: String,
+    /// National Dex number.
+    pub id: i64,
+    pub types: Vec<String>,
+    /// Including the hidden ability.
+    pub abilities: Vec<String>,
+    pub stats: BaseStats,
+    pub generation: u8,
+    pub legendary: bool,
+    pub mythical: bool,
+    pub egg_groups: Vec<String>,
+    pub color: String,
+    pub habitat: Option<String>,
+}
+
+impl DexEntry {
+    /// The entry for `species`, with the types, abilities and stats of
+    /// `pokemon`, its default form.
+    pub fn new(species: &PokemonSpecies, pokemon: &Pokemon) -> Self {
+        Self {
+            name: species.name.clone(),
+            id: species.id,
+            types: pokemon.types.iter().map(|t| t.type_.name.clone()).collect(),
+            abilities: pokemon
+                .abilities
+                .iter()
+                .map(|a| a.ability.name.clone())
+                .collect(),
+            stats: BaseStats::of(pokemon),
+            generation: generation_number(&species.generation.name),
+            legendary: species.is_legendary,
+            mythical: species.is_mythical,
+            egg_groups: species.egg_groups.iter().map(|g| g.name.clone()).collect(),
+            color: species.color.name.clone(),
+            habitat: species.habitat.as_ref().map(|h| h.name.clone()),
+        }
+    }
+
+    pub(crate) fn resource(&self) -> Resource {
+        Resource {
+            endpoint: "pokemon-species",
+            name: self.name.clone(),
+            id: self.id,
+        }
+    }
+}
+
+/// `generation-iv` → 4.
+fn generation_number(name: &str) -> u8 {
+    let numeral = name.strip_prefix("generation-").unwrap_or(name);
+    let value = |c: char| match c {
+        'i' => 1,
+        'v' => 5,
+        'x' => 10,
+        _ => 0,
+    };
+    let digits: Vec<i32> = numeral.chars().map(value).collect();
+    let mut total = 0;
+    for (i, digit) in digits.iter().enumerate() {
+        if digits.get(i + 1).is_some_and(|next| next > digit) {
+            total -= digit;
+        } else {
+            total += digit;
+        }
+    }
+    u8::try_from(total).unwrap_or(0)
+}
+
+/// Filters, ordering and a cap for a set query. Filters left unset match
+/// everything; the rest must all hold.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchQuery {
+    /// Types a match must all have, e.g. `["fire", "flying"]`.
+    #[serde(alias = "type")]
+    pub types: Vec<String>,
+    pub generation: Option<u8>,
+    pub ability: Option<String>,
+    pub stats: BTreeMap<Stat, Range>,
+    pub legendary: Option<bool>,
+    pub mythical: Option<bool>,
+    pub egg_group: Option<String>,
+    pub color: Option<String>,
+    pub habitat: Option<String>,
+    /// Orders matches by this stat, highest first; National Dex order
+    /// otherwise.
+    pub sort: Option<Stat>,
+    /// Lowest first instead.
+    pub ascending: bool,
+    pub limit: Option<usize>,
+}
+
+impl SearchQuery {
+    /// The query with names in PokéAPI's spelling (`Flash Fire` →
+    /// `flash-fire`), failing on types that don't exist.
+    fn normalized(&self) -> Result<Self> {
+        let types = self
+            .types
+            .iter()
+            .map(|t| {
+                let name = api_name(t);
+                if TYPES.contains(&name.as_str()) {
+                    Ok(name)
+                } else {
+                    Err(PokidexError::InvalidRequest(format!(
+                        "unknown type '{}'",
+                        t
+                    )))
+                }
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            types,
+            ability: self.ability.as_deref().map(api_name),
+            egg_group: self.egg_group.as_deref().map(api_name),
+            color: self.color.as_deref().map(api_name),
+            habitat: self.habitat.as_deref().map(api_name),
+            ..self.clone()
+        })
+    }
+
+    fn matches(&self, entry: &DexEntry) -> bool {
+        self.types.iter().all(|t| entry.types.contains(t))
+            && self.generation.is_none_or(|g| entry.generation == g)
+            && self
+                .ability
+                .as_ref()
+                .is_none_or(|a| entry.abilities.contains(a))
+            && self
+                .stats
+                .iter()
+                .all(|(stat, range)| range.contains(entry.stats.get(*stat)))
+            && self.legendary.is_none_or(|l| entry.legendary == l)
+            && self.mythical.is_none_or(|m| entry.mythical == m)
+            && self
+                .egg_group
+                .as_ref()
+                .is_none_or(|g| entry.egg_groups.contains(g))
+            && self.color.as_ref().is_none_or(|c| entry.color == *c)
+            && self
+                .habitat
+                .as_ref()
+                .is_none_or(|h| entry.habitat.as_ref() == Some(h))
+    }
+
+    /// The filters in words, e.g. `fire/flying type, generation 1, speed ≥ 101`.
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.types.is_empty() {
+            parts.push(format!("{} type", self.types.join("/")));
+        }
+        if let Some(generation) = self.generation {
+            parts.push(format!("generation {}", generation));
+        }
+        if let Some(ability) = &self.ability {
+            parts.push(format!("ability {}", ability));
+        }
+        for (stat, range) in &self.stats {
+            parts.push(range.describe(*stat));
+        }
+        match self.legendary {
+            Some(true) => parts.push("legendary".to_string()),
+            Some(false) => parts.push("not legendary".to_string()),
+            None => {}
+        }
+        match self.mythical {
+            Some(true) => parts.push("mythical".to_string()),
+            Some(false) => parts.push("not mythical".to_string()),
+            None => {}
+        }
+        if let Some(egg_group) = &self.egg_group {
+            parts.push(format!("egg group {}", egg_group));
+        }
+        if let Some(color) = &self.color {
+            parts.push(format!("color {}", color));
+        }
+        if let Some(habitat) = &self.habitat {
+            parts.push(format!("habitat {}", habitat));
+        }
+        parts.join(", ")
+    }
+}
+
+/// Whether `question` reads like it asks about a set of Pokémon: it has a
+/// set-style noun phrase ("all Fire/Flying Pokémon"), or ranks a group
+/// ("strongest non-legendary Water types") without naming any species in
+/// `dataset`. "Which types is Charizard weak to?" is about Charizard. Only
+/// a cheap first pass: the model still decides whether a [`SearchQuery`]
+/// can answer it.
+pub(crate) fn is_set_question(question: &str, dataset: &Dataset) -> bool {
+    let question = question.to_lowercase();
+    let words: Vec<&str> = question
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+    let quantified_group = words.iter().enumerate().any(|(i, w)| {
+        GROUP_WORDS.contains(w) && words[..i].iter().any(|q| QUANTIFIERS.contains(q))
+    });
+    if quantified_group {
+        return true;
+    }
+    words.iter().any(|w| SET_WORDS.contains(w))
+        && words.iter().any(|w| GROUP_WORDS.contains(w))
+        && !dataset.names_species(&words)
+}
+
+/// `Flash Fire` → `flash-fire`.
+fn api_name(name: &str) -> String {
+    name.trim().to_lowercase().replace([' ', '_'], "-")
+}
+
+/// The entries a query matched, in order, and how many matched before the
+/// limit cut them off.
+#[derive(Debug, Clone)]
+pub struct SearchResults<'a> {
+    pub query: SearchQuery,
+    pub entries: Vec<&'a DexEntry>,
+    pub total: usize,
+}
+
+impl SearchResults<'_> {
+    /// A heading and a table with one row per match.
+    pub fn format(&self) -> String {
+        let mut out = String::new();
+        let filters = self.query.describe();
+        let order = match self.query.sort {
+            Some(stat) if self.query.ascending => format!(", lowest {} first", stat.name()),
+            Some(stat) => format!(", highest {} first", stat.name()),
+            None => String::new(),
+        };
+        match (self.total, filters.is_empty()) {
+            (0, _) => {
+                writeln!(out, "No Pokémon match {}.", filters).ok();
+                return out;
+            }
+            (total, true) => writeln!(out, "All {} Pokémon{}:", total, order).ok(),
+            (1, false) => writeln!(out, "1 Pokémon matches {}:", filters).ok(),
+            (total, false) => writeln!(out, "{} Pokémon match {}{}:", total, filters, order).ok(),
+        };
+
+        let types: Vec<String> = self.entries.iter().map(|e| e.types.join("/")).collect();
+        let name_width = self
+            .entries
+            .iter()
+            .map(|e| e.name.len())
+            .chain([4])
+            .max()
+            .unwrap_or(4);
+        let type_width = types.iter().map(String::len).chain([5]).max().unwrap_or(5);
+        let mut header = format!(
+            "{:>5}  {:<name_width$}  {:<type_width$}",
+            "#", "Name", "Types"
+        );
+        for stat in Stat::ALL {
+            write!(header, " {:>5}", stat.label()).ok();
+        }
+        writeln!(out, "{}", header.trim_end()).ok();
+        for (entry, types) in self.entries.iter().zip(&types) {
+            write!(
+                out,
+                "{:>5}  {:<name_width$}  {:<type_width$}",
+                entry.id, entry.name, types
+            )
+            .ok();
+            for stat in Stat::ALL {
+                write!(out, " {:>5}", entry.stats.get(stat)).ok();
+            }
+            out.push('\n');
+        }
+        if self.total > self.entries.len() {
+            writeln!(out, "... and {} more", self.total - self.entries.len()).ok();
+        }
+        out
+    }
+}
+
+/// Every species, saved as JSON in the cache directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dataset {
+    pub entries: Vec<DexEntry>,
+}
+
+impl Dataset {
+    /// Where the dataset lives under `cache_dir`.
+    pub fn path(cache_dir: &Path) -> PathBuf {
+        cache_dir.join(DATASET_FILE)
+    }
+
+    /// Reads the dataset at `path`, or `None` if none has been built.
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        serde_json::from_slice(&bytes).map(Some).map_err(|e| {
+            PokidexError::ParseFailure(format!(
+                "{}: {} (rebuild it with `pokidex search --refresh`)",
+                path.display(),
+                e
+            ))
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json =
+            serde_json::to_vec(self).map_err(|e| PokidexError::ParseFailure(e.to_string()))?;
+        // Written aside and renamed so an interrupted save keeps the old file.
+        let partial = path.with_extension("part");
+        fs::write(&partial, json)?;
+        fs::rename(&partial, path)?;
+        Ok(())
+    }
+
+    /// Reads every species `source` lists, skipping any it can't find.
+    /// `on_progress` hears how many species have been read out of how many.
+    pub async fn build(
+        source: &dyn PokemonDataSource,
+        on_progress: &mut (dyn FnMut(usize, usize) + Send),
+    ) -> Result<Self> {
+        let names = source.pokemon_names().await?;
+        if names.is_empty() {
+            return Err(PokidexError::InvalidRequest(
+                "the data source can't list its species".to_string(),
+            ));
+        }
+
+        // Collected up front for the same reason as in `get_many`.
+        let reads: Vec<_> = names
+            .iter()
+            .map(|name| source.get_species_with_default(name))
+            .collect();
+        let mut reads = stream::iter(reads).buffered(MAX_CONCURRENT_REQUESTS);
+        let mut entries = Vec::with_capacity(names.len());
+        let mut done = 0;
+        while let Some(read) = reads.next().await {
+            match read {
+                Ok((species, pokemon)) => entries.push(DexEntry::new(&species, &pokemon)),
+                Err(PokidexError::NotFound { .. }) => {}
+                Err(e) => return Err(e),
+            }
+            done += 1;
+            on_progress(done, names.len());
+        }
+        Ok(Self { entries })
+    }
+
+    /// Whether `words` include a species name, e.g. `charizard`, or `mr` and
+    /// `mime` in a row.
+    fn names_species(&self, words: &[&str]) -> bool {
+        let pairs = words.windows(2).map(|pair| pair.join("-"));
+        let candidates: HashSet<String> =
+            words.iter().map(|w| w.to_string()).chain(pairs).collect();
+        self.entries.iter().any(|e| candidates.contains(&e.name))
+    }
+
+    /// Runs `query` over every entry.
+    pub fn search(&self, query: &SearchQuery) -> Result<SearchResults<'_>> {
+        let query = query.normalized()?;
+        let mut entries: Vec<&DexEntry> =
+            self.entries.iter().filter(|e| query.matches(e)).collect();
+        if let Some(stat) = query.sort {
+            // Stable, so ties stay in Dex order.
+            if query.ascending {
+                entries.sort_by_key(|e| e.stats.get(stat));
+            } else {
+                entries.sort_by_key(|e| Reverse(e.stats.get(stat)));
+            }
+        }
+        let total = entries.len();
+        if let Some(limit) = query.limit {
+            entries.truncate(limit);
+        }
+        Ok(SearchResults {
+            query,
+            entries,
+            total,
+        })
+    }
+}
+
+/// The dataset in a cache directory, read on first use. Building it reads
+/// every species, so that is left to the CLI's `search` command.
+#[derive(Debug)]
+pub struct LazyDataset {
+    path: PathBuf,
+    dataset: OnceCell<Dataset>,
+}
+
+impl LazyDataset {
+    pub fn new(cache_dir: &Path) -> Self {
+        Self {
+            path: Dataset::path(cache_dir),
+            dataset: OnceCell::new(),
+        }
+    }
+
+    /// The saved dataset, or [`PokidexError::Config`] if none has been built.
+    pub async fn get(&self) -> Result<&Dataset> {
+        self.dataset
+            .get_or_try_init(|| async {
+                Dataset::load(&self.path)?.ok_or_else(|| {
+                    PokidexError::Config(format!(
+                        "no Pokédex dataset at {}; build it with `pokidex search --refresh`",
+                        self.path.display()
+                    ))
+                })
+            })
+            .await
+    }
+}
+
+impl From<Dataset> for LazyDataset {
+    /// An already loaded dataset, never read from or saved to disk.
+    fn from(dataset: Dataset) -> Self {
+        Self {
+            path: PathBuf::new(),
+            dataset: OnceCell::new_with(Some(dataset)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_set_question, Dataset, LazyDataset, Range, Stat, StatFilter};
+    use crate::error::PokidexError;
+    use crate::test_support::entry;
+
+    #[test]
+    fn recognises_set_questions() {
+        let dataset = Dataset {
+            entries: vec![
+                entry("charizard", 6, &["fire", "flying"], 100, false),
+                entry("mr-mime", 122, &["psychic", "fairy"], 90, false),
+                entry("mewtwo", 150, &["psychic"], 130, true),
+            ],
+        };
+        let set = |question| is_set_question(question, &dataset);
+        assert!(set("Strongest non-legendary Water types?"));
+        assert!(set(
+            "All Fire/Flying Pokémon with speed over 100 from Gen 1"
+        ));
+        assert!(set("Which Pokémon learn Surf?"));
+        assert!(set("How many legendaries are faster than Mewtwo?"));
+
+        assert!(!set("What type is Charizard?"));
+        assert!(!set("Is Mewtwo legendary?"));
+        assert!(!set("Which types is Charizard weak to?"));
+        assert!(!set("Which Pokémon does Mr. Mime evolve from?"));
+        assert!(!set(
+            "What are the best moves for Charizard against Water types?"
+        ));
+    }
+
+    #[test]
+    fn filters_and_sorts_by_stat() {
+        assert_eq!(
+            "speed>100".parse::<StatFilter>().unwrap(),
+            StatFilter {
+                stat: Stat::Speed,
+                range: Range {
+                    min: Some(101),
+                    max: None
+                }
+            }
+        );
+        assert!("sp-atk=80..120".parse::<StatFilter>().is_ok());
+        assert!("speed~100".parse::<StatFilter>().is_err());
+        assert!("speed>4294967295".parse::<StatFilter>().is_err());
+
+        let dataset = Dataset {
+            entries: vec![
+                entry("charizard", 6, &["fire", "flying"], 100, false),
+                entry("moltres", 146, &["fire", "flying"], 90, true),
+                entry("zapdos", 145, &["electric", "flying"], 100, true),
+                entry("aerodactyl", 142, &["rock", "flying"], 130, false),
+            ],
+        };
+        let query = serde_json::from_value(serde_json::json!({
+            "types": ["Flying"],
+            "stats": { "speed": { "min": 95 } },
+            "sort": "speed",
+            "limit": 2
+        }))
+        .unwrap();
+        let results = dataset.search(&query).unwrap();
+        let names: Vec<&str> = results.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["aerodactyl", "charizard"]);
+        assert_eq!(results.total, 3);
+
+        let query = serde_json::from_value(serde_json::json!({
+            "type": ["fire"],
+            "legendary": false
+        }))
+        .unwrap();
+        let results = dataset.search(&query).unwrap();
+        assert_eq!(results.entries.len(), 1);
+        assert!(results
+            .format()
+            .starts_with("1 Pokémon matches fire type, not legendary:"));
+    }
+
+    #[tokio::test]
+    async fn missing_dataset_is_not_built_on_demand() {
+        let lazy = LazyDataset::new(&std::env::temp_dir().join("pokidex-search-tests"));
+        let err = lazy.get().await.unwrap_err();
+        assert!(matches!(err, PokidexError::Config(_)));
+        assert!(err.to_string().contains("pokidex search --refresh"));
+    }
+}
@@ -0,0 +1,583 @@
+//! HTTP API over [`RAGEngine`], used by `pokidex serve`.
+//!
+//! Every response carries an `x-request-id` header (taken from the request
+//! when the client sends one) and echoes it as `request_id` in the JSON body.
+//!
+//! `GET /chat` upgrades to a WebSocket holding one [`ChatSession`]. Clients
+//! send `{"type":"ask","question":...}` (or a question as plain, non-JSON
+//! text) and `{"type":"reset"}`; the server answers with the [`AnswerEvent`]s
+//! for each question followed by a `done` summary or an `error`. JSON that
+//! isn't one of these messages gets an `error`.
+
+use crate::citation::Source;
+use crate::error::{ErrorSummary, PokidexError, Result};
+use crate::format;
+use crate::image_input::ImageInput;
+use crate::pokeapi::PokeApiClient;
+use crate::rag::{AnswerEvent, RAGEngine, ToolCall};
+use crate::session::ChatSession;
+use crate::verify::Mismatch;
+use axum::extract::rejection::JsonRejection;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{DefaultBodyLimit, Multipart, Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::mpsc;
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+
+/// Largest accepted request body; phone photos comfortably fit.
+const MAX_BODY_BYTES: usize = 20 * 1024 * 1024;
+
+const REQUEST_ID_HEADER: &str = "x-request-id";
+
+type SharedEngine = Arc<RAGEngine>;
+
+#[derive(Deserialize)]
+struct AskRequest {
+    question: String,
+}
+
+#[derive(Serialize)]
+struct AskResponse {
+    request_id: String,
+    answer: String,
+    tool_calls: Vec<ToolCall>,
+    sources: Vec<Source>,
+    mismatches: Vec<Mismatch>,
+}
+
+#[derive(Serialize)]
+struct IdentifyResponse {
+    request_id: String,
+    result: String,
+}
+
+#[derive(Serialize)]
+struct PokemonResponse {
+    request_id: String,
+    name: String,
+    data: String,
+}
+
+/// A [`PokidexError`] rendered as a JSON error body.
+struct ApiError {
+    request_id: String,
+    error: PokidexError,
+}
+
+impl ApiError {
+    fn status(&self) -> StatusCode {
+        match &self.error {
+            PokidexError::NotFound { .. } => StatusCode::NOT_FOUND,
+            PokidexError::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+            PokidexError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            PokidexError::Network(_)
+            | PokidexError::Upstream { .. }
+            | PokidexError::ParseFailure(_) => StatusCode::BAD_GATEWAY,
+            PokidexError::InvalidRequest(_) | PokidexError::InvalidImage(_) => {
+                StatusCode::BAD_REQUEST
+            }
+            PokidexError::LlmRefused(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            PokidexError::MissingApiKey
+            | PokidexError::FilePicker(_)
+            | PokidexError::Io(_)
+            | PokidexError::Config(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = json!({
+            "request_id": self.request_id,
+            "error": ErrorSummary::from(&self.error),
+        });
+        (self.status(), Json(body)).into_response()
+    }
+}
+
+/// Routes for the API. The engine is shared between requests.
+pub(crate) fn router(engine: SharedEngine) -> Router {
+    Router::new()
+        .route("/ask", post(ask))
+        .route("/identify", post(identify))
+        .route("/pokemon/{name}", get(pokemon))
+        .route("/chat", get(chat))
+        .layer(DefaultBodyLimit::max(MAX_BODY_BYTES))
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+        .with_state(engine)
+}
+
+/// Serves the API on `addr` until `shutdown` resolves, then stops accepting
+/// connections and waits for in-flight requests to finish.
+pub async fn serve(
+    engine: RAGEngine,
+    addr: SocketAddr,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, router(Arc::new(engine)))
+        .with_graceful_shutdown(shutdown)
+        .await?;
+    Ok(())
+}
+
+/// The ID set by [`SetRequestIdLayer`], which runs before every handler.
+fn request_id(headers: &HeaderMap) -> String {
+    headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string()
+}
+
+async fn ask(
+    State(engine): State<SharedEngine>,
+    headers: HeaderMap,
+    request: std::result::Result<Json<AskRequest>, JsonRejection>,
+) -> std::result::Result<Json<AskResponse>, ApiError> {
+    let request_id = request_id(&headers);
+    let result = match request {
+        Ok(Json(request)) if !request.question.trim().is_empty() => {
+            engine.process_query(request.question.trim()).await
+        }
+        Ok(_) => Err(PokidexError::InvalidRequest(
+            "question is empty".to_string(),
+        )),
+        Err(rejection) => Err(PokidexError::InvalidRequest(rejection.body_text())),
+    };
+
+    match result {
+        Ok(answer) => Ok(Json(AskResponse {
+            request_id,
+            answer: answer.text,
+            tool_calls: answer.tool_calls,
+            sources: answer.sources,
+            mismatches: answer.mismatches,
+        })),
+        Err(error) => Err(ApiError { request_id, error }),
+    }
+}
+
+async fn identify(
+    State(engine): State<SharedEngine>,
+    headers: HeaderMap,
+    multipart: Multipart,
+) -> std::result::Result<Json<IdentifyResponse>, ApiError> {
+    let request_id = request_id(&headers);
+    let result = async {
+        let image = read_image_field(multipart).await?;
+        engine.process_image(&image).await
+    }
+    .await;
+
+    match result {
+        Ok(result) => Ok(Json(IdentifyResponse { request_id, result })),
+        Err(error) => Err(ApiError { request_id, error }),
+    }
+}
+
+/// Pulls the `image` field out of a multipart upload. The declared content
+/// type is ignored: [`ImageInput::from_bytes`] sniffs the format, off the
+/// async workers since it may decode and resize.
+async fn read_image_field(mut multipart: Multipart) -> Result<ImageInput> {
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| PokidexError::InvalidRequest(e.body_text()))?
+    {
+        if field.name() != Some("image") {
+            continue;
+        }
+        let bytes = field
+            .bytes()
+            .await
+            .map_err(|e| PokidexError::InvalidRequest(e.body_text()))?;
+        return tokio::task::spawn_blocking(move || ImageInput::from_bytes(bytes.to_vec()))
+            .await
+            .map_err(|e| PokidexError::InvalidImage(e.to_string()))?;
+    }
+    Err(PokidexError::InvalidRequest(
+        "missing multipart field 'image'".to_string(),
+    ))
+}
+
+async fn pokemon(
+    State(engine): State<SharedEngine>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+) -> std::result::Result<Json<PokemonResponse>, ApiError> {
+    let request_id = request_id(&headers);
+    let name = PokeApiClient::normalize_pokemon_name(&name);
+    match engine.data_source().get_pokemon_with_species(&name).await {
+        Ok((pokemon, species)) => Ok(Json(PokemonResponse {
+            request_id,
+            name: pokemon.name.clone(),
+            data: format::format_pokemon_entry(&pokemon, species.as_ref(), engine.format_options()),
+        })),
+        Err(error) => Err(ApiError { request_id, error }),
+    }
+}
+
+/// Client messages on the chat socket.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ChatRequest {
+    Ask { question: String },
+    Reset,
+}
+
+/// Server messages on the chat socket, besides the forwarded [`AnswerEvent`]s.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ChatReply {
+    /// Summary sent after the last event of an answered question.
+    Done {
+        request_id: String,
+        answer: String,
+        tool_calls: Vec<ToolCall>,
+        sources: Vec<Source>,
+        mismatches: Vec<Mismatch>,
+        latency_ms: u128,
+        turns: usize,
+    },
+    Error {
+        request_id: String,
+        error: ErrorSummary,
+    },
+    /// Acknowledges a reset; the next question starts a new conversation.
+    Reset { request_id: String },
+}
+
+async fn chat(
+    State(engine): State<SharedEngine>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let request_id = request_id(&headers);
+    ws.on_upgrade(move |socket| run_chat(engine, socket, request_id))
+}
+
+/// Serves one chat connection. Questions are answered one at a time; the
+/// session lives as long as the socket.
+async fn run_chat(engine: SharedEngine, mut socket: WebSocket, request_id: String) {
+    let mut session = ChatSession::new();
+
+    while let Some(Ok(message)) = socket.recv().await {
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        let reply = match parse_chat_request(&text) {
+            Err(error) => ChatReply::Error {
+                request_id: request_id.clone(),
+                error: ErrorSummary::from(&error),
+            },
+            Ok(ChatRequest::Reset) => {
+                session.clear();
+                ChatReply::Reset {
+                    request_id: request_id.clone(),
+                }
+            }
+            Ok(ChatRequest::Ask { question }) if question.trim().is_empty() => ChatReply::Error {
+                request_id: request_id.clone(),
+                error: ErrorSummary::from(&PokidexError::InvalidRequest(
+                    "question is empty".to_string(),
+                )),
+            },
+            Ok(ChatRequest::Ask { question }) => {
+                let started = Instant::now();
+                let (events, mut pending) = mpsc::unbounded_channel();
+                let answer = async {
+                    let events = events;
+                    let mut on_event = |event: AnswerEvent| {
+                        events.send(event).ok();
+                    };
+                    session
+                        .ask_streaming(&engine, question.trim(), &mut on_event)
+                        .await
+                };
+                // Events go out while the answer is still being worked on.
+                let forward = async {
+                    let mut connected = true;
+                    while let Some(event) = pending.recv().await {
+                        if connected {
+                            connected = send_json(&mut socket, &event).await;
+                        }
+                    }
+                    connected
+                };
+                let (result, connected) = tokio::join!(answer, forward);
+                if !connected {
+                    break;
+                }
+
+                match result {
+                    Ok(answer) => ChatReply::Done {
+                        request_id: request_id.clone(),
+                        answer: answer.text,
+                        tool_calls: answer.tool_calls,
+                        sources: answer.sources,
+                        mismatches: answer.mismatches,
+                        latency_ms: started.elapsed().as_millis(),
+                        turns: session.turns().len(),
+                    },
+                    Err(error) => ChatReply::Error {
+                        request_id: request_id.clone(),
+                        error: ErrorSummary::from(&error),
+                    },
+                }
+            }
+        };
+
+        if !send_json(&mut socket, &reply).await {
+            break;
+        }
+    }
+}
+
+/// Reads a chat message. Text that isn't JSON is a question; JSON must be
+/// one of the [`ChatRequest`] messages, so a mistyped one isn't answered as
+/// if it were a question.
+fn parse_chat_request(text: &str) -> Result<ChatRequest> {
+    match serde_json::from_str::<serde_json::Value>(text) {
+        Ok(json) => serde_json::from_value(json)
+            .map_err(|e| PokidexError::InvalidRequest(format!("chat message: {}", e))),
+        Err(_) => Ok(ChatRequest::Ask {
+            question: text.to_string(),
+        }),
+    }
+}
+
+/// Sends `value` as a JSON text frame; false once the client is gone.
+async fn send_json(socket: &mut WebSocket, value: &impl Serialize) -> bool {
+    let Ok(json) = serde_json::to_string(value) else {
+        return false;
+    };
+    socket.send(Message::Text(json.into())).await.is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::router;
+    use crate::config::Config;
+    use crate::rag::RAGEngine;
+    use crate::search::Dataset;
+    use crate::test_support::{entry, EmptySource, ScriptedModel};
+    use axum::body::{to_bytes, Body};
+    use axum::http::{Request, StatusCode};
+    use axum::response::Response;
+    use axum::Router;
+    use futures::{SinkExt, StreamExt};
+    use serde_json::Value;
+    use std::sync::Arc;
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_tungstenite::tungstenite::Message;
+    use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+    use tower::ServiceExt;
+
+    /// Looks up the fastest Flying type in the dataset, then answers.
+    fn fastest_flyer(prompt: &str) -> String {
+        if prompt.contains("Tool results so far") {
+            r#"{"type":"final","answer":"Aerodactyl is the fastest [S1]."}"#
+        } else {
+            r#"{"type":"action","tool":"search_pokemon","args":{"types":["flying"],"sort":"speed","limit":1}}"#
+        }
+        .to_string()
+    }
+
+    fn app() -> Router {
+        let config = Config {
+            cache_dir: std::env::temp_dir().join("pokidex-server-tests"),
+            ..Config::default()
+        };
+        let engine = RAGEngine::builder()
+            .config(config)
+            .llm(ScriptedModel::new(fastest_flyer))
+            .data_source(EmptySource)
+            .dataset(Dataset {
+                entries: vec![
+                    entry("aerodactyl", 142, &["rock", "flying"], 130, false),
+                    entry("archeops", 567, &["rock", "flying"], 110, false),
+                ],
+            })
+            .build()
+            .unwrap();
+        router(Arc::new(engine))
+    }
+
+    async fn json_body(response: Response) -> Value {
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn echoes_the_request_id() {
+        let request = Request::get("/pokemon/missingno")
+            .header("x-request-id", "req-42")
+            .body(Body::empty())
+            .unwrap();
+        let response = app().oneshot(request).await.unwrap();
+        assert_eq!(response.headers()["x-request-id"], "req-42");
+        assert_eq!(json_body(response).await["request_id"], "req-42");
+
+        let request = Request::get("/pokemon/missingno")
+            .body(Body::empty())
+            .unwrap();
+        let response = app().oneshot(request).await.unwrap();
+        let generated = response.headers()["x-request-id"]
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert!(!generated.is_empty());
+        assert_eq!(json_body(response).await["request_id"], generated);
+    }
+
+    #[tokio::test]
+    async fn reports_unknown_pokemon_as_not_found() {
+        let request = Request::get("/pokemon/missingno")
+            .body(Body::empty())
+            .unwrap();
+        let response = app().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(json_body(response).await["error"]["kind"], "not_found");
+    }
+
+    #[tokio::test]
+    async fn rejects_identify_without_an_image() {
+        let body = "--X\r\n\
+                    Content-Disposition: form-data; name=\"photo\"\r\n\r\n\
+                    not an image\r\n\
+                    --X--\r\n";
+        let request = Request::post("/identify")
+            .header("content-type", "multipart/form-data; boundary=X")
+            .body(Body::from(body))
+            .unwrap();
+        let response = app().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = json_body(response).await;
+        assert_eq!(body["error"]["kind"], "invalid_request");
+        assert!(body["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("'image'"));
+    }
+
+    #[tokio::test]
+    async fn answers_questions_as_json() {
+        let request = Request::post("/ask")
+            .header("content-type", "application/json")
+            .header("x-request-id", "req-7")
+            .body(Body::from(r#"{"question": "Fastest Flying Pokémon?"}"#))
+            .unwrap();
+        let response = app().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = json_body(response).await;
+        assert_eq!(body["request_id"], "req-7");
+        assert_eq!(body["answer"], "Aerodactyl is the fastest [S1].");
+        assert_eq!(body["tool_calls"][0]["tool"], "search_pokemon");
+        assert_eq!(body["tool_calls"][0]["args"]["sort"], "speed");
+        assert_eq!(body["sources"].as_array().unwrap().len(), 1);
+        assert_eq!(body["mismatches"], serde_json::json!([]));
+    }
+
+    type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+    /// Serves [`app`] on a free local port and opens a chat socket to it.
+    async fn connect_chat() -> Socket {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app()).await });
+        let (socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/chat", addr))
+            .await
+            .unwrap();
+        socket
+    }
+
+    async fn send(socket: &mut Socket, text: &str) {
+        socket.send(Message::text(text)).await.unwrap();
+    }
+
+    async fn receive(socket: &mut Socket) -> Value {
+        loop {
+            match socket.next().await.unwrap().unwrap() {
+                Message::Text(text) => return serde_json::from_str(&text).unwrap(),
+                Message::Close(_) => panic!("chat socket closed"),
+                _ => continue,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn streams_tool_events_before_the_answer() {
+        let mut socket = connect_chat().await;
+        send(
+            &mut socket,
+            r#"{"type": "ask", "question": "Fastest Flying Pokémon?"}"#,
+        )
+        .await;
+
+        let mut types = Vec::new();
+        let done = loop {
+            let message = receive(&mut socket).await;
+            types.push(message["type"].as_str().unwrap().to_string());
+            if message["type"] == "done" {
+                break message;
+            }
+        };
+        assert_eq!(types, ["tool_start", "tool_end", "text_delta", "done"]);
+        assert_eq!(done["answer"], "Aerodactyl is the fastest [S1].");
+        assert_eq!(done["tool_calls"][0]["tool"], "search_pokemon");
+        assert_eq!(done["turns"], 1);
+
+        // A plain-text question continues the same conversation.
+        send(&mut socket, "And the slowest?").await;
+        let done = loop {
+            let message = receive(&mut socket).await;
+            if message["type"] == "done" {
+                break message;
+            }
+        };
+        assert_eq!(done["turns"], 2);
+    }
+
+    #[tokio::test]
+    async fn resets_the_conversation() {
+        let mut socket = connect_chat().await;
+        send(&mut socket, "Fastest Flying Pokémon?").await;
+        while receive(&mut socket).await["type"] != "done" {}
+
+        send(&mut socket, r#"{"type": "reset"}"#).await;
+        assert_eq!(receive(&mut socket).await["type"], "reset");
+
+        send(&mut socket, "Fastest Flying Pokémon?").await;
+        let done = loop {
+            let message = receive(&mut socket).await;
+            if message["type"] == "done" {
+                break message;
+            }
+        };
+        assert_eq!(done["turns"], 1);
+    }
+
+    #[tokio::test]
+    async fn rejects_malformed_chat_messages() {
+        let mut socket = connect_chat().await;
+        for message in [r#"{"type": "ask", "qustion": "Hi"}"#, r#"{"type": "rest"}"#] {
+            send(&mut socket, message).await;
+            let reply = receive(&mut socket).await;
+            assert_eq!(reply["type"], "error");
+            assert_eq!(reply["error"]["kind"], "invalid_request");
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use crate::error::Result;
+use crate::rag::{Answer, AnswerEvent, RAGEngine};
+use serde::Serialize;
+
+/// Turns kept as context; older ones are dropped to keep prompts short.
+const MAX_TURNS: usize = 10;
+
+/// One question and the answer it got.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Turn {
+    pub question: String,
+    pub answer: String,
+}
+
+/// A conversation with a [`RAGEngine`]: each question is answered with the
+/// previous turns as context, so follow-ups like "what about its evolution?"
+/// work. The engine itself stays stateless and can be shared by sessions.
+#[derive(Debug, Clone, Default)]
+pub struct ChatSession {
+    turns: Vec<Turn>,
+}
+
+impl ChatSession {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn turns(&self) -> &[Turn] {
+        &self.turns
+    }
+
+    /// Forgets the conversation so far.
+    pub fn clear(&mut self) {
+        self.turns.clear();
+    }
+
+    pub async fn ask(&mut self, engine: &RAGEngine, question: &str) -> Result<Answer> {
+        self.ask_streaming(engine, question, &mut |_| {}).await
+    }
+
+    /// Answers `question` and records the turn. Failed questions are not
+    /// recorded.
+    pub async fn ask_streaming(
+        &mut self,
+        engine: &RAGEngine,
+        question: &str,
+        on_event: &mut (dyn FnMut(AnswerEvent) + Send),
+    ) -> Result<Answer> {
+        let answer = engine
+            .process_query_streaming(question, &self.turns, on_event)
+            .await?;
+        self.turns.push(Turn {
+            question: question.to_string(),
+            answer: answer.text.clone(),
+        });
+        if self.turns.len() > MAX_TURNS {
+            self.turns.drain(..self.turns.len() - MAX_TURNS);
+        }
+        Ok(answer)
+    }
+}
@@ -0,0 +1,144 @@
+use crate::error::{PokidexError, Result};
+use crate::pokeapi::PokeApiClient;
+use async_trait::async_trait;
+use futures::stream::{self, StreamExt};
+use rustemon::model::evolution::EvolutionChain;
+use rustemon::model::moves::Move;
+use rustemon::model::pokemon::{Pokemon, PokemonSpecies};
+
+/// Upper bound on requests in flight during batch lookups.
+pub(crate) const MAX_CONCURRENT_REQUESTS: usize = 8;
+
+/// Where the engine gets Pokémon data from.
+///
+/// [`PokeApiClient`] is the default implementation; embedders can supply
+/// their own (a local mirror, a test fixture) through
+/// [`crate::RAGEngineBuilder::data_source`]. Only the two lookups are
+/// required, everything else is built on top of them.
+#[async_trait]
+pub trait PokemonDataSource: Send + Sync {
+    async fn get_pokemon(&self, name_or_id: &str) -> Result<Pokemon>;
+
+    async fn get_pokemon_species(&self, name_or_id: &str) -> Result<PokemonSpecies>;
+
+    /// Every species name in National Dex order. Sources that can't list
+    /// their contents return an empty list.
+    async fn pokemon_names(&self) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    /// The evolution chain `species` belongs to.
+    async fn get_evolution_chain(&self, species: &PokemonSpecies) -> Result<EvolutionChain> {
+        Err(PokidexError::InvalidRequest(format!(
+            "no evolution data for {}",
+            species.name
+        )))
+    }
+
+    /// A move by name or ID, e.g. `flamethrower`.
+    async fn get_move(&self, name_or_id: &str) -> Result<Move> {
+        Err(PokidexError::InvalidRequest(format!(
+            "no move data for {}",
+            name_or_id
+        )))
+    }
+
+    /// Fetches a Pokémon and its species concurrently. The species is
+    /// optional because some forms (e.g. `pikachu-gmax`) have no species
+    /// entry under the same name.
+    async fn get_pokemon_with_species(
+        &self,
+        name_or_id: &str,
+    ) -> Result<(Pokemon, Option<PokemonSpecies>)> {
+        let (pokemon, species) = tokio::join!(
+            self.get_pokemon(name_or_id),
+            self.get_pokemon_species(name_or_id)
+        );
+        Ok((pokemon?, species.ok()))
+    }
+
+    /// Fetches a species and its default Pokémon, e.g. `deoxys-normal` for
+    /// `deoxys`, whose types, stats and abilities stand for the species.
+    async fn get_species_with_default(&self, species: &str) -> Result<(PokemonSpecies, Pokemon)> {
+        let species = self.get_pokemon_species(species).await?;
+        let default = species
+            .varieties
+            .iter()
+            .find(|v| v.is_default)
+            .map_or(species.name.as_str(), |v| v.pokemon.name.as_str());
+        let pokemon = self.get_pokemon(default).await?;
+        Ok((species, pokemon))
+    }
+
+    /// Fetches several Pokémon with at most `MAX_CONCURRENT_REQUESTS` in
+    /// flight. Results come back in the same order as `names`.
+    async fn get_many(&self, names: &[&str]) -> Vec<Result<Pokemon>> {
+        // Collected up front: the boxed futures are lazy, and mapping inside
+        // the stream trips the compiler's Send inference for async traits.
+        let lookups: Vec<_> = names.iter().map(|name| self.get_pokemon(name)).collect();
+        stream::iter(lookups)
+            .buffered(MAX_CONCURRENT_REQUESTS)
+            .collect()
+            .await
+    }
+
+    /// Species counterpart of [`PokemonDataSource::get_many`].
+    async fn get_many_species(&self, names: &[&str]) -> Vec<Result<PokemonSpecies>> {
+        let lookups: Vec<_> = names
+            .iter()
+            .map(|name| self.get_pokemon_species(name))
+            .collect();
+        stream::iter(lookups)
+            .buffered(MAX_CONCURRENT_REQUESTS)
+            .collect()
+            .await
+    }
+
+    /// Fetches each Pokémon and then its species, with at most
+    /// `MAX_CONCURRENT_REQUESTS` requests in flight across both. Results
+    /// come back in the same order as `names`; species are optional as in
+    /// [`PokemonDataSource::get_pokemon_with_species`].
+    async fn get_many_with_species(
+        &self,
+        names: &[&str],
+    ) -> Vec<(Result<Pokemon>, Option<PokemonSpecies>)> {
+        let lookups: Vec<_> = names
+            .iter()
+            .map(|name| async move {
+                let pokemon = self.get_pokemon(name).await;
+                let species = self.get_pokemon_species(name).await.ok();
+                (pokemon, species)
+            })
+            .collect();
+        stream::iter(lookups)
+            .buffered(MAX_CONCURRENT_REQUESTS)
+            .collect()
+            .await
+    }
+
+    async fn extract_pokemon_name(&self, query: &str) -> Option<String> {
+        // Simple extraction - look for capitalized words that might be Pokemon names
+        // This is a basic implementation; could be improved with NLP
+        let words: Vec<&str> = query.split_whitespace().collect();
+
+        for word in words {
+            // Check if word starts with capital letter and is reasonable length
+            if word
+                .chars()
+                .next()
+                .map(|c| c.is_uppercase())
+                .unwrap_or(false)
+                && word.len() > 2
+                && word.len() < 20
+            {
+                // Try to get the Pokemon to verify
+                let normalized = PokeApiClient::normalize_pokemon_name(word);
+                if self.get_pokemon(&normalized).await.is_ok() {
+                    return Some(normalized);
+                }
+            }
+        }
+
+        None
+    }
+}
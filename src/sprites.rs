@@ -0,0 +1,166 @@
+//! Pokémon sprite images, downloaded once and kept next to the PokéAPI
+//! response cache.
+
+use crate::error::{PokidexError, Result};
+use crate::retry::RetryPolicy;
+use rustemon::model::pokemon::Pokemon;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Which picture of a Pokémon to show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SpriteKind {
+    /// The large official artwork.
+    #[default]
+    Artwork,
+    /// The small in-game front sprite.
+    Front,
+}
+
+impl FromStr for SpriteKind {
+    type Err = PokidexError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "artwork" => Ok(SpriteKind::Artwork),
+            "front" => Ok(SpriteKind::Front),
+            other => Err(PokidexError::InvalidRequest(format!(
+                "unknown sprite '{}' (expected artwork or front)",
+                other
+            ))),
+        }
+    }
+}
+
+/// URL of the requested picture, falling back to the other kind when a form
+/// only has one of them.
+pub fn sprite_url(pokemon: &Pokemon, kind: SpriteKind) -> Option<&str> {
+    let artwork = pokemon
+        .sprites
+        .other
+        .official_artwork
+        .front_default
+        .as_deref();
+    let front = pokemon.sprites.front_default.as_deref();
+    match kind {
+        SpriteKind::Artwork => artwork.or(front),
+        SpriteKind::Front => front.or(artwork),
+    }
+}
+
+/// Sprite downloads stored under `<cache_dir>/sprites`, mirroring the
+/// sprite repository's layout so each file is fetched only once.
+pub struct SpriteCache {
+    dir: PathBuf,
+    http: reqwest::Client,
+}
+
+impl SpriteCache {
+    pub fn new(cache_dir: &Path) -> Self {
+        Self {
+            dir: cache_dir.join("sprites"),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// The image bytes behind `url`, from disk when already downloaded.
+    pub async fn get(&self, url: &str) -> Result<Vec<u8>> {
+        let path = self.dir.join(cache_path(url));
+        if let Ok(bytes) = fs::read(&path) {
+            return Ok(bytes);
+        }
+
+        let bytes = RetryPolicy::default()
+            .with_timeout(DOWNLOAD_TIMEOUT)
+            .run(|| self.download(url))
+            .await?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Written aside and renamed so an interrupted run never leaves half
+        // a file behind.
+        let partial = path.with_extension("part");
+        fs::write(&partial, &bytes)?;
+        fs::rename(&partial, &path)?;
+        Ok(bytes)
+    }
+
+    async fn download(&self, url: &str) -> Result<Vec<u8>> {
+        let response = self
+            .http
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| PokidexError::from_reqwest("sprite host", &e))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(PokidexError::Upstream {
+                status: status.as_u16(),
+                message: url.to_string(),
+            });
+        }
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| PokidexError::from_reqwest("sprite host", &e))?;
+        Ok(bytes.to_vec())
+    }
+}
+
+/// Relative cache path for a sprite URL: the part after the last `/sprites/` (or
+/// after the host), with anything but plain file-name characters replaced.
+fn cache_path(url: &str) -> PathBuf {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let path = match without_scheme.rsplit_once("/sprites/") {
+        Some((_, path)) => path,
+        None => without_scheme
+            .split_once('/')
+            .map_or(without_scheme, |(_, path)| path),
+    };
+    let path = path.split(['?', '#']).next().unwrap_or_default();
+
+    let relative: PathBuf = path
+        .split('/')
+        .filter(|segment| !segment.is_empty() && *segment != "." && *segment != "..")
+        .map(|segment| {
+            segment
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect::<String>()
+        })
+        .collect();
+    if relative.as_os_str().is_empty() {
+        PathBuf::from("sprite")
+    } else {
+        relative
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::cache_path;
+    use std::path::PathBuf;
+
+    #[test]
+    fn mirrors_sprite_paths_safely() {
+        assert_eq!(
+            cache_path("https://raw.githubusercontent.com/PokeAPI/sprites/master/sprites/pokemon/other/official-artwork/25.png"),
+            PathBuf::from("pokemon/other/official-artwork/25.png")
+        );
+        assert_eq!(
+            cache_path("https://example.com/../a b/x.png?v=2"),
+            PathBuf::from("a_b/x.png")
+        );
+    }
+}
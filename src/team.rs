@@ -0,0 +1,153 @@
+//! Team summaries: weaknesses several members share and the types their
+//! same-type (STAB) attacks hit super-effectively.
+
+use crate::types::{self, TYPES};
+use rustemon::model::pokemon::Pokemon;
+use serde::Serialize;
+use std::fmt;
+
+/// One member and its types.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Member {
+    pub name: String,
+    pub types: Vec<String>,
+}
+
+impl From<&Pokemon> for Member {
+    fn from(pokemon: &Pokemon) -> Self {
+        Self {
+            name: pokemon.name.clone(),
+            types: pokemon.types.iter().map(|t| t.type_.name.clone()).collect(),
+        }
+    }
+}
+
+/// An attacking type that is super-effective against several members.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SharedWeakness {
+    pub attacking: &'static str,
+    /// Members taking more than 1×.
+    pub weak: usize,
+    /// Members taking less than 1×, immunities included.
+    pub resist: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TeamSummary {
+    pub members: Vec<Member>,
+    /// Most-shared first.
+    pub shared_weaknesses: Vec<SharedWeakness>,
+    /// Defending types no member's own type hits for more than 1×.
+    pub uncovered: Vec<&'static str>,
+}
+
+impl TeamSummary {
+    /// A weakness counts as shared when at least two members have it and
+    /// fewer members resist it than are hit by it.
+    pub fn new(members: Vec<Member>) -> Self {
+        let mut shared_weaknesses: Vec<SharedWeakness> = TYPES
+            .iter()
+            .map(|&attacking| {
+                let multipliers = members.iter().map(|m| {
+                    let defending: Vec<&str> = m.types.iter().map(String::as_str).collect();
+                    types::effectiveness_against(attacking, &defending)
+                });
+                let (weak, resist) = multipliers.fold((0, 0), |(weak, resist), x| {
+                    (weak + usize::from(x > 1.0), resist + usize::from(x < 1.0))
+                });
+                SharedWeakness {
+                    attacking,
+                    weak,
+                    resist,
+                }
+            })
+            .filter(|w| w.weak >= 2 && w.weak > w.resist)
+            .collect();
+        // Stable, so ties keep the chart's order.
+        shared_weaknesses.sort_by_key(|w| std::cmp::Reverse(w.weak - w.resist));
+
+        let uncovered = TYPES
+            .iter()
+            .copied()
+            .filter(|defending| {
+                !members
+                    .iter()
+                    .flat_map(|m| &m.types)
+                    .any(|attacking| types::effectiveness(attacking, defending) > 1.0)
+            })
+            .collect();
+
+        Self {
+            members,
+            shared_weaknesses,
+            uncovered,
+        }
+    }
+
+    pub fn from_pokemon<'a>(team: impl IntoIterator<Item = &'a Pokemon>) -> Self {
+        Self::new(team.into_iter().map(Member::from).collect())
+    }
+}
+
+impl fmt::Display for TeamSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Team summary ({} Pokémon)", self.members.len())?;
+        for member in &self.members {
+            writeln!(f, "  {}: {}", member.name, member.types.join("/"))?;
+        }
+
+        let shared: Vec<String> = self
+            .shared_weaknesses
+            .iter()
+            .map(|w| format!("{} ({} weak, {} resist)", w.attacking, w.weak, w.resist))
+            .collect();
+        if shared.is_empty() {
+            writeln!(f, "Shared weaknesses: none")?;
+        } else {
+            writeln!(f, "Shared weaknesses: {}", shared.join(", "))?;
+        }
+
+        write!(
+            f,
+            "STAB coverage: super-effective against {} of {} types",
+            TYPES.len() - self.uncovered.len(),
+            TYPES.len()
+        )?;
+        if !self.uncovered.is_empty() {
+            write!(f, "\nNot covered: {}", self.uncovered.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Member, TeamSummary};
+
+    fn member(name: &str, types: &[&str]) -> Member {
+        Member {
+            name: name.to_string(),
+            types: types.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn finds_shared_weaknesses_and_coverage_gaps() {
+        let summary = TeamSummary::new(vec![
+            member("charizard", &["fire", "flying"]),
+            member("moltres", &["fire", "flying"]),
+            member("golem", &["rock", "ground"]),
+        ]);
+
+        let shared: Vec<&str> = summary
+            .shared_weaknesses
+            .iter()
+            .map(|w| w.attacking)
+            .collect();
+        // Golem resists rock and is immune to electric, but is outnumbered.
+        assert_eq!(shared, ["water", "rock", "electric"]);
+
+        assert!(summary.uncovered.contains(&"normal"));
+        assert!(!summary.uncovered.contains(&"grass"));
+    }
+}
@@ -0,0 +1,92 @@
+//! Fakes shared by the unit tests: a data source, a language model and
+//! dataset entries.
+
+use crate::error::{PokidexError, Result};
+use crate::image_input::ImageInput;
+use crate::llm::LanguageModel;
+use crate::search::{BaseStats, DexEntry};
+use crate::source::PokemonDataSource;
+use async_trait::async_trait;
+use rustemon::model::pokemon::{Pokemon, PokemonSpecies};
+use std::time::Duration;
+
+/// A source that knows no Pokémon.
+pub(crate) struct EmptySource;
+
+#[async_trait]
+impl PokemonDataSource for EmptySource {
+    async fn get_pokemon(&self, name_or_id: &str) -> Result<Pokemon> {
+        Err(PokidexError::NotFound {
+            kind: "Pokémon",
+            name: name_or_id.to_string(),
+        })
+    }
+
+    async fn get_pokemon_species(&self, name_or_id: &str) -> Result<PokemonSpecies> {
+        Err(PokidexError::NotFound {
+            kind: "Pokémon",
+            name: name_or_id.to_string(),
+        })
+    }
+}
+
+type Script<T> = Box<dyn Fn(&str) -> T + Send + Sync>;
+
+/// A model that replies to each prompt with `reply(prompt)`, optionally
+/// after a delay that also depends on the prompt. It can't see images.
+pub(crate) struct ScriptedModel {
+    reply: Script<String>,
+    delay: Script<Duration>,
+}
+
+impl ScriptedModel {
+    pub(crate) fn new(reply: impl Fn(&str) -> String + Send + Sync + 'static) -> Self {
+        Self {
+            reply: Box::new(reply),
+            delay: Box::new(|_| Duration::ZERO),
+        }
+    }
+
+    pub(crate) fn with_delay(
+        mut self,
+        delay: impl Fn(&str) -> Duration + Send + Sync + 'static,
+    ) -> Self {
+        self.delay = Box::new(delay);
+        self
+    }
+}
+
+#[async_trait]
+impl LanguageModel for ScriptedModel {
+    async fn generate_content(&self, prompt: &str) -> Result<String> {
+        let delay = (self.delay)(prompt);
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+        Ok((self.reply)(prompt))
+    }
+
+    async fn generate_with_image(&self, _: &str, _: &ImageInput) -> Result<String> {
+        Err(PokidexError::InvalidRequest("no vision".to_string()))
+    }
+}
+
+/// A dataset entry with only the fields searches usually filter on set.
+pub(crate) fn entry(name: &str, id: i64, types: &[&str], speed: u32, legendary: bool) -> DexEntry {
+    DexEntry {
+        name: name.to_string(),
+        id,
+        types: types.iter().map(|t| t.to_string()).collect(),
+        abilities: vec!["pressure".to_string()],
+        stats: BaseStats {
+            speed,
+            ..BaseStats::default()
+        },
+        generation: 1,
+        legendary,
+        mythical: false,
+        egg_groups: Vec::new(),
+        color: "red".to_string(),
+        habitat: None,
+    }
+}
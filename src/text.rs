@@ -0,0 +1,18 @@
+//! Display forms of PokéAPI names.
+
+/// `solar-power` as `Solar Power`.
+pub(crate) fn title_case(name: &str) -> String {
+    name.split('-')
+        .map(capitalized)
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// `word` with its first letter in upper case.
+pub(crate) fn capitalized(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
@@ -0,0 +1,399 @@
+//! The data tools shared by the agent loop and the MCP server.
+
+use crate::citation::Resource;
+use crate::damage::{self, CalcRequest};
+use crate::error::{PokidexError, Result};
+use crate::format::{self, FormatOptions};
+use crate::rag::ToolCall;
+use crate::search::{LazyDataset, SearchQuery};
+use crate::source::PokemonDataSource;
+use crate::verify::Facts;
+use serde_json::{json, Value};
+
+/// Moves listed by `get_pokemon_moves`; the rest are summarized as a count.
+const MOVE_LIST_LIMIT: usize = 30;
+
+/// Matches listed by `search_pokemon`, whatever limit the model asks for.
+const SEARCH_LIMIT: usize = 25;
+
+/// What a tool takes as input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolArgs {
+    /// A single Pokémon name or National Dex ID.
+    Name,
+    /// A list of Pokémon names or IDs.
+    Names,
+    /// Search filters, read as a [`SearchQuery`].
+    Query,
+    /// A damage calculation, read as a [`CalcRequest`].
+    Calc,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Tool {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub args: ToolArgs,
+}
+
+pub const TOOLS: &[Tool] = &[
+    Tool {
+        name: "get_pokemon",
+        description: "Returns detailed Pokemon data for a given name or id.",
+        args: ToolArgs::Name,
+    },
+    Tool {
+        name: "get_pokemon_species",
+        description: "Returns species information (flavor text, capture rate, etc.).",
+        args: ToolArgs::Name,
+    },
+    Tool {
+        name: "get_pokemon_stats",
+        description: "Returns only base stats for a given pokemon.",
+        args: ToolArgs::Name,
+    },
+    Tool {
+        name: "get_pokemon_moves",
+        description: "Returns a compact move list for a given pokemon.",
+        args: ToolArgs::Name,
+    },
+    Tool {
+        name: "compare_pokemon",
+        description: "Returns types, stats and abilities for several pokemon at once.",
+        args: ToolArgs::Names,
+    },
+    Tool {
+        name: "search_pokemon",
+        description: "Lists every pokemon matching filters (types, generation, ability, base stat ranges, legendary/mythical, egg group, color, habitat), optionally sorted by a stat. Use it for questions about sets of pokemon.",
+        args: ToolArgs::Query,
+    },
+    Tool {
+        name: "calculate_damage",
+        description: "Main-series damage calculation for one move: min/max damage and % of the defender's HP, with levels, natures, EVs/IVs, items, abilities, stat boosts and critical hits. Use it for \"does X OHKO Y\" questions.",
+        args: ToolArgs::Calc,
+    },
+];
+
+impl Tool {
+    pub fn find(name: &str) -> Option<&'static Tool> {
+        TOOLS.iter().find(|t| t.name == name)
+    }
+
+    /// JSON Schema of the tool's arguments object.
+    pub fn input_schema(&self) -> Value {
+        match self.args {
+            ToolArgs::Name => json!({
+                "type": "object",
+                "properties": {
+                    "name": {
+                        "type": "string",
+                        "description": "Pokemon name or National Dex ID, e.g. \"pikachu\" or \"25\""
+                    }
+                },
+                "required": ["name"]
+            }),
+            ToolArgs::Names => json!({
+                "type": "object",
+                "properties": {
+                    "names": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "minItems": 1,
+                        "description": "Pokemon names or National Dex IDs"
+                    }
+                },
+                "required": ["names"]
+            }),
+            ToolArgs::Query => {
+                let range = json!({
+                    "type": "object",
+                    "properties": {
+                        "min": { "type": "integer", "minimum": 0 },
+                        "max": { "type": "integer", "minimum": 0 }
+                    },
+                    "additionalProperties": false
+                });
+                let stat = json!({
+                    "type": "string",
+                    "enum": ["hp", "attack", "defense", "special-attack", "special-defense", "speed", "total"]
+                });
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "types": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "Types a match must all have, e.g. [\"fire\", \"flying\"]"
+                        },
+                        "generation": { "type": "integer", "minimum": 1, "description": "Generation introduced in, e.g. 1" },
+                        "ability": { "type": "string", "description": "e.g. \"levitate\"" },
+                        "stats": {
+                            "type": "object",
+                            "description": "Inclusive base stat bounds, e.g. {\"speed\": {\"min\": 101}}; \"total\" is the base stat total",
+                            "propertyNames": stat,
+                            "additionalProperties": range
+                        },
+                        "legendary": { "type": "boolean" },
+                        "mythical": { "type": "boolean" },
+                        "egg_group": { "type": "string", "description": "e.g. \"dragon\"" },
+                        "color": { "type": "string", "description": "Pokédex color, e.g. \"red\"" },
+                        "habitat": { "type": "string", "description": "e.g. \"mountain\"" },
+                        "sort": stat,
+                        "ascending": { "type": "boolean", "description": "Sort lowest first instead of highest first" },
+                        "limit": { "type": "integer", "minimum": 1 }
+                    },
+                    "additionalProperties": false
+                })
+            }
+            ToolArgs::Calc => {
+                let stat = json!({
+                    "type": "string",
+                    "enum": ["hp", "attack", "defense", "special-attack", "special-defense", "speed"]
+                });
+                let spread = |description: &str| {
+                    json!({
+                        "type": "object",
+                        "description": description,
+                        "propertyNames": stat,
+                        "additionalProperties": { "type": "integer", "minimum": 0 }
+                    })
+                };
+                let combatant = json!({
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string", "description": "Pokemon name or National Dex ID" },
+                        "level": { "type": "integer", "minimum": 1, "maximum": 100, "description": "Defaults to 100" },
+                        "nature": { "type": "string", "description": "e.g. \"adamant\"; neutral when left out" },
+                        "evs": spread("EVs by stat, e.g. {\"attack\": 252, \"speed\": 252}; 0 when left out"),
+                        "ivs": spread("IVs by stat; 31 when left out"),
+                        "item": { "type": "string", "description": "e.g. \"choice-band\", \"life-orb\", \"assault-vest\"" },
+                        "ability": { "type": "string", "description": "e.g. \"adaptability\", \"levitate\"; none when left out" },
+                        "boost": { "type": "integer", "minimum": -6, "maximum": 6, "description": "Stat stage of the attacking or defending stat" }
+                    },
+                    "required": ["name"],
+                    "additionalProperties": false
+                });
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "attacker": combatant,
+                        "defender": combatant,
+                        "move": { "type": "string", "description": "e.g. \"earthquake\"" },
+                        "critical": { "type": "boolean" },
+                        "burned": { "type": "boolean", "description": "The attacker is burned" }
+                    },
+                    "required": ["attacker", "defender", "move"],
+                    "additionalProperties": false
+                })
+            }
+        }
+    }
+
+    /// Signature shown to the model, e.g. `get_pokemon(name)`.
+    pub(crate) fn signature(&self) -> String {
+        match self.args {
+            ToolArgs::Name => format!("{}(name)", self.name),
+            ToolArgs::Names => format!("{}(names)", self.name),
+            ToolArgs::Query | ToolArgs::Calc => format!("{}(args)", self.name),
+        }
+    }
+
+    /// Example action the model can copy.
+    pub(crate) fn example_action(&self) -> String {
+        match self.args {
+            ToolArgs::Name => format!(
+                r#"{{"type":"action","tool":"{}","name":"pikachu"}}"#,
+                self.name
+            ),
+            ToolArgs::Names => format!(
+                r#"{{"type":"action","tool":"{}","names":["pikachu","raichu"]}}"#,
+                self.name
+            ),
+            ToolArgs::Query => format!(
+                r#"{{"type":"action","tool":"{}","args":{{"types":["fire","flying"],"generation":1,"stats":{{"speed":{{"min":101}}}},"legendary":false,"sort":"speed","limit":10}}}}"#,
+                self.name
+            ),
+            ToolArgs::Calc => format!(
+                r#"{{"type":"action","tool":"{}","args":{{"attacker":{{"name":"garchomp","nature":"adamant","evs":{{"attack":252}},"item":"choice-band"}},"defender":{{"name":"heatran","evs":{{"hp":252}}}},"move":"earthquake"}}}}"#,
+                self.name
+            ),
+        }
+    }
+}
+
+/// A tool's formatted result and the PokéAPI resources it was read from.
+#[derive(Debug, Clone)]
+pub struct ToolOutput {
+    pub text: String,
+    pub resources: Vec<Resource>,
+    /// The Pokémon the result shows as structured data, which answers are
+    /// checked against. Empty for searches and calculations.
+    pub facts: Vec<Facts>,
+}
+
+/// Runs `call` against `source` (or, for searches, `dataset`) and returns
+/// the formatted result. Lookup failures are returned as errors rather than
+/// as text; see [`is_model_error`] for which ones the agent shows the model.
+pub async fn run_tool(
+    source: &dyn PokemonDataSource,
+    options: &FormatOptions,
+    dataset: &LazyDataset,
+    call: &ToolCall,
+) -> Result<ToolOutput> {
+    let Some(tool) = Tool::find(&call.tool) else {
+        return Err(PokidexError::InvalidRequest(format!(
+            "unknown tool '{}'",
+            call.tool
+        )));
+    };
+    match tool.args {
+        ToolArgs::Query => return search(dataset, call).await,
+        ToolArgs::Calc => return calculate_damage(source, call).await,
+        ToolArgs::Name | ToolArgs::Names => {}
+    }
+    if call.names.is_empty() {
+        return Err(PokidexError::InvalidRequest(format!(
+            "{} needs a Pokemon name",
+            tool.name
+        )));
+    }
+
+    let name = call.names[0].as_str();
+    let (text, resources, facts) = match tool.name {
+        "get_pokemon" => {
+            let (pokemon, species) = source.get_pokemon_with_species(name).await?;
+            let mut resources = vec![Resource::pokemon(&pokemon)];
+            resources.extend(species.as_ref().map(Resource::species));
+            let text = format::format_pokemon_entry(&pokemon, species.as_ref(), options);
+            (
+                text,
+                resources,
+                vec![Facts::new(&pokemon, species.as_ref())],
+            )
+        }
+        "get_pokemon_species" => {
+            let (species, pokemon) =
+                tokio::join!(source.get_pokemon_species(name), source.get_pokemon(name));
+            let species = species?;
+            // Reuse the full formatter when the Pokemon itself is available
+            match pokemon {
+                Ok(pokemon) => (
+                    format::format_pokemon_with_species(&pokemon, &species, options),
+                    vec![Resource::species(&species), Resource::pokemon(&pokemon)],
+                    vec![Facts::new(&pokemon, Some(&species))],
+                ),
+                Err(_) => (
+                    format::format_species_summary(&species, options),
+                    vec![Resource::species(&species)],
+                    Vec::new(),
+                ),
+            }
+        }
+        "get_pokemon_stats" => {
+            let pokemon = source.get_pokemon(name).await?;
+            (
+                format::format_pokemon_stats(&pokemon),
+                vec![Resource::pokemon(&pokemon)],
+                vec![Facts::new(&pokemon, None)],
+            )
+        }
+        "get_pokemon_moves" => {
+            let pokemon = source.get_pokemon(name).await?;
+            (
+                format::format_pokemon_moves(&pokemon, MOVE_LIST_LIMIT, options),
+                vec![Resource::pokemon(&pokemon)],
+                vec![Facts::new(&pokemon, None)],
+            )
+        }
+        "compare_pokemon" => {
+            let names: Vec<&str> = call.names.iter().map(String::as_str).collect();
+            let mut sections = Vec::with_capacity(names.len());
+            let mut resources = Vec::with_capacity(names.len());
+            let mut facts = Vec::with_capacity(names.len());
+            for pokemon in source.get_many(&names).await {
+                let pokemon = pokemon?;
+                sections.push(format::format_pokemon_data(&pokemon));
+                resources.push(Resource::pokemon(&pokemon));
+                facts.push(Facts::new(&pokemon, None));
+            }
+            (sections.join("\n---\n"), resources, facts)
+        }
+        other => {
+            return Err(PokidexError::InvalidRequest(format!(
+                "tool '{}' is not implemented",
+                other
+            )))
+        }
+    };
+    Ok(ToolOutput {
+        text,
+        resources,
+        facts,
+    })
+}
+
+/// Whether `error` is a mistake in the call itself (bad arguments, an
+/// unknown tool, a misspelled name) that the model can correct on its next
+/// step, rather than a failure to report to the user.
+pub(crate) fn is_model_error(error: &PokidexError) -> bool {
+    matches!(
+        error,
+        PokidexError::NotFound { .. } | PokidexError::InvalidRequest(_)
+    )
+}
+
+async fn search(dataset: &LazyDataset, call: &ToolCall) -> Result<ToolOutput> {
+    let mut query = if call.args.is_null() {
+        SearchQuery::default()
+    } else {
+        serde_json::from_value::<SearchQuery>(call.args.clone())
+            .map_err(|e| PokidexError::InvalidRequest(format!("{} arguments: {}", call.tool, e)))?
+    };
+    query.limit = Some(query.limit.map_or(SEARCH_LIMIT, |l| l.min(SEARCH_LIMIT)));
+    let results = dataset.get().await?.search(&query)?;
+    Ok(ToolOutput {
+        text: results.format(),
+        resources: results.entries.iter().map(|e| e.resource()).collect(),
+        facts: Vec::new(),
+    })
+}
+
+async fn calculate_damage(source: &dyn PokemonDataSource, call: &ToolCall) -> Result<ToolOutput> {
+    let request = if call.args.is_null() {
+        CalcRequest::default()
+    } else {
+        serde_json::from_value::<CalcRequest>(call.args.clone())
+            .map_err(|e| PokidexError::InvalidRequest(format!("{} arguments: {}", call.tool, e)))?
+    };
+    let (damage, resources) = damage::calc_with_resources(source, &request).await?;
+    Ok(ToolOutput {
+        text: damage.format(),
+        resources,
+        facts: Vec::new(),
+    })
+}
+
+/// The tool list and reply format for the agent prompt.
+pub(crate) fn prompt_description() -> String {
+    let mut out = String::from("\nAvailable tools you can call:\n");
+    for (i, tool) in TOOLS.iter().enumerate() {
+        out.push_str(&format!(
+            "{}) {} -> {}\n",
+            i + 1,
+            tool.signature(),
+            tool.description
+        ));
+    }
+    out.push_str(
+        "\nIf you need to call a tool, respond with a JSON object exactly in this shape:\n",
+    );
+    let examples: Vec<String> = TOOLS
+        .iter()
+        .map(|t| format!("  {}\n", t.example_action()))
+        .collect();
+    out.push_str(&examples.join("or\n"));
+    out.push_str(
+        "\nIf you can answer the user directly without calling a tool, respond with:\n  {\"type\":\"final\",\"answer\":\"<your answer>\"}\n",
+    );
+    out
+}
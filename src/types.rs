@@ -0,0 +1,159 @@
+//! The type effectiveness chart (Generation VI onwards).
+//!
+//! Kept locally rather than fetched from PokéAPI's `type` endpoint: it is
+//! small, fixed, and needed for every team summary.
+
+/// The 18 types, in PokéAPI's order.
+pub const TYPES: &[&str] = &[
+    "normal", "fighting", "flying", "poison", "ground", "rock", "bug", "ghost", "steel", "fire",
+    "water", "grass", "electric", "psychic", "ice", "dragon", "dark", "fairy",
+];
+
+/// Attacking type, then the defending types it hits for 2×, ½× and 0×.
+/// Anything not listed takes 1×.
+type Row = (&'static str, Matchups, Matchups, Matchups);
+type Matchups = &'static [&'static str];
+
+const CHART: &[Row] = &[
+    ("normal", &[], &["rock", "steel"], &["ghost"]),
+    (
+        "fighting",
+        &["normal", "ice", "rock", "dark", "steel"],
+        &["poison", "flying", "psychic", "bug", "fairy"],
+        &["ghost"],
+    ),
+    (
+        "flying",
+        &["grass", "fighting", "bug"],
+        &["electric", "rock", "steel"],
+        &[],
+    ),
+    (
+        "poison",
+        &["grass", "fairy"],
+        &["poison", "ground", "rock", "ghost"],
+        &["steel"],
+    ),
+    (
+        "ground",
+        &["fire", "electric", "poison", "rock", "steel"],
+        &["grass", "bug"],
+        &["flying"],
+    ),
+    (
+        "rock",
+        &["fire", "ice", "flying", "bug"],
+        &["fighting", "ground", "steel"],
+        &[],
+    ),
+    (
+        "bug",
+        &["grass", "psychic", "dark"],
+        &[
+            "fire", "fighting", "poison", "flying", "ghost", "steel", "fairy",
+        ],
+        &[],
+    ),
+    ("ghost", &["psychic", "ghost"], &["dark"], &["normal"]),
+    (
+        "steel",
+        &["ice", "rock", "fairy"],
+        &["fire", "water", "electric", "steel"],
+        &[],
+    ),
+    (
+        "fire",
+        &["grass", "ice", "bug", "steel"],
+        &["fire", "water", "rock", "dragon"],
+        &[],
+    ),
+    (
+        "water",
+        &["fire", "ground", "rock"],
+        &["water", "grass", "dragon"],
+        &[],
+    ),
+    (
+        "grass",
+        &["water", "ground", "rock"],
+        &[
+            "fire", "grass", "poison", "flying", "bug", "dragon", "steel",
+        ],
+        &[],
+    ),
+    (
+        "electric",
+        &["water", "flying"],
+        &["electric", "grass", "dragon"],
+        &["ground"],
+    ),
+    (
+        "psychic",
+        &["fighting", "poison"],
+        &["psychic", "steel"],
+        &["dark"],
+    ),
+    (
+        "ice",
+        &["grass", "ground", "flying", "dragon"],
+        &["fire", "water", "ice", "steel"],
+        &[],
+    ),
+    ("dragon", &["dragon"], &["steel"], &["fairy"]),
+    (
+        "dark",
+        &["psychic", "ghost"],
+        &["fighting", "dark", "fairy"],
+        &[],
+    ),
+    (
+        "fairy",
+        &["fighting", "dragon", "dark"],
+        &["fire", "poison", "steel"],
+        &[],
+    ),
+];
+
+/// Damage multiplier of an `attacking` move against a single `defending`
+/// type. Unknown types (e.g. `stellar`, `shadow`) are neutral.
+pub fn effectiveness(attacking: &str, defending: &str) -> f32 {
+    let Some((_, double, half, immune)) = CHART.iter().find(|(t, ..)| *t == attacking) else {
+        return 1.0;
+    };
+    if immune.contains(&defending) {
+        0.0
+    } else if double.contains(&defending) {
+        2.0
+    } else if half.contains(&defending) {
+        0.5
+    } else {
+        1.0
+    }
+}
+
+/// Combined multiplier against a Pokémon with one or two `defending` types.
+pub fn effectiveness_against(attacking: &str, defending: &[&str]) -> f32 {
+    defending
+        .iter()
+        .map(|d| effectiveness(attacking, d))
+        .product()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{effectiveness, effectiveness_against, CHART, TYPES};
+
+    #[test]
+    fn combines_dual_types() {
+        assert_eq!(effectiveness("water", "fire"), 2.0);
+        assert_eq!(effectiveness_against("rock", &["fire", "flying"]), 4.0);
+        assert_eq!(effectiveness_against("ground", &["fire", "flying"]), 0.0);
+        assert_eq!(effectiveness_against("grass", &["water", "ground"]), 4.0);
+        assert_eq!(effectiveness_against("fire", &["water", "dragon"]), 0.25);
+
+        // Every attacking type has exactly one row.
+        assert!(TYPES
+            .iter()
+            .all(|t| CHART.iter().filter(|(a, ..)| a == t).count() == 1));
+    }
+}
@@ -0,0 +1,980 @@
+//! Checks the figures, types and abilities an answer states against the
+//! data it was generated from, since the model can misquote numbers that
+//! were right there in its context.
+//!
+//! Claims are read with simple word-distance rules. Anything ambiguous, such
+//! as a sentence about a Pokémon that wasn't looked up or a number sitting
+//! between two stat names, is skipped rather than risk a false alarm.
+
+use crate::text::title_case;
+use crate::types::TYPES;
+use rustemon::model::pokemon::{Pokemon, PokemonSpecies};
+use serde::Serialize;
+use std::fmt;
+
+/// Significant words a number may sit from the stat it belongs to.
+const MAX_DISTANCE: usize = 2;
+
+/// What a claim is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Field {
+    Hp,
+    Attack,
+    Defense,
+    SpecialAttack,
+    SpecialDefense,
+    Speed,
+    BaseStatTotal,
+    CaptureRate,
+    Height,
+    Weight,
+    Types,
+    Abilities,
+}
+
+impl Field {
+    fn label(self) -> &'static str {
+        match self {
+            Field::Hp => "base HP",
+            Field::Attack => "base Attack",
+            Field::Defense => "base Defense",
+            Field::SpecialAttack => "base Special Attack",
+            Field::SpecialDefense => "base Special Defense",
+            Field::Speed => "base Speed",
+            Field::BaseStatTotal => "base stat total",
+            Field::CaptureRate => "capture rate",
+            Field::Height => "height",
+            Field::Weight => "weight",
+            Field::Types => "type",
+            Field::Abilities => "abilities",
+        }
+    }
+
+    /// PokéAPI's name for the stat, if the field is one.
+    fn stat(self) -> Option<&'static str> {
+        match self {
+            Field::Hp => Some("hp"),
+            Field::Attack => Some("attack"),
+            Field::Defense => Some("defense"),
+            Field::SpecialAttack => Some("special-attack"),
+            Field::SpecialDefense => Some("special-defense"),
+            Field::Speed => Some("speed"),
+            _ => None,
+        }
+    }
+}
+
+/// Word sequences naming a field, longest first.
+const KEYWORDS: &[(&[&str], Field)] = &[
+    (&["base", "stat", "total"], Field::BaseStatTotal),
+    (&["base", "stats", "total"], Field::BaseStatTotal),
+    (&["total", "base", "stats"], Field::BaseStatTotal),
+    (&["stat", "total"], Field::BaseStatTotal),
+    (&["bst"], Field::BaseStatTotal),
+    (&["special", "attack"], Field::SpecialAttack),
+    (&["sp", "atk"], Field::SpecialAttack),
+    (&["sp", "attack"], Field::SpecialAttack),
+    (&["special", "defense"], Field::SpecialDefense),
+    (&["special", "defence"], Field::SpecialDefense),
+    (&["sp", "def"], Field::SpecialDefense),
+    (&["sp", "defense"], Field::SpecialDefense),
+    (&["capture", "rate"], Field::CaptureRate),
+    (&["catch", "rate"], Field::CaptureRate),
+    (&["hit", "points"], Field::Hp),
+    (&["hp"], Field::Hp),
+    (&["attack"], Field::Attack),
+    (&["atk"], Field::Attack),
+    (&["defense"], Field::Defense),
+    (&["defence"], Field::Defense),
+    (&["def"], Field::Defense),
+    (&["speed"], Field::Speed),
+    (&["type"], Field::Types),
+    (&["types"], Field::Types),
+    (&["typing"], Field::Types),
+    (&["ability"], Field::Abilities),
+    (&["abilities"], Field::Abilities),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Unit {
+    label: &'static str,
+    field: Field,
+    /// Size in metres or kilograms.
+    factor: f64,
+    decimals: usize,
+}
+
+const METRE: Unit = Unit {
+    label: "m",
+    field: Field::Height,
+    factor: 1.0,
+    decimals: 1,
+};
+const CENTIMETRE: Unit = Unit {
+    label: "cm",
+    field: Field::Height,
+    factor: 0.01,
+    decimals: 0,
+};
+const DECIMETRE: Unit = Unit {
+    label: "dm",
+    field: Field::Height,
+    factor: 0.1,
+    decimals: 0,
+};
+const KILOGRAM: Unit = Unit {
+    label: "kg",
+    field: Field::Weight,
+    factor: 1.0,
+    decimals: 1,
+};
+const HECTOGRAM: Unit = Unit {
+    label: "hg",
+    field: Field::Weight,
+    factor: 0.1,
+    decimals: 0,
+};
+const POUND: Unit = Unit {
+    label: "lbs",
+    field: Field::Weight,
+    factor: 0.453_592_37,
+    decimals: 1,
+};
+
+const UNITS: &[(&str, Unit)] = &[
+    ("m", METRE),
+    ("meter", METRE),
+    ("meters", METRE),
+    ("metre", METRE),
+    ("metres", METRE),
+    ("cm", CENTIMETRE),
+    ("centimeters", CENTIMETRE),
+    ("centimetres", CENTIMETRE),
+    ("dm", DECIMETRE),
+    ("decimeters", DECIMETRE),
+    ("decimetres", DECIMETRE),
+    ("kg", KILOGRAM),
+    ("kilogram", KILOGRAM),
+    ("kilograms", KILOGRAM),
+    ("hg", HECTOGRAM),
+    ("hectograms", HECTOGRAM),
+    ("lb", POUND),
+    ("lbs", POUND),
+    ("pound", POUND),
+    ("pounds", POUND),
+];
+
+/// Words that don't separate a number from its stat ("HP of 78").
+const FILLER: &[&str] = &[
+    "a", "an", "the", "of", "is", "was", "are", "has", "have", "with", "its", "their", "base",
+    "stat", "stats", "value", "sits", "stands", "boasts",
+];
+
+/// Words that make the number after them a bound rather than a value.
+const APPROXIMATE: &[&str] = &[
+    "over",
+    "above",
+    "below",
+    "under",
+    "than",
+    "around",
+    "about",
+    "nearly",
+    "roughly",
+    "approximately",
+    "almost",
+    "least",
+    "most",
+    "to",
+    "exceeds",
+    "beyond",
+];
+
+/// Words before a number that isn't a stat ("level 36").
+const NOT_STATS: &[&str] = &["level", "lv", "gen", "generation", "no", "number"];
+
+/// Words that end the stretch a number can be matched within.
+const BARRIERS: &[&str] = &[
+    "while", "whereas", "but", "unlike", "compared", "versus", "vs",
+];
+
+/// Capitalized words that don't name a different Pokémon. Any other
+/// capitalized word might (or a move or form), so the subject is dropped
+/// until a Pokémon in context is named again.
+const COMMON_WORDS: &[&str] = &[
+    "a",
+    "also",
+    "an",
+    "and",
+    "as",
+    "at",
+    "base",
+    "because",
+    "both",
+    "but",
+    "catch",
+    "capture",
+    "compared",
+    "each",
+    "for",
+    "generation",
+    "height",
+    "here",
+    "hidden",
+    "however",
+    "i",
+    "if",
+    "in",
+    "it",
+    "its",
+    "it's",
+    "like",
+    "no",
+    "note",
+    "on",
+    "overall",
+    "pokeapi",
+    "pokemon",
+    "pokéapi",
+    "pokémon",
+    "rate",
+    "so",
+    "special",
+    "stat",
+    "stats",
+    "that",
+    "the",
+    "their",
+    "these",
+    "they",
+    "this",
+    "those",
+    "total",
+    "unlike",
+    "weight",
+    "when",
+    "while",
+    "with",
+    "yes",
+    "you",
+    "your",
+];
+
+/// Facts about one Pokémon an answer is checked against.
+#[derive(Debug, Clone, Default)]
+pub struct Facts {
+    pub name: String,
+    /// Base stats by PokéAPI stat name.
+    pub stats: Vec<(String, i64)>,
+    /// In decimetres.
+    pub height: i64,
+    /// In hectograms.
+    pub weight: i64,
+    pub capture_rate: Option<i64>,
+    pub types: Vec<String>,
+    pub abilities: Vec<String>,
+}
+
+impl Facts {
+    pub fn new(pokemon: &Pokemon, species: Option<&PokemonSpecies>) -> Self {
+        Self {
+            name: pokemon.name.clone(),
+            stats: pokemon
+                .stats
+                .iter()
+                .map(|s| (s.stat.name.clone(), s.base_stat))
+                .collect(),
+            height: pokemon.height,
+            weight: pokemon.weight,
+            capture_rate: species.map(|s| s.capture_rate),
+            types: pokemon.types.iter().map(|t| t.type_.name.clone()).collect(),
+            abilities: pokemon
+                .abilities
+                .iter()
+                .map(|a| a.ability.name.clone())
+                .collect(),
+        }
+    }
+
+    fn stat(&self, field: Field) -> Option<i64> {
+        if field == Field::BaseStatTotal {
+            return Some(self.stats.iter().map(|(_, value)| value).sum());
+        }
+        if field == Field::CaptureRate {
+            return self.capture_rate;
+        }
+        let name = field.stat()?;
+        self.stats
+            .iter()
+            .find(|(stat, _)| stat == name)
+            .map(|(_, value)| *value)
+    }
+
+    fn has_ability(&self, stated: &str) -> bool {
+        let stated = squash(stated);
+        self.abilities.iter().any(|a| squash(a) == stated)
+    }
+
+    /// Whether `word` could be part of talking about this Pokémon.
+    fn knows_word(&self, word: &str) -> bool {
+        word == self.name
+            || self
+                .abilities
+                .iter()
+                .any(|a| a.split('-').any(|w| w == word))
+    }
+}
+
+/// A claim in an answer that contradicts the data.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Mismatch {
+    pub pokemon: String,
+    pub field: Field,
+    /// As the answer put it, e.g. `90` or `2 m`.
+    pub stated: String,
+    /// From the data, in the answer's units.
+    pub actual: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pokemon = title_case(&self.pokemon);
+        match self.field {
+            Field::Types => write!(
+                f,
+                "{} is {} type, not {}.",
+                pokemon, self.actual, self.stated
+            ),
+            Field::Abilities => write!(
+                f,
+                "{}'s abilities are {}; {} isn't one of them.",
+                pokemon, self.actual, self.stated
+            ),
+            field => write!(
+                f,
+                "{}'s {} is {}, not {}.",
+                pokemon,
+                field.label(),
+                self.actual,
+                self.stated
+            ),
+        }
+    }
+}
+
+/// Claims in `answer` about the Pokémon in `facts` that the data
+/// contradicts. Only English answers can be read.
+pub fn check(answer: &str, facts: &[Facts]) -> Vec<Mismatch> {
+    let tokens = tokenize(answer);
+    // Until a Pokémon is named, a lone Pokémon in context is the subject.
+    let mut subject = (facts.len() == 1).then_some(0);
+    let mut mismatches = Vec::new();
+    for sentence in tokens.split(|t| *t == Token::Break) {
+        let items = classify(sentence, facts, &mut subject);
+        check_numbers(&items, facts, &mut mismatches);
+        check_types(&items, facts, &mut mismatches);
+        check_abilities(&items, facts, &mut mismatches);
+    }
+    let mut unique: Vec<Mismatch> = Vec::with_capacity(mismatches.len());
+    for mismatch in mismatches {
+        if !unique.contains(&mismatch) {
+            unique.push(mismatch);
+        }
+    }
+    unique
+}
+
+/// A Markdown note listing `mismatches`, appended to the answer.
+pub fn note(mismatches: &[Mismatch]) -> String {
+    let mut note = String::from("\n\n**Data check:** PokéAPI disagrees with part of this answer:");
+    for mismatch in mismatches {
+        note.push_str(&format!("\n- {}", mismatch));
+    }
+    note
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token<'a> {
+    Word(&'a str),
+    Number(f64),
+    Symbol(char),
+    /// The end of a sentence, line or table row.
+    Break,
+}
+
+fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c.is_alphabetic() {
+            let mut end = start + c.len_utf8();
+            while let Some(&(i, next)) = chars.peek() {
+                let apostrophe = matches!(next, '\'' | '’')
+                    && text[i + next.len_utf8()..]
+                        .chars()
+                        .next()
+                        .is_some_and(char::is_alphabetic);
+                if !next.is_alphanumeric() && !apostrophe {
+                    break;
+                }
+                end = i + next.len_utf8();
+                chars.next();
+            }
+            tokens.push(Token::Word(&text[start..end]));
+        } else if c.is_ascii_digit() {
+            let mut end = start + 1;
+            while let Some(&(i, next)) = chars.peek() {
+                let decimal = next == '.'
+                    && text[i + 1..]
+                        .chars()
+                        .next()
+                        .is_some_and(|c| c.is_ascii_digit());
+                if !next.is_ascii_digit() && !decimal {
+                    break;
+                }
+                end = i + 1;
+                chars.next();
+            }
+            if let Ok(value) = text[start..end].parse() {
+                tokens.push(Token::Number(value));
+            }
+        } else if c == '.' {
+            // "Sp. Atk" is one stat, and "e.g." isn't a sentence end.
+            let abbreviation =
+                matches!(tokens.last(), Some(Token::Word(w)) if w.eq_ignore_ascii_case("sp"));
+            let ends = chars.peek().is_none_or(|&(_, next)| next.is_whitespace());
+            if ends && !abbreviation {
+                tokens.push(Token::Break);
+            }
+        } else if matches!(c, '\n' | '!' | '?' | ';') {
+            tokens.push(Token::Break);
+        } else if matches!(c, ',' | '/' | '%' | '#' | ':' | '(' | ')' | '×') {
+            tokens.push(Token::Symbol(c));
+        }
+    }
+    tokens
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Key(Field),
+    /// Covered by the keyword or number before it.
+    Skip,
+    Type(&'static str),
+    Number {
+        value: f64,
+        unit: Option<Unit>,
+        /// False for bounds, percentages, fractions and the like.
+        exact: bool,
+    },
+    Word,
+    Symbol(char),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Item<'a> {
+    kind: Kind,
+    /// The word as written; empty for numbers and symbols.
+    text: &'a str,
+    /// The Pokémon in `facts` this part of the sentence is about.
+    subject: Option<usize>,
+}
+
+impl Item<'_> {
+    /// Whether the item counts towards the distance between a number and
+    /// its stat.
+    fn significant(&self) -> bool {
+        match self.kind {
+            Kind::Skip | Kind::Symbol(_) => false,
+            Kind::Word => !self.is_word(FILLER),
+            _ => true,
+        }
+    }
+
+    fn is_word(&self, words: &[&str]) -> bool {
+        self.kind == Kind::Word && words.contains(&self.text.to_lowercase().as_str())
+    }
+
+    fn is_capitalized(&self) -> bool {
+        matches!(self.kind, Kind::Word | Kind::Key(_) | Kind::Type(_))
+            && self.text.starts_with(char::is_uppercase)
+    }
+}
+
+/// Tags the tokens of one sentence with their meaning and subject.
+/// `subject` carries the last Pokémon named into the next sentence.
+fn classify<'a>(
+    sentence: &[Token<'a>],
+    facts: &[Facts],
+    subject: &mut Option<usize>,
+) -> Vec<Item<'a>> {
+    let lower: Vec<Option<String>> = sentence
+        .iter()
+        .map(|t| match t {
+            Token::Word(w) => Some(possessive(&w.to_lowercase()).to_string()),
+            _ => None,
+        })
+        .collect();
+    let word_at = |i: usize| lower.get(i).and_then(Option::as_deref);
+
+    let mut local = *subject;
+    // Set right after an unfamiliar capitalized word, as in "Alolan Raichu".
+    let mut qualified = false;
+    let mut items: Vec<Item> = Vec::with_capacity(sentence.len());
+    let mut i = 0;
+    while i < sentence.len() {
+        let (mut kind, text) = match sentence[i] {
+            Token::Word(word) => (Kind::Word, word),
+            Token::Number(value) => (
+                Kind::Number {
+                    value,
+                    unit: None,
+                    exact: true,
+                },
+                "",
+            ),
+            Token::Symbol(c) => (Kind::Symbol(c), ""),
+            Token::Break => unreachable!("sentences are split at breaks"),
+        };
+        let mut width = 1;
+        let was_qualified = std::mem::take(&mut qualified);
+
+        if let Some(w) = word_at(i) {
+            let keyword = KEYWORDS
+                .iter()
+                .find(|(words, _)| (0..words.len()).all(|k| word_at(i + k) == Some(words[k])));
+            if let Some((words, field)) = keyword {
+                kind = Kind::Key(*field);
+                width = words.len();
+            } else if let Some(t) = TYPES.iter().find(|t| **t == w) {
+                kind = Kind::Type(t);
+            } else if let Some(k) = facts.iter().position(|f| f.name == w) {
+                // A regional form or Mega Evolution has its own data.
+                if !was_qualified {
+                    local = Some(k);
+                    *subject = Some(k);
+                }
+            } else if w.chars().any(|c| c.is_ascii_digit()) {
+                // Citation tags like S1.
+                kind = Kind::Skip;
+            } else if text.starts_with(char::is_uppercase)
+                && !COMMON_WORDS.contains(&w)
+                && !facts.iter().any(|f| f.knows_word(w))
+            {
+                local = None;
+                qualified = true;
+            }
+        }
+
+        if let Kind::Number { value, .. } = kind {
+            let before = items
+                .iter()
+                .rev()
+                .find(|item| item.significant() || matches!(item.kind, Kind::Symbol(_)));
+            let next = sentence.get(i + 1);
+            let unit = word_at(i + 1)
+                .and_then(|w| UNITS.iter().find(|(name, _)| *name == w))
+                .map(|(_, unit)| *unit);
+            let fraction = (matches!(next, Some(Token::Symbol('/')))
+                && matches!(sentence.get(i + 2), Some(Token::Number(_))))
+                || (i >= 2
+                    && sentence[i - 1] == Token::Symbol('/')
+                    && matches!(sentence[i - 2], Token::Number(_)));
+            let bound = before.is_some_and(|b| b.is_word(APPROXIMATE) || b.is_word(NOT_STATS));
+            let ratio =
+                matches!(next, Some(Token::Symbol('%' | '×'))) || word_at(i + 1) == Some("x");
+            let numbered = before.is_some_and(|b| b.kind == Kind::Symbol('#'));
+            kind = Kind::Number {
+                value,
+                unit,
+                exact: !(fraction || bound || ratio || numbered),
+            };
+            if unit.is_some() {
+                width = 2;
+            }
+        }
+
+        items.push(Item {
+            kind,
+            text,
+            subject: local,
+        });
+        for _ in 1..width {
+            items.push(Item {
+                kind: Kind::Skip,
+                text: "",
+                subject: local,
+            });
+        }
+        i += width;
+    }
+    items
+}
+
+/// Stats, capture rates, heights and weights.
+fn check_numbers(items: &[Item], facts: &[Facts], mismatches: &mut Vec<Mismatch>) {
+    let segments = items.split(|item| item.kind == Kind::Symbol(',') || item.is_word(BARRIERS));
+    for segment in segments {
+        let significant: Vec<&Item> = segment.iter().filter(|item| item.significant()).collect();
+        // Each stat keyword and the numbers closest to it.
+        let mut matches: Vec<(usize, Vec<usize>)> = Vec::new();
+        // By position, to list them in the answer's order.
+        let mut found: Vec<(usize, Mismatch)> = Vec::new();
+        for (n, item) in significant.iter().enumerate() {
+            let Kind::Number { value, unit, exact } = item.kind else {
+                continue;
+            };
+            let Some(subject) = item.subject.filter(|_| exact) else {
+                continue;
+            };
+            if let Some(unit) = unit {
+                if let Some(mismatch) = check_measure(&facts[subject], value, unit) {
+                    found.push((n, mismatch));
+                }
+                continue;
+            }
+
+            let nearby: Vec<(usize, usize, Field)> = significant
+                .iter()
+                .enumerate()
+                .filter_map(|(k, key)| match key.kind {
+                    Kind::Key(field) if field != Field::Types && field != Field::Abilities => {
+                        Some((n.abs_diff(k), k, field))
+                    }
+                    _ => None,
+                })
+                .filter(|(distance, _, _)| *distance <= MAX_DISTANCE)
+                .collect();
+            let Some(closest) = nearby.iter().map(|(d, _, _)| *d).min() else {
+                continue;
+            };
+            let candidates: Vec<&(usize, usize, Field)> =
+                nearby.iter().filter(|(d, _, _)| *d == closest).collect();
+            if candidates.windows(2).any(|pair| pair[0].2 != pair[1].2) {
+                continue;
+            }
+            let key = candidates[0].1;
+            match matches.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, numbers)) => numbers.push(n),
+                None => matches.push((key, vec![n])),
+            }
+        }
+
+        for (key, numbers) in matches {
+            // Several numbers on one stat is a table or comparison.
+            let [n] = numbers[..] else {
+                continue;
+            };
+            let (Kind::Key(field), Kind::Number { value, .. }, Some(subject)) = (
+                significant[key].kind,
+                significant[n].kind,
+                significant[n].subject,
+            ) else {
+                continue;
+            };
+            let facts = &facts[subject];
+            let Some(actual) = facts.stat(field) else {
+                continue;
+            };
+            if value.fract() == 0.0 && value as i64 != actual {
+                let mismatch = Mismatch {
+                    pokemon: facts.name.clone(),
+                    field,
+                    stated: value.to_string(),
+                    actual: actual.to_string(),
+                };
+                found.push((n, mismatch));
+            }
+        }
+        found.sort_by_key(|(n, _)| *n);
+        mismatches.extend(found.into_iter().map(|(_, mismatch)| mismatch));
+    }
+}
+
+/// Compares in metres or kilograms, allowing for rounding.
+fn check_measure(facts: &Facts, value: f64, unit: Unit) -> Option<Mismatch> {
+    let actual = match unit.field {
+        Field::Height => facts.height as f64 / 10.0,
+        _ => facts.weight as f64 / 10.0,
+    };
+    let stated = value * unit.factor;
+    if (stated - actual).abs() <= actual * 0.03 + 0.051 {
+        return None;
+    }
+    Some(Mismatch {
+        pokemon: facts.name.clone(),
+        field: unit.field,
+        stated: format!("{} {}", value, unit.label),
+        actual: format!("{:.*} {}", unit.decimals, actual / unit.factor, unit.label),
+    })
+}
+
+/// "is a Fire/Flying type" and "its types are Fire and Flying".
+fn check_types(items: &[Item], facts: &[Facts], mismatches: &mut Vec<Mismatch>) {
+    /// Head nouns of clauses about other types, as in "its weaknesses are
+    /// Rock and Water types".
+    const OTHER_TYPES: &[&str] = &[
+        "weakness",
+        "weaknesses",
+        "weak",
+        "resistance",
+        "resistances",
+        "resistant",
+        "immunity",
+        "immunities",
+        "counter",
+        "counters",
+        "check",
+        "checks",
+        "move",
+        "moves",
+        "attack",
+        "attacks",
+        "effective",
+    ];
+    let words: Vec<&Item> = items
+        .iter()
+        .filter(|item| item.kind != Kind::Skip)
+        .collect();
+    for start in 0..words.len() {
+        let linked = |i: usize| {
+            words
+                .get(i)
+                .is_some_and(|w| w.is_word(&["is", "are"]) || w.kind == Kind::Symbol(':'))
+        };
+        let before = start.checked_sub(1).map(|b| words[b]);
+        // "Charizard is" or "it is": the Pokémon itself is the subject.
+        let about_itself = before.is_some_and(|w| {
+            w.is_word(&["it"]) || facts.iter().any(|f| w.is_word(&[f.name.as_str()]))
+        });
+        // After "is", only "Fire type" or "Fire Pokémon" names its typing.
+        let (mut i, needs_suffix) = if words[start].is_word(&["is", "are"]) && about_itself {
+            (start + 1, true)
+        } else if words[start].kind == Kind::Key(Field::Types)
+            && linked(start + 1)
+            && !before.is_some_and(|w| w.is_word(OTHER_TYPES))
+        {
+            (start + 2, false)
+        } else {
+            continue;
+        };
+        if needs_suffix {
+            while words
+                .get(i)
+                .is_some_and(|w| w.is_word(&["a", "an", "pure", "dual"]))
+            {
+                i += 1;
+            }
+        }
+
+        let mut stated: Vec<&'static str> = Vec::new();
+        let mut subject = None;
+        while let Some(Kind::Type(t)) = words.get(i).map(|w| w.kind) {
+            subject = subject.or(words[i].subject);
+            stated.push(t);
+            i += 1;
+            if words
+                .get(i)
+                .is_some_and(|w| w.kind == Kind::Symbol('/') || w.is_word(&["and"]))
+            {
+                i += 1;
+            }
+        }
+        let suffixed = words.get(i).is_some_and(|w| {
+            w.kind == Kind::Key(Field::Types) || w.is_word(&["pokémon", "pokemon"])
+        });
+        if stated.is_empty() || (needs_suffix && !suffixed) {
+            continue;
+        }
+        let Some(facts) = subject.map(|s| &facts[s]) else {
+            continue;
+        };
+        if stated
+            .iter()
+            .any(|t| !facts.types.iter().any(|actual| actual == t))
+        {
+            let stated: Vec<String> = stated.iter().map(|t| title_case(t)).collect();
+            let actual: Vec<String> = facts.types.iter().map(|t| title_case(t)).collect();
+            mismatches.push(Mismatch {
+                pokemon: facts.name.clone(),
+                field: Field::Types,
+                stated: stated.join("/"),
+                actual: actual.join("/"),
+            });
+        }
+    }
+}
+
+/// "its abilities are Blaze and Solar Power", "has the ability Blaze".
+fn check_abilities(items: &[Item], facts: &[Facts], mismatches: &mut Vec<Mismatch>) {
+    const LEAD_IN: &[&str] = &[
+        "is",
+        "are",
+        "include",
+        "includes",
+        "including",
+        "like",
+        "such",
+        "as",
+        "the",
+        "of",
+        "called",
+        "named",
+        "its",
+        "hidden",
+        "possible",
+        "both",
+        "either",
+    ];
+    let words: Vec<&Item> = items
+        .iter()
+        .filter(|item| item.kind != Kind::Skip)
+        .collect();
+    for (start, word) in words.iter().enumerate() {
+        if word.kind != Kind::Key(Field::Abilities) {
+            continue;
+        }
+        let Some(facts) = word.subject.map(|s| &facts[s]) else {
+            continue;
+        };
+        let mut i = start + 1;
+        while words
+            .get(i)
+            .is_some_and(|w| w.is_word(LEAD_IN) || w.kind == Kind::Symbol(':'))
+        {
+            i += 1;
+        }
+
+        loop {
+            // An ability name is one to three capitalized words.
+            let mut name: Vec<&str> = Vec::new();
+            while name.len() < 3 && words.get(i).is_some_and(|w| w.is_capitalized()) {
+                name.push(words[i].text);
+                i += 1;
+            }
+            if name.is_empty() {
+                break;
+            }
+            let stated = name.join(" ");
+            if !facts.has_ability(&stated) {
+                let actual: Vec<String> = facts.abilities.iter().map(|a| title_case(a)).collect();
+                mismatches.push(Mismatch {
+                    pokemon: facts.name.clone(),
+                    field: Field::Abilities,
+                    stated,
+                    actual: actual.join(", "),
+                });
+            }
+
+            // Skip a "(hidden)" note, then continue only through a list.
+            if words.get(i).is_some_and(|w| w.kind == Kind::Symbol('(')) {
+                while words.get(i).is_some_and(|w| w.kind != Kind::Symbol(')')) {
+                    i += 1;
+                }
+                i += 1;
+            }
+            let separated = |i: usize| {
+                words.get(i).is_some_and(|w| {
+                    matches!(w.kind, Kind::Symbol(',' | '/')) || w.is_word(&["and", "or"])
+                })
+            };
+            if !separated(i) {
+                break;
+            }
+            while separated(i) {
+                i += 1;
+            }
+        }
+    }
+}
+
+/// `charizard's` as `charizard`.
+fn possessive(word: &str) -> &str {
+    word.strip_suffix("'s")
+        .or_else(|| word.strip_suffix("’s"))
+        .unwrap_or(word)
+}
+
+/// Lowercase letters and digits only, so `Solar Power` matches `solar-power`.
+fn squash(name: &str) -> String {
+    name.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check, Facts, Field};
+
+    fn charizard() -> Facts {
+        let stats = [
+            ("hp", 78),
+            ("attack", 84),
+            ("defense", 78),
+            ("special-attack", 109),
+            ("special-defense", 85),
+            ("speed", 100),
+        ];
+        Facts {
+            name: "charizard".to_string(),
+            stats: stats.iter().map(|(s, v)| (s.to_string(), *v)).collect(),
+            height: 17,
+            weight: 905,
+            capture_rate: Some(45),
+            types: vec!["fire".to_string(), "flying".to_string()],
+            abilities: vec!["blaze".to_string(), "solar-power".to_string()],
+        }
+    }
+
+    #[test]
+    fn flags_only_contradicted_claims() {
+        let facts = [charizard()];
+        let correct = "Charizard is a Fire/Flying-type Pokémon [S1]. It has 78 HP, 84 Attack, \
+                       109 Sp. Atk and a base Speed of 100. Its abilities are Blaze and Solar \
+                       Power (hidden). It stands 1.7 m tall, weighs 199.5 lbs, and has a capture \
+                       rate of 45. Blaze boosts Fire moves when HP drops below 1/3. \
+                       Unlike Blastoise, which has 79 HP, it is weak to Rock-type moves.";
+        assert_eq!(check(correct, &facts), []);
+
+        // Other types named in a sentence about Charizard aren't its typing.
+        for other in [
+            "Charizard is strong. Its weaknesses are Rock and Water types.",
+            "Against Charizard, the best counters are Rock and Water types.",
+            "Charizard's weakness types are Rock and Water.",
+            "Its strongest moves are Fire and Dragon types.",
+        ] {
+            assert_eq!(check(other, &facts), [], "{}", other);
+        }
+
+        let wrong = "Charizard is a Fire/Dragon type with 90 Attack and a height of 2 m. \
+                     Its abilities are Blaze and Drought.";
+        let found: Vec<(Field, String)> = check(wrong, &facts)
+            .into_iter()
+            .map(|m| (m.field, m.to_string()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (
+                    Field::Attack,
+                    "Charizard's base Attack is 84, not 90.".to_string()
+                ),
+                (
+                    Field::Height,
+                    "Charizard's height is 1.7 m, not 2 m.".to_string()
+                ),
+                (
+                    Field::Types,
+                    "Charizard is Fire/Flying type, not Fire/Dragon.".to_string()
+                ),
+                (
+                    Field::Abilities,
+                    "Charizard's abilities are Blaze, Solar Power; Drought isn't one of them."
+                        .to_string()
+                ),
+            ]
+        );
+    }
+}
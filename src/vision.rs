@@ -0,0 +1,230 @@
+//! Structured replies to the image prompt in [`crate::llm::vision_prompt`].
+
+use crate::pokeapi::PokeApiClient;
+use crate::rag::parse_json_reply;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+
+/// Below this the CLI shows the other candidates next to the best guess.
+pub const LOW_CONFIDENCE: f32 = 0.6;
+
+/// Most Pokémon reported for one image; a full double-battle preview has 12.
+pub const MAX_DETECTIONS: usize = 12;
+
+/// What the model made of an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Verdict {
+    Pokemon,
+    NotPokemon,
+    /// The reply could not be understood.
+    Unknown,
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Verdict::Pokemon => "pokemon",
+            Verdict::NotPokemon => "not_pokemon",
+            Verdict::Unknown => "unknown",
+        })
+    }
+}
+
+/// A Pokémon the model thinks the image may show.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Candidate {
+    /// PokéAPI name.
+    pub name: String,
+    pub confidence: Option<f32>,
+}
+
+/// One Pokémon found in an image.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct Detection {
+    /// PokéAPI name of the most likely Pokémon; `None` when the model
+    /// couldn't name it, or none of its names matched.
+    pub pokemon: Option<String>,
+    pub confidence: Option<f32>,
+    /// Where in the image it is, as described by the model (e.g. `left`,
+    /// `opponent side`).
+    pub region: Option<String>,
+    /// Possible Pokémon, most likely first.
+    pub candidates: Vec<Candidate>,
+    /// Names the model suggested that PokéAPI doesn't know.
+    pub unmatched: Vec<String>,
+}
+
+impl Detection {
+    /// Reads one detection; `json` carries `name`, `confidence`, `region`
+    /// and `candidates`. The top-level name is always a candidate, and
+    /// candidates are ordered by confidence.
+    fn from_json(json: &Value) -> Self {
+        let top = text(json, "name").map(|name| Candidate {
+            name,
+            confidence: confidence(json),
+        });
+        let listed = json
+            .get("candidates")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|c| {
+                text(c, "name").map(|name| Candidate {
+                    name,
+                    confidence: confidence(c),
+                })
+            });
+
+        let mut candidates: Vec<Candidate> = Vec::new();
+        for mut candidate in top.into_iter().chain(listed) {
+            candidate.name = PokeApiClient::normalize_pokemon_name(&candidate.name);
+            if !candidate.name.is_empty() && !candidates.iter().any(|c| c.name == candidate.name) {
+                candidates.push(candidate);
+            }
+        }
+        // Stable, so the model's own order breaks ties.
+        candidates.sort_by(|a, b| {
+            b.confidence
+                .unwrap_or(0.0)
+                .total_cmp(&a.confidence.unwrap_or(0.0))
+        });
+
+        Self {
+            pokemon: candidates.first().map(|c| c.name.clone()),
+            confidence: confidence(json),
+            region: text(json, "region"),
+            candidates,
+            unmatched: Vec::new(),
+        }
+    }
+
+    /// Whether the best guess is shaky enough to show the alternatives.
+    pub fn is_low_confidence(&self) -> bool {
+        let best = self.candidates.first().and_then(|c| c.confidence);
+        best.or(self.confidence).is_some_and(|c| c < LOW_CONFIDENCE)
+    }
+}
+
+/// The model's structured reply about an image.
+///
+/// [`crate::RAGEngine::identify`] checks every detection's candidates
+/// against PokéAPI: names it doesn't know move to `unmatched`, and
+/// `pokemon` becomes the best remaining candidate.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Identification {
+    pub verdict: Verdict,
+    /// The model's overall confidence from 0 to 1, if it gave one.
+    pub confidence: Option<f32>,
+    pub reason: Option<String>,
+    /// Each Pokémon seen, in the model's order. Empty unless the verdict is
+    /// [`Verdict::Pokemon`].
+    pub detections: Vec<Detection>,
+}
+
+impl Identification {
+    /// Reads the strict-JSON image reply. Replies with a top-level `name`
+    /// instead of `detections` count as a single detection.
+    pub(crate) fn from_reply(raw: &str) -> Self {
+        let json = parse_json_reply(raw).unwrap_or(Value::Null);
+        let verdict = match json.get("type").and_then(Value::as_str) {
+            Some("pokemon") => Verdict::Pokemon,
+            Some("not_pokemon") => Verdict::NotPokemon,
+            _ => Verdict::Unknown,
+        };
+
+        let detections = match (verdict, json.get("detections").and_then(Value::as_array)) {
+            (Verdict::Pokemon, Some(list)) => list.iter().map(Detection::from_json).collect(),
+            (Verdict::Pokemon, None) if json.get("name").is_some() => {
+                vec![Detection::from_json(&json)]
+            }
+            _ => Vec::new(),
+        };
+
+        Self {
+            verdict,
+            confidence: confidence(&json),
+            reason: text(&json, "reason"),
+            detections,
+        }
+    }
+
+    /// Names of the identified Pokémon, in detection order.
+    pub fn pokemon(&self) -> Vec<&str> {
+        self.detections
+            .iter()
+            .filter_map(|d| d.pokemon.as_deref())
+            .collect()
+    }
+}
+
+fn text(json: &Value, key: &str) -> Option<String> {
+    json.get(key)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+fn confidence(json: &Value) -> Option<f32> {
+    json.get("confidence")
+        .and_then(Value::as_f64)
+        .map(|c| c.clamp(0.0, 1.0) as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Identification, Verdict};
+
+    #[test]
+    fn reads_image_replies() {
+        let found = Identification::from_reply(
+            "```json\n{\"type\":\"pokemon\",\"name\":\"Mr. Mime\",\"confidence\":0.92}\n```",
+        );
+        assert_eq!(found.verdict, Verdict::Pokemon);
+        assert_eq!(found.pokemon(), ["mr-mime"]);
+        assert_eq!(found.confidence, Some(0.92));
+        assert!(!found.detections[0].is_low_confidence());
+
+        let rejected =
+            Identification::from_reply("{\"type\":\"not_pokemon\",\"reason\":\"a cat\"}");
+        assert_eq!(rejected.verdict, Verdict::NotPokemon);
+        assert_eq!(rejected.reason.as_deref(), Some("a cat"));
+
+        assert_eq!(
+            Identification::from_reply("I think it's a cat").verdict,
+            Verdict::Unknown
+        );
+    }
+
+    #[test]
+    fn orders_and_dedupes_candidates() {
+        let found = Identification::from_reply(
+            r#"{"type":"pokemon","name":"Pichu","confidence":0.4,
+                "candidates":[{"name":"pichu","confidence":0.4},
+                              {"name":"Pikachu","confidence":0.5},
+                              {"name":"Raichu","confidence":0.1}]}"#,
+        );
+        let detection = &found.detections[0];
+        let names: Vec<&str> = detection
+            .candidates
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(names, ["pikachu", "pichu", "raichu"]);
+        assert_eq!(detection.pokemon.as_deref(), Some("pikachu"));
+        assert!(detection.is_low_confidence());
+    }
+
+    #[test]
+    fn reads_several_detections() {
+        let found = Identification::from_reply(
+            r#"{"type":"pokemon","detections":[
+                {"name":"Charizard","confidence":0.9,"region":"left"},
+                {"name":"Blastoise","confidence":0.8,"region":"right"}]}"#,
+        );
+        assert_eq!(found.pokemon(), ["charizard", "blastoise"]);
+        assert_eq!(found.detections[1].region.as_deref(), Some("right"));
+    }
+}